@@ -6,7 +6,7 @@ Flask API that generates music from text prompts
 from flask import Flask, request, jsonify, send_file
 from flask_cors import CORS
 import torch
-from transformers import AutoProcessor, MusicgenForConditionalGeneration, BlipProcessor, BlipForConditionalGeneration
+from transformers import AutoProcessor, MusicgenForConditionalGeneration, MusicgenMelodyForConditionalGeneration, BlipProcessor, BlipForConditionalGeneration
 from PIL import Image
 import scipy.io.wavfile as wavfile
 import numpy as np
@@ -39,26 +39,55 @@ processor = None
 model = None
 device = None
 
+# Models other than the default one, loaded on first use and keyed by size
+DEFAULT_MODEL_SIZE = "medium"
+MODEL_SIZES = ("small", "medium", "large")
+extra_models = {}
+
+# Melody-conditioned model, loaded on first use
+melody_processor = None
+melody_model = None
+
 # BLIP Image Captioning model
 blip_processor = None
 blip_model = None
 
-def enhance_music_prompt(user_prompt, with_vocals=False):
+# Sampling settings used when a request does not override them
+DEFAULT_SAMPLING = {
+    "guidance_scale": 3.5,  # Higher guidance for better quality
+    "temperature": 0.9,  # Slightly lower for more coherent output
+    "top_k": 250,  # Add top-k sampling
+    "top_p": 0.95  # Add nucleus sampling
+}
+
+def enhance_music_prompt(user_prompt, with_vocals=False, controls=None):
     """
     Enhance user prompt with more detailed music generation instructions
     """
+    controls = controls or {}
+
     # Music quality descriptors
     quality_terms = "high quality, clear sound, professional production"
 
     # Add genre-specific enhancements
     enhanced = user_prompt.strip()
 
+    if controls.get('genre'):
+        enhanced = f"{controls['genre']} track, {enhanced}"
+
     # Add instrumentation details if not specified
-    if "instrument" not in enhanced.lower():
+    if controls.get('instrumentation'):
+        enhanced += ", featuring " + ", ".join(controls['instrumentation'])
+    elif "instrument" not in enhanced.lower():
         enhanced += ", rich instrumentation"
 
+    if controls.get('key'):
+        enhanced += f", in the key of {controls['key']}"
+
     # Add tempo/energy if not specified
-    if not any(word in enhanced.lower() for word in ["fast", "slow", "upbeat", "calm", "energetic", "relaxed"]):
+    if controls.get('bpm'):
+        enhanced += f", {controls['bpm']} bpm"
+    elif not any(word in enhanced.lower() for word in ["fast", "slow", "upbeat", "calm", "energetic", "relaxed"]):
         enhanced += ", dynamic tempo"
 
     # Add production quality
@@ -101,6 +130,78 @@ def initialize_model():
         logger.error(f"Failed to initialize model: {str(e)}")
         return False
 
+def get_model(size):
+    """Return the (processor, model) pair for a model size, loading it on first use"""
+    if not size or size == DEFAULT_MODEL_SIZE:
+        return processor, model
+
+    if size not in MODEL_SIZES:
+        raise ValueError(f"Unknown model size: {size}")
+
+    if size not in extra_models:
+        model_name = f"facebook/musicgen-{size}"
+        logger.info(f"Loading model: {model_name}")
+        extra_processor = AutoProcessor.from_pretrained(model_name)
+        extra_model = MusicgenForConditionalGeneration.from_pretrained(model_name).to(device)
+        extra_models[size] = (extra_processor, extra_model)
+
+    return extra_models[size]
+
+def sampling_options(data):
+    """Merge request sampling overrides over the defaults"""
+    options = dict(DEFAULT_SAMPLING)
+    for name in options:
+        if data.get(name) is not None:
+            options[name] = data[name]
+    return options
+
+def get_melody_model():
+    """Return the musicgen-melody (processor, model) pair, loading it on first use"""
+    global melody_processor, melody_model
+
+    if melody_model is None:
+        model_name = "facebook/musicgen-melody"
+        logger.info(f"Loading model: {model_name}")
+        melody_processor = AutoProcessor.from_pretrained(model_name)
+        melody_model = MusicgenMelodyForConditionalGeneration.from_pretrained(model_name).to(device)
+
+    return melody_processor, melody_model
+
+def decode_wav_base64(data):
+    """Decode a base64 WAV into (sample_rate, mono float32 samples)"""
+    sample_rate, samples = wavfile.read(io.BytesIO(base64.b64decode(data)))
+
+    if samples.dtype == np.int16:
+        samples = samples.astype(np.float32) / 32768.0
+    else:
+        samples = samples.astype(np.float32)
+
+    if samples.ndim == 2:
+        samples = samples.mean(axis=1)
+
+    return sample_rate, samples
+
+def save_generated_audio(audio_values, sample_rate):
+    """Normalize generated audio, write it as 16-bit WAV and return its file id"""
+    audio_array = audio_values[0].cpu().numpy()
+
+    # If audio is 2D (channels, samples), transpose to (samples, channels)
+    if len(audio_array.shape) == 2:
+        audio_array = audio_array.T
+
+    max_val = np.abs(audio_array).max()
+    if max_val > 1e-8:
+        audio_array = audio_array / max_val
+    else:
+        audio_array = audio_array * 0.1
+
+    audio_array = np.clip(audio_array, -1.0, 1.0)
+    audio_array = np.round(audio_array * 32767.0).astype(np.int16)
+
+    file_id = str(uuid.uuid4())
+    wavfile.write(os.path.join(OUTPUT_DIR, f"{file_id}.wav"), sample_rate, audio_array)
+    return file_id
+
 def initialize_blip_model():
     """Initialize BLIP image captioning model"""
     global blip_processor, blip_model, device
@@ -127,10 +228,20 @@ def initialize_blip_model():
 
 @app.route('/health', methods=['GET'])
 def health_check():
-    """Health check endpoint"""
+    """Health check endpoint, listing every model currently in memory"""
+    models = []
+    if model is not None:
+        models.append(f"facebook/musicgen-{DEFAULT_MODEL_SIZE}")
+    models.extend(f"facebook/musicgen-{size}" for size in extra_models)
+    if melody_model is not None:
+        models.append("facebook/musicgen-melody")
+    if blip_model is not None:
+        models.append("Salesforce/blip-image-captioning-base")
+
     return jsonify({
-        "status": "ok",
+        "status": "ok" if model is not None else "loading",
         "model_loaded": model is not None,
+        "models": models,
         "device": str(device) if device else "unknown"
     })
 
@@ -144,7 +255,11 @@ def generate_music():
         "prompt": "upbeat electronic dance music",
         "duration": 10,  # seconds (optional, default: 10, max: 30)
         "with_vocals": false,  # include vocals (optional, default: false)
-        "return_url": true  # return file path instead of audio data
+        "bpm": 120, "key": "A minor", "genre": "house",  # optional musical controls
+        "instrumentation": ["piano", "synth bass"],  # optional
+        "seed": 42,  # optional, makes generation reproducible
+        "temperature": 0.9, "top_k": 250, "top_p": 0.95, "guidance_scale": 3.5,  # optional
+        "model_size": "medium"  # small, medium or large (optional)
     }
 
     Response JSON:
@@ -167,7 +282,9 @@ def generate_music():
         user_prompt = data.get('prompt', '')
         duration = min(data.get('duration', 10), 30)  # Default 10 seconds, max 30
         with_vocals = data.get('with_vocals', False)
-        return_url = data.get('return_url', True)
+        seed = data.get('seed')
+        sampling = sampling_options(data)
+        size_processor, size_model = get_model(data.get('model_size'))
 
         if not user_prompt:
             return jsonify({
@@ -176,12 +293,12 @@ def generate_music():
             }), 400
 
         # Enhance the prompt for better quality
-        prompt = enhance_music_prompt(user_prompt, with_vocals)
+        prompt = enhance_music_prompt(user_prompt, with_vocals, data)
 
         logger.info(f"Generating music for prompt: '{prompt}' (duration: {duration}s)")
 
         # Prepare inputs
-        inputs = processor(
+        inputs = size_processor(
             text=[prompt],
             padding=True,
             return_tensors="pt",
@@ -194,15 +311,14 @@ def generate_music():
 
         # Generate audio with quality-optimized settings
         logger.info("Generating audio...")
+        if seed is not None:
+            torch.manual_seed(seed)
         with torch.no_grad():
-            audio_values = model.generate(
+            audio_values = size_model.generate(
                 **inputs,
                 max_new_tokens=max_new_tokens,
                 do_sample=True,
-                guidance_scale=3.5,  # Higher guidance for better quality
-                temperature=0.9,  # Slightly lower for more coherent output
-                top_k=250,  # Add top-k sampling
-                top_p=0.95  # Add nucleus sampling
+                **sampling
             )
 
         # Convert to numpy array
@@ -216,7 +332,7 @@ def generate_music():
         file_path = os.path.join(OUTPUT_DIR, f"{file_id}.wav")
 
         # MusicGen outputs at 32kHz sample rate
-        sample_rate = model.config.audio_encoder.sampling_rate
+        sample_rate = size_model.config.audio_encoder.sampling_rate
 
         # If audio is 2D (channels, samples), transpose to (samples, channels)
         if len(audio_array.shape) == 2:
@@ -287,6 +403,36 @@ def download_music(file_id):
             "error": str(e)
         }), 500
 
+@app.route('/files/<file_id>', methods=['DELETE'])
+def delete_music(file_id):
+    """Delete a generated music file"""
+    try:
+        # Only accept UUIDs so the id can't point outside OUTPUT_DIR
+        file_id = str(uuid.UUID(file_id))
+    except ValueError:
+        return jsonify({
+            "success": False,
+            "error": "Invalid file id"
+        }), 400
+
+    file_path = os.path.join(OUTPUT_DIR, f"{file_id}.wav")
+
+    if not os.path.exists(file_path):
+        return jsonify({
+            "success": False,
+            "error": "File not found"
+        }), 404
+
+    try:
+        os.remove(file_path)
+        return jsonify({"success": True, "file_id": file_id})
+    except Exception as e:
+        logger.error(f"Error deleting file: {str(e)}")
+        return jsonify({
+            "success": False,
+            "error": str(e)
+        }), 500
+
 @app.route('/batch-generate', methods=['POST'])
 def batch_generate():
     """
@@ -300,16 +446,20 @@ def batch_generate():
         ],
         "duration": 10,
         "with_vocals": false
+        # plus the same optional controls as /generate, applied to every prompt
     }
 
     Response JSON:
     {
         "success": true,
         "songs": [
-            {"title": "Song 1", "file_id": "uuid1", "file_path": "/download/uuid1"},
-            {"title": "Song 2", "file_id": "uuid2", "file_path": "/download/uuid2"}
+            {"index": 0, "title": "Song 1", "file_id": "uuid1", "file_path": "/download/uuid1"}
+        ],
+        "failures": [
+            {"index": 1, "title": "Song 2", "prompt": "sad piano melody", "error": "..."}
         ]
     }
+    success is true when at least one song was generated
     """
     try:
         if model is None:
@@ -322,6 +472,9 @@ def batch_generate():
         prompts = data.get('prompts', [])
         duration = min(data.get('duration', 10), 30)  # Max 30 seconds
         with_vocals = data.get('with_vocals', False)
+        seed = data.get('seed')
+        sampling = sampling_options(data)
+        size_processor, size_model = get_model(data.get('model_size'))
 
         if not prompts:
             return jsonify({
@@ -330,81 +483,249 @@ def batch_generate():
             }), 400
 
         results = []
+        failures = []
 
-        for item in prompts:
+        for index, item in enumerate(prompts):
             title = item.get('title', 'Untitled')
             user_prompt = item.get('prompt', '')
 
             if not user_prompt:
                 continue
 
-            # Enhance the prompt for better quality
-            prompt = enhance_music_prompt(user_prompt, with_vocals)
+            try:
+                # Enhance the prompt for better quality
+                prompt = enhance_music_prompt(user_prompt, with_vocals, data)
 
-            logger.info(f"Generating '{title}': {prompt}")
+                logger.info(f"Generating '{title}': {prompt}")
 
-            # Generate music using the same logic as single generation
-            inputs = processor(
-                text=[prompt],
-                padding=True,
-                return_tensors="pt",
-            )
-            inputs = inputs.to(device)
-
-            max_new_tokens = int(duration * 50)
-
-            with torch.no_grad():
-                audio_values = model.generate(
-                    **inputs,
-                    max_new_tokens=max_new_tokens,
-                    do_sample=True,
-                    guidance_scale=3.5,  # Higher guidance for better quality
-                    temperature=0.9,  # Slightly lower for more coherent output
-                    top_k=250,  # Add top-k sampling
-                    top_p=0.95  # Add nucleus sampling
+                # Generate music using the same logic as single generation
+                inputs = size_processor(
+                    text=[prompt],
+                    padding=True,
+                    return_tensors="pt",
                 )
+                inputs = inputs.to(device)
+
+                max_new_tokens = int(duration * 50)
+
+                # Offset the seed per song so tracks differ but stay reproducible
+                if seed is not None:
+                    torch.manual_seed(seed + index)
+                with torch.no_grad():
+                    audio_values = size_model.generate(
+                        **inputs,
+                        max_new_tokens=max_new_tokens,
+                        do_sample=True,
+                        **sampling
+                    )
+
+                audio_array = audio_values[0].cpu().numpy()
+
+                file_id = str(uuid.uuid4())
+                file_path = os.path.join(OUTPUT_DIR, f"{file_id}.wav")
+
+                sample_rate = size_model.config.audio_encoder.sampling_rate
+
+                # If audio is 2D (channels, samples), transpose to (samples, channels)
+                if len(audio_array.shape) == 2:
+                    audio_array = audio_array.T
+
+                # Normalize audio safely
+                max_val = np.abs(audio_array).max()
+                if max_val > 1e-8:  # Avoid division by very small numbers
+                    audio_array = audio_array / max_val
+                else:
+                    audio_array = audio_array * 0.1  # Small default amplitude
+
+                # Clamp values between -1 and 1
+                audio_array = np.clip(audio_array, -1.0, 1.0)
+
+                # Convert to int16 with proper rounding
+                audio_array = np.round(audio_array * 32767.0).astype(np.int16)
+
+                wavfile.write(file_path, sample_rate, audio_array)
+
+                results.append({
+                    "index": index,
+                    "title": title,
+                    "file_id": file_id,
+                    "file_path": f"/download/{file_id}",
+                    "prompt": prompt
+                })
+            except Exception as e:
+                # Keep going so one bad prompt does not sink the whole batch
+                logger.error(f"Error generating '{title}': {str(e)}")
+                failures.append({
+                    "index": index,
+                    "title": title,
+                    "prompt": user_prompt,
+                    "error": str(e)
+                })
 
-            audio_array = audio_values[0].cpu().numpy()
+        return jsonify({
+            "success": len(results) > 0,
+            "songs": results,
+            "count": len(results),
+            "failures": failures
+        }), 200 if results else 500
 
-            file_id = str(uuid.uuid4())
-            file_path = os.path.join(OUTPUT_DIR, f"{file_id}.wav")
+    except Exception as e:
+        logger.error(f"Error in batch generation: {str(e)}")
+        return jsonify({
+            "success": False,
+            "error": str(e)
+        }), 500
 
-            sample_rate = model.config.audio_encoder.sampling_rate
+@app.route('/generate-melody', methods=['POST'])
+def generate_melody():
+    """
+    Generate music from a text prompt that follows the melody of an input clip
 
-            # If audio is 2D (channels, samples), transpose to (samples, channels)
-            if len(audio_array.shape) == 2:
-                audio_array = audio_array.T
+    Request JSON:
+    {
+        "prompt": "orchestral arrangement",
+        "melody": "base64 mono 32kHz WAV",
+        "duration": 10,
+        # plus the same optional controls as /generate (model_size is ignored)
+    }
+    """
+    try:
+        if model is None:
+            return jsonify({
+                "success": False,
+                "error": "Model not initialized"
+            }), 500
 
-            # Normalize audio safely
-            max_val = np.abs(audio_array).max()
-            if max_val > 1e-8:  # Avoid division by very small numbers
-                audio_array = audio_array / max_val
-            else:
-                audio_array = audio_array * 0.1  # Small default amplitude
+        data = request.get_json()
+        user_prompt = data.get('prompt', '')
+        duration = min(data.get('duration', 10), 30)
 
-            # Clamp values between -1 and 1
-            audio_array = np.clip(audio_array, -1.0, 1.0)
+        if not user_prompt or not data.get('melody'):
+            return jsonify({
+                "success": False,
+                "error": "Prompt and melody are required"
+            }), 400
 
-            # Convert to int16 with proper rounding
-            audio_array = np.round(audio_array * 32767.0).astype(np.int16)
+        melody_rate, melody = decode_wav_base64(data['melody'])
+        m_processor, m_model = get_melody_model()
 
-            wavfile.write(file_path, sample_rate, audio_array)
+        prompt = enhance_music_prompt(user_prompt, data.get('with_vocals', False), data)
+        logger.info(f"Generating melody-conditioned music for prompt: '{prompt}' (duration: {duration}s)")
 
-            results.append({
-                "title": title,
-                "file_id": file_id,
-                "file_path": f"/download/{file_id}",
-                "prompt": prompt
-            })
+        inputs = m_processor(
+            audio=melody,
+            sampling_rate=melody_rate,
+            text=[prompt],
+            padding=True,
+            return_tensors="pt",
+        ).to(device)
+
+        if data.get('seed') is not None:
+            torch.manual_seed(data['seed'])
+        with torch.no_grad():
+            audio_values = m_model.generate(
+                **inputs,
+                max_new_tokens=int(duration * 50),
+                do_sample=True,
+                **sampling_options(data)
+            )
+
+        sample_rate = m_model.config.audio_encoder.sampling_rate
+        file_id = save_generated_audio(audio_values, sample_rate)
 
         return jsonify({
             "success": True,
-            "songs": results,
-            "count": len(results)
+            "file_id": file_id,
+            "file_path": f"/download/{file_id}",
+            "duration": duration,
+            "sample_rate": sample_rate,
+            "prompt": prompt,
+            "timestamp": datetime.now().isoformat()
         })
 
     except Exception as e:
-        logger.error(f"Error in batch generation: {str(e)}")
+        logger.error(f"Error generating melody-conditioned music: {str(e)}")
+        return jsonify({
+            "success": False,
+            "error": str(e)
+        }), 500
+
+@app.route('/continue', methods=['POST'])
+def continue_music():
+    """
+    Continue an existing clip up to a longer total duration
+
+    Request JSON:
+    {
+        "audio": "base64 mono 32kHz WAV",
+        "duration": 20,  # total length including the input clip (max 30)
+        "prompt": "add drums"  # optional
+        # plus the same optional controls as /generate
+    }
+    """
+    try:
+        if model is None:
+            return jsonify({
+                "success": False,
+                "error": "Model not initialized"
+            }), 500
+
+        data = request.get_json()
+        duration = min(data.get('duration', 20), 30)
+
+        if not data.get('audio'):
+            return jsonify({
+                "success": False,
+                "error": "Audio is required"
+            }), 400
+
+        audio_rate, audio = decode_wav_base64(data['audio'])
+        clip_seconds = len(audio) / audio_rate
+        if clip_seconds >= duration:
+            return jsonify({
+                "success": False,
+                "error": "Duration must be longer than the input clip"
+            }), 400
+
+        size_processor, size_model = get_model(data.get('model_size'))
+        user_prompt = data.get('prompt') or "continue the music in the same style"
+        prompt = enhance_music_prompt(user_prompt, data.get('with_vocals', False), data)
+        logger.info(f"Continuing {clip_seconds:.1f}s clip to {duration}s: '{prompt}'")
+
+        inputs = size_processor(
+            audio=audio,
+            sampling_rate=audio_rate,
+            text=[prompt],
+            padding=True,
+            return_tensors="pt",
+        ).to(device)
+
+        if data.get('seed') is not None:
+            torch.manual_seed(data['seed'])
+        with torch.no_grad():
+            # The output starts with the input clip, so only the remainder is new
+            audio_values = size_model.generate(
+                **inputs,
+                max_new_tokens=int((duration - clip_seconds) * 50),
+                do_sample=True,
+                **sampling_options(data)
+            )
+
+        sample_rate = size_model.config.audio_encoder.sampling_rate
+        file_id = save_generated_audio(audio_values, sample_rate)
+
+        return jsonify({
+            "success": True,
+            "file_id": file_id,
+            "file_path": f"/download/{file_id}",
+            "duration": duration,
+            "sample_rate": sample_rate,
+            "prompt": prompt,
+            "timestamp": datetime.now().isoformat()
+        })
+
+    except Exception as e:
+        logger.error(f"Error continuing music: {str(e)}")
         return jsonify({
             "success": False,
             "error": str(e)
@@ -0,0 +1,328 @@
+use super::{AudioClip, AudioResult};
+use ebur128::{EbuR128, Mode};
+use std::f32::consts::FRAC_PI_2;
+
+/// Samples quieter than this (about -50 dBFS) count as silence
+const SILENCE_THRESHOLD: f32 = 0.003;
+
+/// Keep normalized peaks just below full scale to avoid clipping
+const PEAK_CEILING: f32 = 0.989; // -0.1 dBFS
+
+/// Measure integrated loudness (EBU R128) and apply the gain needed to hit `target_lufs`
+pub fn normalize_loudness(clip: &mut AudioClip, target_lufs: f64) -> AudioResult<()> {
+    let mut meter = EbuR128::new(clip.channels as u32, clip.sample_rate, Mode::I)?;
+    meter.add_frames_f32(&clip.samples)?;
+
+    let measured = meter.loudness_global()?;
+    if !measured.is_finite() {
+        // Silent clips have no defined loudness
+        return Ok(());
+    }
+
+    let mut gain = 10f64.powf((target_lufs - measured) / 20.0) as f32;
+
+    let peak = clip
+        .samples
+        .iter()
+        .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+    if peak * gain > PEAK_CEILING {
+        gain = PEAK_CEILING / peak;
+    }
+
+    for sample in clip.samples.iter_mut() {
+        *sample *= gain;
+    }
+
+    Ok(())
+}
+
+pub fn fade_in(clip: &mut AudioClip, ms: u32) {
+    let fade_frames = clip.frames_for_ms(ms);
+    let channels = clip.channels as usize;
+
+    for frame in 0..fade_frames {
+        let gain = frame as f32 / fade_frames as f32;
+        for sample in &mut clip.samples[frame * channels..(frame + 1) * channels] {
+            *sample *= gain;
+        }
+    }
+}
+
+pub fn fade_out(clip: &mut AudioClip, ms: u32) {
+    let fade_frames = clip.frames_for_ms(ms);
+    let channels = clip.channels as usize;
+    let start = clip.frames() - fade_frames;
+
+    for frame in start..clip.frames() {
+        let gain = (clip.frames() - frame) as f32 / fade_frames as f32;
+        for sample in &mut clip.samples[frame * channels..(frame + 1) * channels] {
+            *sample *= gain;
+        }
+    }
+}
+
+/// Remove leading and trailing silence
+pub fn trim_silence(clip: &mut AudioClip) {
+    let channels = clip.channels as usize;
+    let is_loud = |frame: &[f32]| frame.iter().any(|sample| sample.abs() > SILENCE_THRESHOLD);
+
+    let frames: Vec<&[f32]> = clip.samples.chunks(channels).collect();
+    let Some(first) = frames.iter().position(|frame| is_loud(frame)) else {
+        return;
+    };
+    let last = frames
+        .iter()
+        .rposition(|frame| is_loud(frame))
+        .unwrap_or(first);
+
+    clip.samples = clip.samples[first * channels..(last + 1) * channels].to_vec();
+}
+
+/// Concatenate clips, overlapping each pair by `crossfade_ms` with an equal-power curve
+pub fn crossfade_concat(clips: &[AudioClip], crossfade_ms: u32) -> AudioResult<AudioClip> {
+    let Some(first) = clips.first() else {
+        return Err("There are no tracks to mix".into());
+    };
+
+    if clips
+        .iter()
+        .any(|clip| clip.channels != first.channels || clip.sample_rate != first.sample_rate)
+    {
+        return Err("All tracks in a mix must share the same sample rate and channel count".into());
+    }
+
+    let channels = first.channels as usize;
+    let mut mixed = first.clone();
+
+    for next in &clips[1..] {
+        let overlap = mixed
+            .frames_for_ms(crossfade_ms)
+            .min(next.frames_for_ms(crossfade_ms));
+        let start = (mixed.frames() - overlap) * channels;
+
+        for frame in 0..overlap {
+            let position = (frame as f32 + 0.5) / overlap as f32;
+            let fade_out = (position * FRAC_PI_2).cos();
+            let fade_in = (position * FRAC_PI_2).sin();
+
+            for channel in 0..channels {
+                let index = frame * channels + channel;
+                mixed.samples[start + index] =
+                    mixed.samples[start + index] * fade_out + next.samples[index] * fade_in;
+            }
+        }
+
+        mixed
+            .samples
+            .extend_from_slice(&next.samples[overlap * channels..]);
+    }
+
+    Ok(mixed)
+}
+
+/// Average all channels down to one
+pub fn to_mono(clip: &mut AudioClip) {
+    let channels = clip.channels.max(1) as usize;
+    if channels == 1 {
+        return;
+    }
+
+    clip.samples = clip
+        .samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+    clip.channels = 1;
+}
+
+/// Linearly resample every channel to `sample_rate`
+pub fn resample(clip: &mut AudioClip, sample_rate: u32) {
+    if clip.sample_rate == sample_rate || clip.frames() == 0 {
+        return;
+    }
+
+    let channels = clip.channels.max(1) as usize;
+    let source_frames = clip.frames();
+    let target_frames =
+        (source_frames as u64 * sample_rate as u64 / clip.sample_rate as u64).max(1) as usize;
+    let step = clip.sample_rate as f64 / sample_rate as f64;
+
+    let mut samples = Vec::with_capacity(target_frames * channels);
+    for frame in 0..target_frames {
+        let position = frame as f64 * step;
+        let index = (position as usize).min(source_frames - 1);
+        let next = (index + 1).min(source_frames - 1);
+        let weight = (position - index as f64) as f32;
+
+        for channel in 0..channels {
+            let a = clip.samples[index * channels + channel];
+            let b = clip.samples[next * channels + channel];
+            samples.push(a + (b - a) * weight);
+        }
+    }
+
+    clip.samples = samples;
+    clip.sample_rate = sample_rate;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(samples: Vec<f32>, channels: u16, sample_rate: u32) -> AudioClip {
+        AudioClip {
+            samples,
+            channels,
+            sample_rate,
+        }
+    }
+
+    fn sine(amplitude: f32, seconds: f32, sample_rate: u32) -> AudioClip {
+        let frames = (seconds * sample_rate as f32) as usize;
+        let samples = (0..frames)
+            .map(|n| {
+                let t = n as f32 / sample_rate as f32;
+                amplitude * (2.0 * std::f32::consts::PI * 440.0 * t).sin()
+            })
+            .collect();
+        clip(samples, 1, sample_rate)
+    }
+
+    fn loudness(clip: &AudioClip) -> f64 {
+        let mut meter = EbuR128::new(clip.channels as u32, clip.sample_rate, Mode::I).unwrap();
+        meter.add_frames_f32(&clip.samples).unwrap();
+        meter.loudness_global().unwrap()
+    }
+
+    #[test]
+    fn normalize_loudness_reaches_the_target() {
+        let mut quiet = sine(0.05, 3.0, 48_000);
+
+        normalize_loudness(&mut quiet, -14.0).unwrap();
+
+        assert!((loudness(&quiet) + 14.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn normalize_loudness_never_clips() {
+        let mut loud = sine(0.9, 3.0, 48_000);
+
+        normalize_loudness(&mut loud, 0.0).unwrap();
+
+        let peak = loud
+            .samples
+            .iter()
+            .fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!(peak <= PEAK_CEILING + f32::EPSILON);
+    }
+
+    #[test]
+    fn normalize_loudness_leaves_silence_alone() {
+        let mut silent = clip(vec![0.0; 48_000], 1, 48_000);
+
+        normalize_loudness(&mut silent, -14.0).unwrap();
+
+        assert!(silent.samples.iter().all(|sample| *sample == 0.0));
+    }
+
+    #[test]
+    fn fades_ramp_linearly_over_the_requested_time() {
+        let mut faded = clip(vec![1.0; 1000], 1, 1000);
+
+        fade_in(&mut faded, 100);
+        fade_out(&mut faded, 100);
+
+        assert_eq!(faded.samples[0], 0.0);
+        assert_eq!(faded.samples[50], 0.5);
+        assert_eq!(faded.samples[500], 1.0);
+        assert_eq!(faded.samples[900], 1.0);
+        assert_eq!(faded.samples[999], 0.01);
+    }
+
+    #[test]
+    fn fades_longer_than_the_clip_cover_the_whole_clip() {
+        let mut faded = clip(vec![1.0; 10], 1, 1000);
+
+        fade_in(&mut faded, 1000);
+
+        assert_eq!(faded.samples[0], 0.0);
+        assert_eq!(faded.samples[5], 0.5);
+    }
+
+    #[test]
+    fn fades_apply_the_same_gain_to_every_channel() {
+        let mut faded = clip(vec![1.0; 200], 2, 1000);
+
+        fade_in(&mut faded, 100);
+
+        assert_eq!(faded.samples[50 * 2], faded.samples[50 * 2 + 1]);
+    }
+
+    #[test]
+    fn trim_silence_keeps_the_loud_part() {
+        let mut samples = vec![0.0; 10];
+        samples.extend([0.5, -0.5, 0.5]);
+        samples.extend(vec![0.001; 10]);
+        let mut trimmed = clip(samples, 1, 1000);
+
+        trim_silence(&mut trimmed);
+
+        assert_eq!(trimmed.samples, vec![0.5, -0.5, 0.5]);
+    }
+
+    #[test]
+    fn trim_silence_leaves_all_silent_clips_unchanged() {
+        let mut silent = clip(vec![0.0; 10], 1, 1000);
+
+        trim_silence(&mut silent);
+
+        assert_eq!(silent.frames(), 10);
+    }
+
+    #[test]
+    fn crossfade_overlaps_each_pair_of_clips() {
+        let clips = vec![
+            clip(vec![1.0; 1000], 1, 1000),
+            clip(vec![1.0; 1000], 1, 1000),
+            clip(vec![1.0; 1000], 1, 1000),
+        ];
+
+        let mixed = crossfade_concat(&clips, 100).unwrap();
+
+        assert_eq!(mixed.frames(), 2800);
+        // Equal-power fades keep a constant-level signal close to its level
+        assert!(mixed
+            .samples
+            .iter()
+            .all(|sample| (0.99..=1.42).contains(sample)));
+    }
+
+    #[test]
+    fn crossfade_rejects_mismatched_or_missing_clips() {
+        assert!(crossfade_concat(&[], 100).is_err());
+
+        let clips = vec![clip(vec![0.0; 100], 1, 1000), clip(vec![0.0; 100], 1, 2000)];
+        assert!(crossfade_concat(&clips, 10).is_err());
+    }
+
+    #[test]
+    fn to_mono_averages_channels() {
+        let mut stereo = clip(vec![1.0, 0.5, -1.0, 0.0], 2, 1000);
+
+        to_mono(&mut stereo);
+
+        assert_eq!(stereo.channels, 1);
+        assert_eq!(stereo.samples, vec![0.75, -0.5]);
+    }
+
+    #[test]
+    fn resample_keeps_the_duration() {
+        let mut tone = sine(0.5, 1.0, 48_000);
+
+        resample(&mut tone, 32_000);
+
+        assert_eq!(tone.sample_rate, 32_000);
+        assert_eq!(tone.frames(), 32_000);
+        assert_eq!(tone.duration_seconds(), 1.0);
+    }
+}
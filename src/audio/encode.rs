@@ -0,0 +1,178 @@
+use super::{AudioClip, AudioResult};
+use crate::models::playlist::AudioFormat;
+use mp3lame_encoder::{Builder, FlushNoGap, InterleavedPcm, MonoPcm};
+use std::io::Cursor;
+use std::num::{NonZeroU32, NonZeroU8};
+use vorbis_rs::VorbisEncoderBuilder;
+
+/// Vorbis encodes best with blocks of around a thousand frames
+const VORBIS_BLOCK_FRAMES: usize = 1024;
+
+pub fn encode(clip: &AudioClip, format: AudioFormat) -> AudioResult<Vec<u8>> {
+    match format {
+        AudioFormat::Wav => encode_wav(clip),
+        AudioFormat::Mp3 => encode_mp3(clip),
+        AudioFormat::Ogg => encode_ogg(clip),
+        AudioFormat::Flac => encode_flac(clip),
+    }
+}
+
+fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16
+}
+
+fn encode_wav(clip: &AudioClip) -> AudioResult<Vec<u8>> {
+    let spec = hound::WavSpec {
+        channels: clip.channels,
+        sample_rate: clip.sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut bytes = Vec::new();
+    let mut writer = hound::WavWriter::new(Cursor::new(&mut bytes), spec)?;
+    for sample in &clip.samples {
+        writer.write_sample(to_i16(*sample))?;
+    }
+    writer.finalize()?;
+
+    Ok(bytes)
+}
+
+fn encode_mp3(clip: &AudioClip) -> AudioResult<Vec<u8>> {
+    if clip.channels > 2 {
+        return Err("MP3 output supports at most two channels".into());
+    }
+
+    let mut builder = Builder::new().ok_or("Failed to create MP3 encoder")?;
+    builder.set_num_channels(clip.channels as u8)?;
+    builder.set_sample_rate(clip.sample_rate)?;
+    builder.set_brate(mp3lame_encoder::Bitrate::Kbps192)?;
+    builder.set_quality(mp3lame_encoder::Quality::Good)?;
+    let mut encoder = builder.build()?;
+
+    let pcm: Vec<i16> = clip.samples.iter().map(|sample| to_i16(*sample)).collect();
+    let mut bytes = Vec::with_capacity(mp3lame_encoder::max_required_buffer_size(clip.frames()));
+
+    if clip.channels == 1 {
+        encoder.encode_to_vec(MonoPcm(&pcm), &mut bytes)?;
+    } else {
+        encoder.encode_to_vec(InterleavedPcm(&pcm), &mut bytes)?;
+    }
+    encoder.flush_to_vec::<FlushNoGap>(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+fn encode_ogg(clip: &AudioClip) -> AudioResult<Vec<u8>> {
+    let channels = clip.channels as usize;
+    let sample_rate = NonZeroU32::new(clip.sample_rate).ok_or("Sample rate must not be zero")?;
+    let channel_count = NonZeroU8::new(clip.channels as u8).ok_or("Audio has no channels")?;
+
+    let mut encoder = VorbisEncoderBuilder::new(sample_rate, channel_count, Vec::new())?.build()?;
+
+    for block in clip.samples.chunks(VORBIS_BLOCK_FRAMES * channels) {
+        let planar: Vec<Vec<f32>> = (0..channels)
+            .map(|channel| {
+                block
+                    .iter()
+                    .skip(channel)
+                    .step_by(channels)
+                    .copied()
+                    .collect()
+            })
+            .collect();
+        encoder.encode_audio_block(&planar)?;
+    }
+
+    Ok(encoder.finish()?)
+}
+
+fn encode_flac(clip: &AudioClip) -> AudioResult<Vec<u8>> {
+    use flacenc::component::BitRepr;
+    use flacenc::error::Verify;
+
+    let config = flacenc::config::Encoder::default()
+        .into_verified()
+        .map_err(|(_, e)| format!("Invalid FLAC encoder config: {:?}", e))?;
+
+    let pcm: Vec<i32> = clip
+        .samples
+        .iter()
+        .map(|sample| to_i16(*sample) as i32)
+        .collect();
+    let source = flacenc::source::MemSource::from_samples(
+        &pcm,
+        clip.channels as usize,
+        16,
+        clip.sample_rate as usize,
+    );
+
+    let stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
+        .map_err(|e| format!("FLAC encoding failed: {:?}", e))?;
+
+    let mut sink = flacenc::bitsink::ByteSink::new();
+    stream
+        .write(&mut sink)
+        .map_err(|e| format!("Failed to write FLAC stream: {:?}", e))?;
+
+    Ok(sink.as_slice().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(channels: u16) -> AudioClip {
+        let sample_rate = 32_000;
+        let samples = (0..sample_rate as usize * channels as usize)
+            .map(|n| 0.5 * (n as f32 * 0.05).sin())
+            .collect();
+        AudioClip {
+            samples,
+            channels,
+            sample_rate,
+        }
+    }
+
+    #[test]
+    fn wav_round_trips_through_the_decoder() {
+        let clip = tone(2);
+
+        let decoded = AudioClip::from_wav(&encode(&clip, AudioFormat::Wav).unwrap()).unwrap();
+
+        assert_eq!(decoded.channels, 2);
+        assert_eq!(decoded.sample_rate, clip.sample_rate);
+        assert_eq!(decoded.samples.len(), clip.samples.len());
+        for (decoded, original) in decoded.samples.iter().zip(&clip.samples) {
+            assert!((decoded - original).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn wav_clamps_samples_outside_full_scale() {
+        assert_eq!(to_i16(2.0), i16::MAX);
+        assert_eq!(to_i16(-2.0), -i16::MAX);
+    }
+
+    #[test]
+    fn compressed_formats_write_their_container_headers() {
+        let clip = tone(1);
+
+        let mp3 = encode(&clip, AudioFormat::Mp3).unwrap();
+        // An MPEG frame sync, possibly after an ID3 tag
+        assert!(mp3.starts_with(b"ID3") || (mp3[0] == 0xFF && mp3[1] & 0xE0 == 0xE0));
+
+        assert!(encode(&clip, AudioFormat::Ogg)
+            .unwrap()
+            .starts_with(b"OggS"));
+        assert!(encode(&clip, AudioFormat::Flac)
+            .unwrap()
+            .starts_with(b"fLaC"));
+    }
+
+    #[test]
+    fn mp3_rejects_more_than_two_channels() {
+        assert!(encode(&tone(3), AudioFormat::Mp3).is_err());
+    }
+}
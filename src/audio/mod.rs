@@ -0,0 +1,128 @@
+pub mod effects;
+pub mod encode;
+
+use crate::models::playlist::{AudioFormat, AudioPostProcessing};
+use std::error::Error;
+use std::io::Cursor;
+
+/// Sample rate MusicGen generates at and expects for conditioning audio
+pub const MUSICGEN_SAMPLE_RATE: u32 = 32_000;
+
+pub type AudioResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Decoded PCM audio with samples interleaved across channels, in the range -1.0..=1.0
+#[derive(Debug, Clone)]
+pub struct AudioClip {
+    pub samples: Vec<f32>,
+    pub channels: u16,
+    pub sample_rate: u32,
+}
+
+impl AudioClip {
+    pub fn from_wav(bytes: &[u8]) -> AudioResult<Self> {
+        let mut reader = hound::WavReader::new(Cursor::new(bytes))?;
+        let spec = reader.spec();
+
+        let samples = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|sample| sample.map(|value| value as f32 / scale))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
+
+        Ok(Self {
+            samples,
+            channels: spec.channels,
+            sample_rate: spec.sample_rate,
+        })
+    }
+
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+
+    pub fn duration_seconds(&self) -> f64 {
+        self.frames() as f64 / self.sample_rate as f64
+    }
+
+    /// Number of frames covering `ms` milliseconds, capped at the clip length
+    pub fn frames_for_ms(&self, ms: u32) -> usize {
+        ((self.sample_rate as u64 * ms as u64 / 1000) as usize).min(self.frames())
+    }
+}
+
+/// Apply the per-track effects in `options` to a WAV file and encode it in the requested format
+pub fn process(wav: &[u8], options: &AudioPostProcessing) -> AudioResult<(Vec<u8>, AudioFormat)> {
+    let mut clip = AudioClip::from_wav(wav)?;
+    apply_effects(&mut clip, options)?;
+
+    let format = options.format.unwrap_or_default();
+    Ok((encode::encode(&clip, format)?, format))
+}
+
+/// Join several WAV files into one continuous mix, crossfading between them
+pub fn mix(
+    wavs: &[Vec<u8>],
+    options: &AudioPostProcessing,
+) -> AudioResult<(Vec<u8>, AudioFormat, f64)> {
+    let mut clips = wavs
+        .iter()
+        .map(|wav| AudioClip::from_wav(wav))
+        .collect::<AudioResult<Vec<_>>>()?;
+
+    if options.trim_silence {
+        clips.iter_mut().for_each(effects::trim_silence);
+    }
+
+    let mut mixed = effects::crossfade_concat(&clips, options.crossfade_ms.unwrap_or(0))?;
+
+    // Fades and loudness apply to the mix as a whole
+    let mix_options = AudioPostProcessing {
+        trim_silence: false,
+        ..options.clone()
+    };
+    apply_effects(&mut mixed, &mix_options)?;
+
+    let format = options.format.unwrap_or_default();
+    let duration = mixed.duration_seconds();
+    Ok((encode::encode(&mixed, format)?, format, duration))
+}
+
+/// Convert an input clip to the mono 32 kHz WAV MusicGen conditions on.
+/// Returns the encoded file and its duration in seconds.
+pub fn prepare_conditioning(wav: &[u8], max_seconds: f64) -> AudioResult<(Vec<u8>, f64)> {
+    let mut clip = AudioClip::from_wav(wav)?;
+
+    if clip.frames() == 0 {
+        return Err("Audio file contains no samples".into());
+    }
+    if clip.duration_seconds() > max_seconds {
+        return Err(format!("Audio must be at most {} seconds long", max_seconds).into());
+    }
+
+    effects::to_mono(&mut clip);
+    effects::resample(&mut clip, MUSICGEN_SAMPLE_RATE);
+
+    let duration = clip.duration_seconds();
+    Ok((encode::encode(&clip, AudioFormat::Wav)?, duration))
+}
+
+fn apply_effects(clip: &mut AudioClip, options: &AudioPostProcessing) -> AudioResult<()> {
+    if options.trim_silence {
+        effects::trim_silence(clip);
+    }
+    if let Some(target_lufs) = options.normalize_lufs {
+        effects::normalize_loudness(clip, target_lufs)?;
+    }
+    if let Some(ms) = options.fade_in_ms {
+        effects::fade_in(clip, ms);
+    }
+    if let Some(ms) = options.fade_out_ms {
+        effects::fade_out(clip, ms);
+    }
+    Ok(())
+}
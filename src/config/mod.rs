@@ -1,2 +1,86 @@
-// Config values are now loaded from environment variables
-// See .env.example for required variables
+// Config values are loaded from environment variables or mounted secret files.
+// See .env.example for required variables.
+
+use rspotify::Credentials;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Where secret files are looked up when neither NAME nor NAME_FILE is set
+const DEFAULT_SECRETS_DIR: &str = "/run/secrets";
+
+/// A credential that never appears in `Debug` output or logs.
+///
+/// There is deliberately no `Display` impl; call `expose` at the point of use.
+#[derive(Clone)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret([REDACTED])")
+    }
+}
+
+/// Load a secret from, in order: the `NAME` variable, the file named by
+/// `NAME_FILE`, or `$SECRETS_DIR/NAME` (default `/run/secrets`) as mounted by
+/// Docker or Kubernetes. Empty values count as missing.
+pub fn load_secret(name: &str) -> Option<Secret> {
+    if let Ok(value) = std::env::var(name) {
+        if !value.trim().is_empty() {
+            return Some(Secret::new(value.trim()));
+        }
+    }
+
+    let path = match std::env::var(format!("{}_FILE", name)) {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => {
+            let dir = std::env::var("SECRETS_DIR").unwrap_or_else(|_| DEFAULT_SECRETS_DIR.into());
+            PathBuf::from(dir).join(name)
+        }
+    };
+
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|contents| contents.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .map(Secret::new)
+}
+
+/// Like `load_secret`, but the server cannot start without it
+pub fn require_secret(name: &str) -> Secret {
+    load_secret(name)
+        .unwrap_or_else(|| panic!("{} must be set, either directly or via {}_FILE", name, name))
+}
+
+/// The Spotify app registration used for OAuth and client-credentials calls
+#[derive(Debug, Clone)]
+pub struct SpotifyConfig {
+    pub client_id: String,
+    pub client_secret: Secret,
+    pub redirect_uri: String,
+}
+
+impl SpotifyConfig {
+    /// Read SPOTIFY_CLIENT_ID, SPOTIFY_CLIENT_SECRET and SPOTIFY_REDIRECT_URI.
+    /// Returns `None` when any of them is missing.
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            client_id: std::env::var("SPOTIFY_CLIENT_ID").ok()?,
+            client_secret: load_secret("SPOTIFY_CLIENT_SECRET")?,
+            redirect_uri: std::env::var("SPOTIFY_REDIRECT_URI").ok()?,
+        })
+    }
+
+    pub fn credentials(&self) -> Credentials {
+        Credentials::new(&self.client_id, self.client_secret.expose())
+    }
+}
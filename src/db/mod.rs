@@ -0,0 +1,196 @@
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use std::str::FromStr;
+
+/// Page size for list endpoints when the request does not set `limit`
+pub const DEFAULT_PAGE_SIZE: u32 = 20;
+
+/// Largest `limit` a list endpoint accepts
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// Clamp a requested page size to `1..=MAX_PAGE_SIZE`
+pub fn page_limit(limit: Option<u32>) -> u32 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+/// Escape LIKE wildcards so search terms match literally; use with `ESCAPE '\'`
+pub fn like_pattern(term: &str) -> String {
+    let escaped = term
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// Open the SQLite pool and make sure every table the app relies on exists
+pub async fn init_pool(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
+    let options = SqliteConnectOptions::from_str(database_url)?.create_if_missing(true);
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect_with(options)
+        .await?;
+
+    run_migrations(&pool).await?;
+
+    Ok(pool)
+}
+
+async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS ai_daily_quota (
+            user_id TEXT NOT NULL,
+            day TEXT NOT NULL,
+            used INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (user_id, day)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS ai_music_jobs (
+            id TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            status TEXT NOT NULL,
+            progress REAL NOT NULL DEFAULT 0,
+            request TEXT NOT NULL,
+            file_ids TEXT NOT NULL DEFAULT '[]',
+            result TEXT,
+            error TEXT,
+            user_id TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Jobs queued without a signed-in user belong to the browser session that queued them
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS ai_music_job_sessions (
+            job_id TEXT PRIMARY KEY,
+            session_owner TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS ai_music_files (
+            file_id TEXT PRIMARY KEY,
+            user_id TEXT,
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Post-processed files point back at the raw WAV they were made from
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS ai_music_file_sources (
+            file_id TEXT PRIMARY KEY,
+            original_file_id TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS ai_music_library (
+            file_id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            original_file_id TEXT,
+            title TEXT,
+            prompt TEXT NOT NULL,
+            source TEXT NOT NULL,
+            parameters TEXT NOT NULL DEFAULT '{}',
+            duration_seconds REAL NOT NULL,
+            favorite INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_ai_music_library_user ON ai_music_library (user_id, created_at)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS api_keys (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            prefix TEXT NOT NULL,
+            key_hash TEXT NOT NULL UNIQUE,
+            scopes TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            expires_at TEXT,
+            last_used_at TEXT,
+            revoked_at TEXT
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS users (
+            spotify_id TEXT PRIMARY KEY,
+            display_name TEXT,
+            country TEXT,
+            product TEXT,
+            preferences TEXT NOT NULL DEFAULT '{}',
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            last_login_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS playlist_generations (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            prompt TEXT NOT NULL,
+            parameters TEXT NOT NULL DEFAULT '{}',
+            model TEXT NOT NULL,
+            tracks TEXT NOT NULL DEFAULT '[]',
+            playlist_name TEXT NOT NULL,
+            playlist_description TEXT NOT NULL,
+            latency_ms INTEGER NOT NULL,
+            source_generation_id TEXT,
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_playlist_generations_user ON playlist_generations (user_id, created_at)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
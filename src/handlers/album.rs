@@ -0,0 +1,95 @@
+use crate::i18n::{Locale, Message};
+use crate::middleware::api_key::Caller;
+use crate::middleware::rate_limit::charge_prompts;
+use crate::models::job::JobKind;
+use crate::models::playlist::{validate_duration, AiAlbumRequest};
+use crate::services::job_service::DEFAULT_ALBUM_TRACKS;
+use crate::AppState;
+use actix_session::Session;
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+
+use super::jobs::{job_created, session_owner};
+
+const MAX_ALBUM_TRACKS: usize = 10;
+
+/// Queue a full album: Gemini writes the track concepts, MusicGen renders them.
+/// The finished job's result is an `AiAlbumResponse`.
+#[utoipa::path(
+    post,
+    path = "/api/v1/albums",
+    tag = "ai-music",
+    security((), ("api_key" = [])),
+    request_body = AiAlbumRequest,
+    responses(
+        (status = 202, description = "Album job queued", body = JobCreatedResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 429, description = "Rate limited or daily quota used up", body = ErrorResponse),
+        (status = 500, description = "Job could not be queued", body = ErrorResponse)
+    )
+)]
+pub async fn generate_ai_album(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    request: web::Json<AiAlbumRequest>,
+    caller: Caller,
+    session: Session,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    tracing::debug!(prompt = %request.prompt, track_count = ?request.track_count, "Received AI album request");
+
+    if request.prompt.trim().is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": locale.text("prompt-empty")
+        })));
+    }
+
+    let track_count = request.track_count.unwrap_or(DEFAULT_ALBUM_TRACKS);
+    if track_count == 0 || track_count > MAX_ALBUM_TRACKS {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": locale.message(
+                &Message::new("field-out-of-range")
+                    .arg("field", "track_count")
+                    .arg("min", 1)
+                    .arg("max", MAX_ALBUM_TRACKS)
+            )
+        })));
+    }
+
+    if let Err(message) = validate_duration(request.duration) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": locale.message(&message)
+        })));
+    }
+
+    // Every track is a MusicGen generation, so each one counts toward the quota
+    if let Some(response) = charge_prompts(&req, track_count, locale).await {
+        return Ok(response);
+    }
+
+    let session_owner = session_owner(&caller, &session)?;
+    let user_id = caller.user_id;
+    let payload = serde_json::to_value(request.into_inner())?;
+
+    match app_state
+        .job_service
+        .enqueue(JobKind::Album, payload, user_id, session_owner)
+        .await
+    {
+        Ok(job) => {
+            tracing::info!(job_id = %job.id, "Queued AI album job");
+            Ok(job_created(&req, &job))
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error queueing AI album job");
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": locale.message(
+                    &Message::new("job-queue-failed").arg("error", e.to_string())
+                )
+            })))
+        }
+    }
+}
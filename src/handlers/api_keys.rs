@@ -0,0 +1,167 @@
+use crate::i18n::{Locale, Message};
+use crate::models::api_key::{
+    ApiKeyCreatedResponse, ApiKeyListResponse, ApiKeyRevokedResponse, CreateApiKeyRequest,
+};
+use crate::AppState;
+use actix_session::Session;
+use actix_web::{web, Error, HttpResponse};
+
+/// Longest lifetime a key can be created with
+const MAX_EXPIRY_DAYS: u32 = 365;
+
+fn login_required(locale: Locale) -> HttpResponse {
+    HttpResponse::Unauthorized().json(serde_json::json!({
+        "success": false,
+        "error": locale.text("login-required-api-keys")
+    }))
+}
+
+fn api_key_error(locale: Locale, action: &'static str, e: impl std::fmt::Display) -> HttpResponse {
+    tracing::error!(error = %e, "Error trying to {} API key", action);
+    HttpResponse::InternalServerError().json(serde_json::json!({
+        "success": false,
+        "error": locale.message(&Message::new("api-key-failed").arg("action", action))
+    }))
+}
+
+fn validate_create_request(request: &CreateApiKeyRequest) -> Result<(), Message> {
+    if request.name.trim().is_empty() || request.name.len() > 100 {
+        return Err(Message::new("field-length")
+            .arg("field", "name")
+            .arg("max", 100));
+    }
+
+    if request.scopes.is_empty() {
+        return Err(Message::new("scopes-empty"));
+    }
+
+    if let Some(days) = request.expires_in_days {
+        if days == 0 || days > MAX_EXPIRY_DAYS {
+            return Err(Message::new("field-out-of-range")
+                .arg("field", "expires_in_days")
+                .arg("min", 1)
+                .arg("max", MAX_EXPIRY_DAYS));
+        }
+    }
+
+    Ok(())
+}
+
+/// Create an API key for the signed-in user. Only a browser session can do this,
+/// so a leaked key cannot be used to mint more keys.
+#[utoipa::path(
+    post,
+    path = "/api/v1/api-keys",
+    tag = "api-keys",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "Key created with its secret", body = ApiKeyCreatedResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in with Spotify", body = ErrorResponse),
+        (status = 500, description = "Key could not be created", body = ErrorResponse)
+    )
+)]
+pub async fn create_api_key(
+    app_state: web::Data<AppState>,
+    request: web::Json<CreateApiKeyRequest>,
+    session: Session,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(user_id) = session.get::<String>("spotify_user_id")? else {
+        return Ok(login_required(locale));
+    };
+
+    if let Err(message) = validate_create_request(&request) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": locale.message(&message)
+        })));
+    }
+
+    match app_state
+        .api_key_service
+        .create(
+            &user_id,
+            request.name.trim(),
+            &request.scopes,
+            request.expires_in_days,
+        )
+        .await
+    {
+        Ok((api_key, key)) => {
+            tracing::info!(key_id = %api_key.id, "Created API key");
+            Ok(HttpResponse::Created().json(ApiKeyCreatedResponse {
+                success: true,
+                key,
+                api_key,
+            }))
+        }
+        Err(e) => Ok(api_key_error(locale, "create", e)),
+    }
+}
+
+/// List the signed-in user's API keys without their secrets
+#[utoipa::path(
+    get,
+    path = "/api/v1/api-keys",
+    tag = "api-keys",
+    responses(
+        (status = 200, description = "The user's keys, newest first", body = ApiKeyListResponse),
+        (status = 401, description = "Not signed in with Spotify", body = ErrorResponse),
+        (status = 500, description = "Keys could not be loaded", body = ErrorResponse)
+    )
+)]
+pub async fn list_api_keys(
+    app_state: web::Data<AppState>,
+    session: Session,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(user_id) = session.get::<String>("spotify_user_id")? else {
+        return Ok(login_required(locale));
+    };
+
+    match app_state.api_key_service.list(&user_id).await {
+        Ok(api_keys) => Ok(HttpResponse::Ok().json(ApiKeyListResponse {
+            success: true,
+            api_keys,
+        })),
+        Err(e) => Ok(api_key_error(locale, "load", e)),
+    }
+}
+
+/// Revoke one of the signed-in user's API keys; it stops working immediately
+#[utoipa::path(
+    delete,
+    path = "/api/v1/api-keys/{id}",
+    tag = "api-keys",
+    params(("id" = String, Path, description = "API key ID")),
+    responses(
+        (status = 200, description = "Key revoked", body = ApiKeyRevokedResponse),
+        (status = 401, description = "Not signed in with Spotify", body = ErrorResponse),
+        (status = 404, description = "No active key with this ID", body = ErrorResponse),
+        (status = 500, description = "Key could not be revoked", body = ErrorResponse)
+    )
+)]
+pub async fn revoke_api_key(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    session: Session,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(user_id) = session.get::<String>("spotify_user_id")? else {
+        return Ok(login_required(locale));
+    };
+    let id = path.into_inner();
+
+    match app_state.api_key_service.revoke(&user_id, &id).await {
+        Ok(true) => {
+            tracing::info!(key_id = %id, "Revoked API key");
+            Ok(HttpResponse::Ok().json(ApiKeyRevokedResponse { success: true, id }))
+        }
+        Ok(false) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": locale.text("api-key-not-found")
+        }))),
+        Err(e) => Ok(api_key_error(locale, "revoke", e)),
+    }
+}
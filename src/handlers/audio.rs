@@ -0,0 +1,100 @@
+use crate::i18n::{Locale, Message};
+use crate::middleware::api_key::Caller;
+use crate::services::audio_cache_service::FileOwner;
+use crate::AppState;
+use actix_files::NamedFile;
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use std::path::PathBuf;
+
+fn audio_not_found(locale: Locale) -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "success": false,
+        "error": locale.text("audio-not-found")
+    }))
+}
+
+/// Resolve a generated song to its local cache path if the caller may access it.
+/// The inner `Err` is the response to send instead.
+pub(crate) async fn resolve_owned_audio(
+    app_state: &AppState,
+    file_id: &str,
+    caller: &Caller,
+    locale: Locale,
+) -> Result<Result<PathBuf, HttpResponse>, Error> {
+    // File IDs are UUIDs; anything else could escape the cache directory
+    if uuid::Uuid::parse_str(file_id).is_err() {
+        return Ok(Err(audio_not_found(locale)));
+    }
+
+    let owner = match app_state.audio_cache_service.owner_of(file_id).await {
+        Ok(owner) => owner,
+        Err(e) => {
+            tracing::error!(file_id = %file_id, error = %e, "Error looking up file owner");
+            return Ok(Err(HttpResponse::InternalServerError().json(
+                serde_json::json!({
+                    "success": false,
+                    "error": locale.text("audio-load-failed")
+                }),
+            )));
+        }
+    };
+
+    match owner {
+        FileOwner::Unknown => return Ok(Err(audio_not_found(locale))),
+        FileOwner::Anonymous => {}
+        FileOwner::User(user_id) => {
+            if caller.user_id.as_deref() != Some(user_id.as_str()) {
+                // Same response as a missing file so IDs can't be probed
+                return Ok(Err(audio_not_found(locale)));
+            }
+        }
+    }
+
+    match app_state
+        .audio_cache_service
+        .cached_path(file_id, app_state.musicgen_service.as_ref())
+        .await
+    {
+        Ok(path) => Ok(Ok(path)),
+        Err(e) => {
+            tracing::error!(file_id = %file_id, error = %e, "Error fetching audio");
+            Ok(Err(HttpResponse::BadGateway().json(serde_json::json!({
+                "success": false,
+                "error": locale.message(
+                    &Message::new("audio-fetch-failed").arg("error", e.to_string())
+                )
+            }))))
+        }
+    }
+}
+
+/// Stream a generated song through the Rust server, with Range support from the local cache
+#[utoipa::path(
+    get,
+    path = "/api/v1/ai-music/{file_id}",
+    tag = "ai-music",
+    security((), ("api_key" = [])),
+    params(("file_id" = String, Path, description = "ID of a generated song")),
+    responses(
+        (status = 200, description = "Audio file", content_type = "audio/*"),
+        (status = 206, description = "Requested byte range", content_type = "audio/*"),
+        (status = 404, description = "Unknown file or owned by another user", body = ErrorResponse),
+        (status = 502, description = "MusicGen could not supply the audio", body = ErrorResponse)
+    )
+)]
+pub async fn stream_ai_music(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    caller: Caller,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let file_id = path.into_inner();
+
+    let path = match resolve_owned_audio(&app_state, &file_id, &caller, locale).await? {
+        Ok(path) => path,
+        Err(response) => return Ok(response),
+    };
+
+    Ok(NamedFile::open_async(path).await?.into_response(&req))
+}
@@ -0,0 +1,324 @@
+use crate::audio;
+use crate::handlers::audio::resolve_owned_audio;
+use crate::i18n::{Locale, Message};
+use crate::middleware::api_key::Caller;
+use crate::models::library::{NewLibraryTrack, TrackSource};
+use crate::models::playlist::{
+    validate_duration, AiMusicResponse, GenerationControls, MAX_AI_MUSIC_DURATION,
+};
+use crate::services::audio_cache_service::AudioCacheService;
+use crate::AppState;
+use actix_multipart::Multipart;
+use actix_web::{web, Error, HttpResponse};
+use futures::StreamExt;
+
+/// Largest audio upload accepted before decoding
+const MAX_AUDIO_BYTES: usize = 10 * 1024 * 1024;
+
+/// Text fields (prompt, controls JSON) are small; anything bigger is rejected
+const MAX_TEXT_FIELD_BYTES: usize = 16 * 1024;
+
+/// Seconds added to a clip when a continuation does not ask for a duration
+const DEFAULT_CONTINUATION_SECONDS: u32 = 10;
+
+const ALLOWED_AUDIO_TYPES: [&str; 4] = ["audio/wav", "audio/x-wav", "audio/wave", "audio/vnd.wave"];
+
+fn bad_request(locale: Locale, message: impl Into<Message>) -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "success": false,
+        "error": locale.message(&message.into())
+    }))
+}
+
+/// Multipart fields shared by melody and continuation requests
+#[derive(Debug, Default)]
+struct ConditioningForm {
+    audio: Option<Vec<u8>>,
+    file_id: Option<String>,
+    prompt: Option<String>,
+    duration: Option<u32>,
+    controls: GenerationControls,
+}
+
+/// Read the form, rejecting unknown audio types, oversized fields and malformed values
+async fn read_form(
+    mut payload: Multipart,
+    locale: Locale,
+) -> Result<Result<ConditioningForm, HttpResponse>, Error> {
+    let mut form = ConditioningForm::default();
+
+    while let Some(field) = payload.next().await {
+        let mut field = field?;
+        let name = field.name().to_string();
+
+        if name == "audio" {
+            let content_type = field
+                .content_type()
+                .map(|mime| mime.essence_str().to_string())
+                .unwrap_or_default();
+            if !ALLOWED_AUDIO_TYPES.contains(&content_type.as_str()) {
+                return Ok(Err(bad_request(locale, "unsupported-audio-type")));
+            }
+        }
+
+        let limit = if name == "audio" {
+            MAX_AUDIO_BYTES
+        } else {
+            MAX_TEXT_FIELD_BYTES
+        };
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk?;
+            if bytes.len() + chunk.len() > limit {
+                return Ok(Err(bad_request(
+                    locale,
+                    Message::new("form-field-too-large").arg("field", name),
+                )));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        if name == "audio" {
+            form.audio = Some(bytes);
+            continue;
+        }
+
+        let Ok(text) = String::from_utf8(bytes) else {
+            return Ok(Err(bad_request(
+                locale,
+                Message::new("form-field-not-text").arg("field", name),
+            )));
+        };
+        let text = text.trim().to_string();
+
+        match name.as_str() {
+            "file_id" => form.file_id = Some(text),
+            "prompt" => form.prompt = Some(text).filter(|prompt| !prompt.is_empty()),
+            "duration" => match text.parse() {
+                Ok(duration) => form.duration = Some(duration),
+                Err(_) => return Ok(Err(bad_request(locale, "duration-not-integer"))),
+            },
+            "controls" => match serde_json::from_str(&text) {
+                Ok(controls) => form.controls = controls,
+                Err(e) => {
+                    return Ok(Err(bad_request(
+                        locale,
+                        Message::new("invalid-controls").arg("error", e.to_string()),
+                    )))
+                }
+            },
+            _ => {}
+        }
+    }
+
+    if let Err(message) = form.controls.validate() {
+        return Ok(Err(bad_request(locale, message)));
+    }
+
+    Ok(Ok(form))
+}
+
+/// Load the input clip from the upload or a previously generated song and
+/// convert it to mono 32 kHz WAV. Returns the WAV bytes and their duration.
+/// Post-processed songs in other formats fall back to their raw WAV original.
+async fn load_input_audio(
+    app_state: &AppState,
+    form: &mut ConditioningForm,
+    caller: &Caller,
+    locale: Locale,
+) -> Result<Result<(Vec<u8>, f64), HttpResponse>, Error> {
+    let bytes = match (form.audio.take(), form.file_id.as_deref()) {
+        (Some(_), Some(_)) => return Ok(Err(bad_request(locale, "audio-and-file-id"))),
+        (None, None) => return Ok(Err(bad_request(locale, "audio-missing"))),
+        (Some(bytes), None) => bytes,
+        (None, Some(file_id)) => {
+            let mut path = match resolve_owned_audio(app_state, file_id, caller, locale).await? {
+                Ok(path) => path,
+                Err(response) => return Ok(Err(response)),
+            };
+
+            // Transcoded files can't be decoded here, so condition on the WAV they came from
+            if path.extension().and_then(|ext| ext.to_str()) != Some("wav") {
+                let original = match app_state.audio_cache_service.original_of(file_id).await {
+                    Ok(Some(original)) => original,
+                    Ok(None) => return Ok(Err(bad_request(locale, "conditioning-needs-wav"))),
+                    Err(e) => {
+                        tracing::error!(file_id = %file_id, error = %e, "Error looking up original file");
+                        return Ok(Err(HttpResponse::InternalServerError().json(
+                            serde_json::json!({
+                                "success": false,
+                                "error": locale.text("audio-load-failed")
+                            }),
+                        )));
+                    }
+                };
+                path = match resolve_owned_audio(app_state, &original, caller, locale).await? {
+                    Ok(path) => path,
+                    Err(response) => return Ok(Err(response)),
+                };
+            }
+
+            tokio::fs::read(path).await?
+        }
+    };
+
+    let max_seconds = MAX_AI_MUSIC_DURATION as f64;
+    match web::block(move || audio::prepare_conditioning(&bytes, max_seconds)).await? {
+        Ok(prepared) => Ok(Ok(prepared)),
+        Err(e) => Ok(Err(bad_request(
+            locale,
+            Message::new("invalid-wav").arg("error", e.to_string()),
+        ))),
+    }
+}
+
+/// Record who owns a new song, add it to the library and point its path at the Rust proxy
+async fn finish_song(
+    app_state: &AppState,
+    caller: &Caller,
+    mut song: AiMusicResponse,
+    source: TrackSource,
+    form: &ConditioningForm,
+) -> Result<HttpResponse, Error> {
+    let user_id = caller.user_id.clone();
+    if let Err(e) = app_state
+        .audio_cache_service
+        .record_owner(&song.file_id, user_id.as_deref())
+        .await
+    {
+        tracing::warn!(file_id = %song.file_id, error = %e, "Failed to record file owner");
+    }
+    song.file_path = AudioCacheService::public_path(&song.file_id);
+
+    let mut parameters = NewLibraryTrack::parameters(&form.controls, None);
+    parameters["input_file_id"] = serde_json::json!(form.file_id);
+    app_state
+        .library_service
+        .save(
+            user_id.as_deref(),
+            vec![NewLibraryTrack::from_song(&song, source, parameters)],
+        )
+        .await;
+
+    Ok(HttpResponse::Ok().json(song))
+}
+
+/// Generate a song from a prompt that follows the melody of an uploaded or generated clip
+#[utoipa::path(
+    post,
+    path = "/api/v1/ai-music/melody",
+    tag = "ai-music",
+    security((), ("api_key" = [])),
+    request_body(content = AudioConditioningForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Generated song", body = AiMusicResponse),
+        (status = 400, description = "Invalid form or audio", body = ErrorResponse),
+        (status = 429, description = "Rate limited or daily quota used up", body = ErrorResponse),
+        (status = 500, description = "Generation failed", body = ErrorResponse)
+    )
+)]
+pub async fn generate_from_melody(
+    app_state: web::Data<AppState>,
+    payload: Multipart,
+    caller: Caller,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let mut form = match read_form(payload, locale).await? {
+        Ok(form) => form,
+        Err(response) => return Ok(response),
+    };
+
+    let Some(prompt) = form.prompt.clone() else {
+        return Ok(bad_request(locale, "prompt-empty"));
+    };
+    if let Err(message) = validate_duration(form.duration) {
+        return Ok(bad_request(locale, message));
+    }
+
+    let (melody, _) = match load_input_audio(&app_state, &mut form, &caller, locale).await? {
+        Ok(prepared) => prepared,
+        Err(response) => return Ok(response),
+    };
+
+    match app_state
+        .musicgen_service
+        .generate_with_melody(&prompt, &melody, form.duration, &form.controls)
+        .await
+    {
+        Ok(song) => finish_song(&app_state, &caller, song, TrackSource::Melody, &form).await,
+        Err(e) => {
+            tracing::error!(error = %e, "Error generating melody-conditioned AI music");
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": locale.message(
+                    &Message::new("music-generation-failed").arg("error", e.to_string())
+                )
+            })))
+        }
+    }
+}
+
+/// Extend an uploaded or generated clip to a longer total duration
+#[utoipa::path(
+    post,
+    path = "/api/v1/ai-music/continuations",
+    tag = "ai-music",
+    security((), ("api_key" = [])),
+    request_body(content = AudioConditioningForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Extended song", body = AiMusicResponse),
+        (status = 400, description = "Invalid form or audio", body = ErrorResponse),
+        (status = 429, description = "Rate limited or daily quota used up", body = ErrorResponse),
+        (status = 500, description = "Generation failed", body = ErrorResponse)
+    )
+)]
+pub async fn continue_ai_music(
+    app_state: web::Data<AppState>,
+    payload: Multipart,
+    caller: Caller,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let mut form = match read_form(payload, locale).await? {
+        Ok(form) => form,
+        Err(response) => return Ok(response),
+    };
+
+    if let Err(message) = validate_duration(form.duration) {
+        return Ok(bad_request(locale, message));
+    }
+
+    let (clip, clip_seconds) =
+        match load_input_audio(&app_state, &mut form, &caller, locale).await? {
+            Ok(prepared) => prepared,
+            Err(response) => return Ok(response),
+        };
+
+    let duration = form.duration.unwrap_or_else(|| {
+        (clip_seconds.ceil() as u32 + DEFAULT_CONTINUATION_SECONDS).min(MAX_AI_MUSIC_DURATION)
+    });
+    if duration as f64 <= clip_seconds {
+        return Ok(bad_request(
+            locale,
+            Message::new("duration-shorter-than-clip")
+                .arg("seconds", format!("{:.1}", clip_seconds)),
+        ));
+    }
+
+    match app_state
+        .musicgen_service
+        .continue_song(form.prompt.as_deref(), &clip, duration, &form.controls)
+        .await
+    {
+        Ok(song) => finish_song(&app_state, &caller, song, TrackSource::Continuation, &form).await,
+        Err(e) => {
+            tracing::error!(error = %e, "Error continuing AI music");
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": locale.message(
+                    &Message::new("continuation-failed").arg("error", e.to_string())
+                )
+            })))
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use crate::routes::openapi::ApiDoc;
+use actix_web::{Error, HttpResponse};
+use utoipa::OpenApi;
+
+/// The OpenAPI document for `/api/v1`
+pub async fn openapi_json() -> Result<HttpResponse, Error> {
+    Ok(HttpResponse::Ok().json(ApiDoc::openapi()))
+}
@@ -0,0 +1,44 @@
+use crate::i18n::{Locale, Message};
+use crate::models::health::CheckStatus;
+use crate::AppState;
+use actix_web::{web, Error, HttpResponse};
+
+/// Liveness: answers as long as the server is running
+pub async fn healthz(app_state: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    Ok(HttpResponse::Ok().json(app_state.health_service.liveness()))
+}
+
+/// Readiness: 200 when every configured dependency is reachable, 503 otherwise
+pub async fn readyz(app_state: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    let report = app_state.health_service.readiness().await;
+
+    if report.ready {
+        Ok(HttpResponse::Ok().json(report))
+    } else {
+        Ok(HttpResponse::ServiceUnavailable().json(report))
+    }
+}
+
+/// MusicGen status only, served from the cached readiness checks
+pub async fn ai_music_health_check(
+    app_state: web::Data<AppState>,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let musicgen = app_state.health_service.readiness().await.checks.musicgen;
+
+    if musicgen.status == CheckStatus::Ok {
+        Ok(HttpResponse::Ok().json(serde_json::json!({
+            "status": "ok",
+            "message": locale.text("ai-music-service-running"),
+            "details": musicgen.details
+        })))
+    } else {
+        Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "status": "error",
+            "message": locale.message(
+                &Message::new("ai-music-service-unavailable")
+                    .arg("error", musicgen.message.unwrap_or_default())
+            )
+        })))
+    }
+}
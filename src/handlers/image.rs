@@ -0,0 +1,226 @@
+use crate::i18n::{Locale, Message};
+use crate::middleware::api_key::Caller;
+use crate::models::library::{NewLibraryTrack, TrackSource};
+use crate::models::playlist::{
+    validate_duration, AnalyzeImageQuery, AnalyzeImageResponse, GenerationControls,
+};
+use crate::services::audio_cache_service::AudioCacheService;
+use crate::AppState;
+use actix_multipart::Multipart;
+use actix_web::{web, Error, HttpResponse};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use futures::StreamExt;
+use image::imageops::FilterType;
+use image::ImageOutputFormat;
+use std::io::Cursor;
+
+/// Largest upload accepted before decoding
+const MAX_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+
+/// BLIP works on small inputs, so larger images are shrunk before forwarding
+const MAX_IMAGE_DIMENSION: u32 = 512;
+
+const ALLOWED_IMAGE_TYPES: [&str; 4] = ["image/jpeg", "image/png", "image/webp", "image/gif"];
+
+fn bad_request(locale: Locale, message: impl Into<Message>) -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "success": false,
+        "error": locale.message(&message.into())
+    }))
+}
+
+/// Decode the upload, downscale it and re-encode it as base64 JPEG
+fn prepare_image(bytes: &[u8]) -> Result<String, Message> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| Message::new("invalid-image").arg("error", e.to_string()))?;
+
+    let image = if image.width() > MAX_IMAGE_DIMENSION || image.height() > MAX_IMAGE_DIMENSION {
+        image.resize(
+            MAX_IMAGE_DIMENSION,
+            MAX_IMAGE_DIMENSION,
+            FilterType::Triangle,
+        )
+    } else {
+        image
+    };
+
+    let mut encoded = Cursor::new(Vec::new());
+    image
+        .to_rgb8()
+        .write_to(&mut encoded, ImageOutputFormat::Jpeg(85))
+        .map_err(|e| Message::new("image-encode-failed").arg("error", e.to_string()))?;
+
+    Ok(STANDARD.encode(encoded.into_inner()))
+}
+
+/// Analyze an uploaded image and optionally turn the caption into a playlist or a song
+#[utoipa::path(
+    post,
+    path = "/api/v1/image-analyses",
+    tag = "ai-music",
+    security((), ("api_key" = [])),
+    params(AnalyzeImageQuery),
+    request_body(content = ImageUploadForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Caption and any chained result", body = AnalyzeImageResponse),
+        (status = 400, description = "Invalid upload or query", body = ErrorResponse),
+        (status = 429, description = "Rate limited or daily quota used up", body = ErrorResponse),
+        (status = 500, description = "Analysis or chained generation failed", body = ErrorResponse)
+    )
+)]
+pub async fn analyze_image(
+    app_state: web::Data<AppState>,
+    query: web::Query<AnalyzeImageQuery>,
+    mut payload: Multipart,
+    caller: Caller,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let chain = query.chain.as_deref();
+    if let Some(chain) = chain {
+        if chain != "playlist" && chain != "music" {
+            return Ok(bad_request(locale, "invalid-chain"));
+        }
+    }
+
+    if let Err(message) = validate_duration(query.duration) {
+        return Ok(bad_request(locale, message));
+    }
+
+    let mut image_bytes: Option<Vec<u8>> = None;
+
+    while let Some(field) = payload.next().await {
+        let mut field = field?;
+
+        if field.name() != "image" {
+            continue;
+        }
+
+        let content_type = field
+            .content_type()
+            .map(|mime| mime.essence_str().to_string())
+            .unwrap_or_default();
+        if !ALLOWED_IMAGE_TYPES.contains(&content_type.as_str()) {
+            return Ok(bad_request(locale, "unsupported-image-type"));
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk?;
+            if bytes.len() + chunk.len() > MAX_IMAGE_BYTES {
+                return Ok(bad_request(
+                    locale,
+                    Message::new("image-too-large").arg("megabytes", MAX_IMAGE_BYTES / 1024 / 1024),
+                ));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        image_bytes = Some(bytes);
+    }
+
+    let Some(image_bytes) = image_bytes else {
+        return Ok(bad_request(locale, "image-missing"));
+    };
+
+    let image_base64 = match web::block(move || prepare_image(&image_bytes)).await? {
+        Ok(encoded) => encoded,
+        Err(message) => return Ok(bad_request(locale, message)),
+    };
+
+    let analysis = match app_state
+        .musicgen_service
+        .analyze_image(&image_base64)
+        .await
+    {
+        Ok(analysis) => analysis,
+        Err(e) => {
+            tracing::error!(error = %e, "Error analyzing image");
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": locale.message(
+                    &Message::new("image-analysis-failed").arg("error", e.to_string())
+                )
+            })));
+        }
+    };
+
+    let mut response = AnalyzeImageResponse {
+        success: true,
+        caption: analysis.caption,
+        suggested_prompt: analysis.suggested_prompt,
+        playlist: None,
+        song: None,
+    };
+
+    match chain {
+        Some("playlist") => {
+            match app_state
+                .gemini_service
+                .generate_playlist(&response.suggested_prompt, locale)
+                .await
+            {
+                Ok(playlist) => response.playlist = Some(playlist),
+                Err(e) => {
+                    tracing::error!(error = %e, "Error generating playlist from image");
+                    return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                        "success": false,
+                        "caption": response.caption,
+                        "error": locale.text("playlist-generation-failed")
+                    })));
+                }
+            }
+        }
+        Some("music") => {
+            match app_state
+                .musicgen_service
+                .generate_song(
+                    &response.suggested_prompt,
+                    query.duration,
+                    &GenerationControls::default(),
+                )
+                .await
+            {
+                Ok(mut song) => {
+                    let user_id = caller.user_id.clone();
+                    if let Err(e) = app_state
+                        .audio_cache_service
+                        .record_owner(&song.file_id, user_id.as_deref())
+                        .await
+                    {
+                        tracing::warn!(file_id = %song.file_id, error = %e, "Failed to record file owner");
+                    }
+                    song.file_path = AudioCacheService::public_path(&song.file_id);
+
+                    let parameters = serde_json::json!({ "caption": response.caption });
+                    app_state
+                        .library_service
+                        .save(
+                            user_id.as_deref(),
+                            vec![NewLibraryTrack::from_song(
+                                &song,
+                                TrackSource::Image,
+                                parameters,
+                            )],
+                        )
+                        .await;
+
+                    response.song = Some(song);
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "Error generating AI music from image");
+                    return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                        "success": false,
+                        "caption": response.caption,
+                        "error": locale.message(
+                            &Message::new("music-generation-failed").arg("error", e.to_string())
+                        )
+                    })));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    Ok(HttpResponse::Ok().json(response))
+}
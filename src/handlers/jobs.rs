@@ -0,0 +1,375 @@
+use crate::i18n::{Locale, Message};
+use crate::middleware::api_key::Caller;
+use crate::middleware::rate_limit::charge_prompts;
+use crate::models::job::{Job, JobCreatedResponse, JobKind};
+use crate::models::playlist::{AiMusicBatchRequest, AiMusicRequest};
+use crate::routes::v1::API_V1_PREFIX;
+use crate::AppState;
+use actix_session::Session;
+use actix_web::web::Bytes;
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use tokio::sync::broadcast::error::RecvError;
+
+use super::{validate_ai_music_request, validate_batch_prompts};
+
+pub(crate) fn job_created(req: &HttpRequest, job: &Job) -> HttpResponse {
+    // Point clients at the same API version they submitted through
+    let base = if req.path().starts_with(API_V1_PREFIX) {
+        API_V1_PREFIX
+    } else {
+        ""
+    };
+
+    HttpResponse::Accepted().json(JobCreatedResponse {
+        success: true,
+        job_id: job.id.clone(),
+        status: job.status,
+        status_url: format!("{}/jobs/{}", base, job.id),
+        events_url: format!("{}/jobs/{}/events", base, job.id),
+    })
+}
+
+fn job_not_found(locale: Locale) -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "success": false,
+        "error": locale.text("job-not-found")
+    }))
+}
+
+fn job_load_failed(locale: Locale, e: impl std::fmt::Display) -> HttpResponse {
+    HttpResponse::InternalServerError().json(serde_json::json!({
+        "success": false,
+        "error": locale.message(&Message::new("job-load-failed").arg("error", e.to_string()))
+    }))
+}
+
+/// Session key holding the random ID that anonymous jobs are tied to
+const SESSION_JOB_OWNER_KEY: &str = "job_owner";
+
+/// The session owner to store with a new job: `None` for signed-in callers, otherwise
+/// this browser session's ID, created on first use
+pub(crate) fn session_owner(caller: &Caller, session: &Session) -> Result<Option<String>, Error> {
+    if caller.user_id.is_some() {
+        return Ok(None);
+    }
+
+    if let Some(owner) = session.get::<String>(SESSION_JOB_OWNER_KEY)? {
+        return Ok(Some(owner));
+    }
+
+    let owner = uuid::Uuid::new_v4().to_string();
+    session.insert(SESSION_JOB_OWNER_KEY, &owner)?;
+    Ok(Some(owner))
+}
+
+/// Jobs created by a signed-in user are only visible to that user, and anonymous jobs
+/// only to the browser session that queued them
+fn can_view(job: &Job, caller: &Caller, session: &Session) -> Result<bool, Error> {
+    if let Some(owner) = &job.user_id {
+        return Ok(caller.user_id.as_ref() == Some(owner));
+    }
+
+    // Signed-in callers and API keys never see another session's anonymous jobs
+    if caller.user_id.is_some() {
+        return Ok(false);
+    }
+
+    let current = session.get::<String>(SESSION_JOB_OWNER_KEY)?;
+    Ok(current.is_some() && job.session_owner == current)
+}
+
+fn sse_event(job: &Job) -> Bytes {
+    let payload = serde_json::to_string(job).unwrap_or_else(|_| "{}".to_string());
+    Bytes::from(format!("event: job\ndata: {}\n\n", payload))
+}
+
+/// Queue a single AI song and return the job ID immediately
+#[utoipa::path(
+    post,
+    path = "/api/v1/jobs/ai-music",
+    tag = "jobs",
+    security((), ("api_key" = [])),
+    request_body = AiMusicRequest,
+    responses(
+        (status = 202, description = "Job queued", body = JobCreatedResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 429, description = "Rate limited or daily quota used up", body = ErrorResponse),
+        (status = 500, description = "Job could not be queued", body = ErrorResponse)
+    )
+)]
+pub async fn submit_ai_music_job(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    request: web::Json<AiMusicRequest>,
+    caller: Caller,
+    session: Session,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    if let Err(message) = validate_ai_music_request(&request) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": locale.message(&message)
+        })));
+    }
+
+    let session_owner = session_owner(&caller, &session)?;
+    let user_id = caller.user_id;
+    let payload = serde_json::to_value(request.into_inner())?;
+
+    match app_state
+        .job_service
+        .enqueue(JobKind::Single, payload, user_id, session_owner)
+        .await
+    {
+        Ok(job) => {
+            tracing::info!(job_id = %job.id, "Queued AI music job");
+            Ok(job_created(&req, &job))
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error queueing AI music job");
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": locale.message(
+                    &Message::new("job-queue-failed").arg("error", e.to_string())
+                )
+            })))
+        }
+    }
+}
+
+/// Queue a batch of AI songs and return the job ID immediately
+#[utoipa::path(
+    post,
+    path = "/api/v1/jobs/ai-music-batches",
+    tag = "jobs",
+    security((), ("api_key" = [])),
+    request_body = AiMusicBatchRequest,
+    responses(
+        (status = 202, description = "Job queued", body = JobCreatedResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 429, description = "Rate limited or daily quota used up", body = ErrorResponse),
+        (status = 500, description = "Job could not be queued", body = ErrorResponse)
+    )
+)]
+pub async fn submit_ai_music_batch_job(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    request: web::Json<AiMusicBatchRequest>,
+    caller: Caller,
+    session: Session,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    if let Err(message) = validate_batch_prompts(&request) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": locale.message(&message)
+        })));
+    }
+
+    if let Some(response) = charge_prompts(&req, request.prompts.len(), locale).await {
+        return Ok(response);
+    }
+
+    let session_owner = session_owner(&caller, &session)?;
+    let user_id = caller.user_id;
+    let payload = serde_json::to_value(request.into_inner())?;
+
+    match app_state
+        .job_service
+        .enqueue(JobKind::Batch, payload, user_id, session_owner)
+        .await
+    {
+        Ok(job) => {
+            tracing::info!(job_id = %job.id, "Queued AI music batch job");
+            Ok(job_created(&req, &job))
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error queueing AI music batch job");
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": locale.message(
+                    &Message::new("batch-job-queue-failed").arg("error", e.to_string())
+                )
+            })))
+        }
+    }
+}
+
+/// Poll the current state of a job
+#[utoipa::path(
+    get,
+    path = "/api/v1/jobs/{id}",
+    tag = "jobs",
+    security((), ("api_key" = [])),
+    params(("id" = String, Path, description = "Job ID")),
+    responses(
+        (status = 200, description = "Current job state", body = Job),
+        (status = 404, description = "Unknown job or owned by another user", body = ErrorResponse),
+        (status = 500, description = "Job could not be loaded", body = ErrorResponse)
+    )
+)]
+pub async fn get_job(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    caller: Caller,
+    session: Session,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let job_id = path.into_inner();
+
+    match app_state.job_service.get(&job_id).await {
+        Ok(Some(job)) if can_view(&job, &caller, &session)? => Ok(HttpResponse::Ok().json(job)),
+        Ok(_) => Ok(job_not_found(locale)),
+        Err(e) => {
+            tracing::error!(job_id = %job_id, error = %e, "Error loading job");
+            Ok(job_load_failed(locale, e))
+        }
+    }
+}
+
+/// Requeue a failed job, or only the failed prompts of a finished batch
+#[utoipa::path(
+    post,
+    path = "/api/v1/jobs/{id}/retry",
+    tag = "jobs",
+    security((), ("api_key" = [])),
+    params(("id" = String, Path, description = "Job ID")),
+    responses(
+        (status = 202, description = "Job requeued", body = JobCreatedResponse),
+        (status = 404, description = "Unknown job or owned by another user", body = ErrorResponse),
+        (status = 409, description = "Nothing to retry", body = ErrorResponse),
+        (status = 429, description = "Rate limited or daily quota used up", body = ErrorResponse),
+        (status = 500, description = "Job could not be requeued", body = ErrorResponse)
+    )
+)]
+pub async fn retry_job(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    caller: Caller,
+    session: Session,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let job_id = path.into_inner();
+
+    let job = match app_state.job_service.get(&job_id).await {
+        Ok(Some(job)) if can_view(&job, &caller, &session)? => job,
+        Ok(_) => return Ok(job_not_found(locale)),
+        Err(e) => {
+            tracing::error!(job_id = %job_id, error = %e, "Error loading job");
+            return Ok(job_load_failed(locale, e));
+        }
+    };
+
+    let nothing_to_retry = || {
+        HttpResponse::Conflict().json(serde_json::json!({
+            "success": false,
+            "error": locale.text("nothing-to-retry")
+        }))
+    };
+
+    if !job.is_retryable() {
+        return Ok(nothing_to_retry());
+    }
+
+    let prompts = match app_state.job_service.retry_prompts(&job).await {
+        Ok(prompts) => prompts,
+        Err(e) => {
+            tracing::error!(job_id = %job_id, error = %e, "Error loading job");
+            return Ok(job_load_failed(locale, e));
+        }
+    };
+    if let Some(response) = charge_prompts(&req, prompts, locale).await {
+        return Ok(response);
+    }
+
+    match app_state.job_service.retry(&job_id).await {
+        Ok(Some(job)) => {
+            tracing::info!(job_id = %job.id, "Retrying AI music job");
+            Ok(job_created(&req, &job))
+        }
+        Ok(None) => Ok(nothing_to_retry()),
+        Err(e) => {
+            tracing::error!(job_id = %job_id, error = %e, "Error retrying job");
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": locale.message(
+                    &Message::new("job-retry-failed").arg("error", e.to_string())
+                )
+            })))
+        }
+    }
+}
+
+/// Stream job updates as Server-Sent Events until the job finishes
+#[utoipa::path(
+    get,
+    path = "/api/v1/jobs/{id}/events",
+    tag = "jobs",
+    security((), ("api_key" = [])),
+    params(("id" = String, Path, description = "Job ID")),
+    responses(
+        (
+            status = 200,
+            description = "`job` events carrying the Job JSON, ending when it finishes",
+            body = String,
+            content_type = "text/event-stream"
+        ),
+        (status = 404, description = "Unknown job or owned by another user", body = ErrorResponse),
+        (status = 500, description = "Job could not be loaded", body = ErrorResponse)
+    )
+)]
+pub async fn job_events(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    caller: Caller,
+    session: Session,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let job_id = path.into_inner();
+
+    // Subscribe before reading the current state so no update is missed in between
+    let receiver = app_state.job_service.subscribe();
+
+    let job = match app_state.job_service.get(&job_id).await {
+        Ok(Some(job)) if can_view(&job, &caller, &session)? => job,
+        Ok(_) => return Ok(job_not_found(locale)),
+        Err(e) => {
+            tracing::error!(job_id = %job_id, error = %e, "Error loading job");
+            return Ok(job_load_failed(locale, e));
+        }
+    };
+
+    let stream = futures::stream::unfold(
+        (Some(job), receiver, false),
+        move |(pending, mut receiver, finished)| {
+            let job_id = job_id.clone();
+            async move {
+                if finished {
+                    return None;
+                }
+
+                if let Some(job) = pending {
+                    let finished = job.status.is_finished();
+                    return Some((Ok::<_, Error>(sse_event(&job)), (None, receiver, finished)));
+                }
+
+                loop {
+                    match receiver.recv().await {
+                        Ok(job) if job.id == job_id => {
+                            let finished = job.status.is_finished();
+                            return Some((Ok(sse_event(&job)), (None, receiver, finished)));
+                        }
+                        Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            }
+        },
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(stream))
+}
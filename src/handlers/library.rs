@@ -0,0 +1,176 @@
+use crate::i18n::{Locale, Message};
+use crate::models::library::{
+    LibraryDeleteResponse, LibraryListResponse, LibraryQuery, LibraryUpdate,
+};
+use crate::AppState;
+use actix_session::Session;
+use actix_web::{web, Error, HttpResponse};
+
+fn login_required(locale: Locale) -> HttpResponse {
+    HttpResponse::Unauthorized().json(serde_json::json!({
+        "success": false,
+        "error": locale.text("login-required-library")
+    }))
+}
+
+fn track_not_found(locale: Locale) -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "success": false,
+        "error": locale.text("library-track-not-found")
+    }))
+}
+
+fn library_error(locale: Locale, action: &'static str, e: impl std::fmt::Display) -> HttpResponse {
+    tracing::error!(error = %e, "Error trying to {} library", action);
+    HttpResponse::InternalServerError().json(serde_json::json!({
+        "success": false,
+        "error": locale.message(&Message::new("library-failed").arg("action", action))
+    }))
+}
+
+/// List the signed-in user's generated songs, newest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/library",
+    tag = "library",
+    params(LibraryQuery),
+    responses(
+        (status = 200, description = "One page of tracks", body = LibraryListResponse),
+        (status = 401, description = "Not signed in with Spotify", body = ErrorResponse),
+        (status = 500, description = "Library could not be loaded", body = ErrorResponse)
+    )
+)]
+pub async fn list_library(
+    app_state: web::Data<AppState>,
+    query: web::Query<LibraryQuery>,
+    session: Session,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(user_id) = session.get::<String>("spotify_user_id")? else {
+        return Ok(login_required(locale));
+    };
+
+    match app_state.library_service.list(&user_id, &query).await {
+        Ok((total, tracks)) => Ok(HttpResponse::Ok().json(LibraryListResponse {
+            success: true,
+            total,
+            tracks,
+        })),
+        Err(e) => Ok(library_error(locale, "load", e)),
+    }
+}
+
+/// Fetch one track from the signed-in user's library
+#[utoipa::path(
+    get,
+    path = "/api/v1/library/{file_id}",
+    tag = "library",
+    params(("file_id" = String, Path, description = "ID of a generated song")),
+    responses(
+        (status = 200, description = "The track", body = LibraryTrack),
+        (status = 401, description = "Not signed in with Spotify", body = ErrorResponse),
+        (status = 404, description = "Not in the user's library", body = ErrorResponse),
+        (status = 500, description = "Library could not be loaded", body = ErrorResponse)
+    )
+)]
+pub async fn get_library_track(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    session: Session,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(user_id) = session.get::<String>("spotify_user_id")? else {
+        return Ok(login_required(locale));
+    };
+
+    match app_state.library_service.get(&user_id, &path).await {
+        Ok(Some(track)) => Ok(HttpResponse::Ok().json(track)),
+        Ok(None) => Ok(track_not_found(locale)),
+        Err(e) => Ok(library_error(locale, "load", e)),
+    }
+}
+
+/// Mark a track as a favorite or rename it
+#[utoipa::path(
+    patch,
+    path = "/api/v1/library/{file_id}",
+    tag = "library",
+    params(("file_id" = String, Path, description = "ID of a generated song")),
+    request_body = LibraryUpdate,
+    responses(
+        (status = 200, description = "The updated track", body = LibraryTrack),
+        (status = 400, description = "Invalid title", body = ErrorResponse),
+        (status = 401, description = "Not signed in with Spotify", body = ErrorResponse),
+        (status = 404, description = "Not in the user's library", body = ErrorResponse),
+        (status = 500, description = "Library could not be updated", body = ErrorResponse)
+    )
+)]
+pub async fn update_library_track(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    update: web::Json<LibraryUpdate>,
+    session: Session,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(user_id) = session.get::<String>("spotify_user_id")? else {
+        return Ok(login_required(locale));
+    };
+
+    if let Some(title) = &update.title {
+        if title.trim().is_empty() || title.len() > 200 {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": locale.message(
+                    &Message::new("field-length").arg("field", "title").arg("max", 200)
+                )
+            })));
+        }
+    }
+
+    match app_state
+        .library_service
+        .update(&user_id, &path, &update)
+        .await
+    {
+        Ok(Some(track)) => Ok(HttpResponse::Ok().json(track)),
+        Ok(None) => Ok(track_not_found(locale)),
+        Err(e) => Ok(library_error(locale, "update", e)),
+    }
+}
+
+/// Remove a track from the library and delete its audio files
+#[utoipa::path(
+    delete,
+    path = "/api/v1/library/{file_id}",
+    tag = "library",
+    params(("file_id" = String, Path, description = "ID of a generated song")),
+    responses(
+        (status = 200, description = "Track deleted", body = LibraryDeleteResponse),
+        (status = 401, description = "Not signed in with Spotify", body = ErrorResponse),
+        (status = 404, description = "Not in the user's library", body = ErrorResponse),
+        (status = 500, description = "Library could not be updated", body = ErrorResponse)
+    )
+)]
+pub async fn delete_library_track(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    session: Session,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(user_id) = session.get::<String>("spotify_user_id")? else {
+        return Ok(login_required(locale));
+    };
+
+    match app_state
+        .library_service
+        .delete(&user_id, &path, app_state.musicgen_service.as_ref())
+        .await
+    {
+        Ok(true) => Ok(HttpResponse::Ok().json(LibraryDeleteResponse {
+            success: true,
+            file_id: path.into_inner(),
+        })),
+        Ok(false) => Ok(track_not_found(locale)),
+        Err(e) => Ok(library_error(locale, "update", e)),
+    }
+}
@@ -0,0 +1,28 @@
+use crate::metrics::{self, MUSICGEN_JOBS, PENDING_OAUTH_HANDOFFS};
+use crate::AppState;
+use actix_web::{web, Error, HttpResponse};
+
+/// Prometheus scrape endpoint
+pub async fn metrics(app_state: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    // Gauges describing current state are refreshed on each scrape
+    let pending = app_state.pending_tracks.lock().unwrap().len();
+    PENDING_OAUTH_HANDOFFS.set(pending as i64);
+
+    match app_state.job_service.count_active().await {
+        Ok((queued, running)) => {
+            MUSICGEN_JOBS.with_label_values(&["queued"]).set(queued);
+            MUSICGEN_JOBS.with_label_values(&["running"]).set(running);
+        }
+        Err(e) => tracing::warn!(error = %e, "Failed to count AI music jobs for metrics"),
+    }
+
+    match metrics::render() {
+        Ok(body) => Ok(HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4")
+            .body(body)),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to encode metrics");
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
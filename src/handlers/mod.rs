@@ -1,171 +1,97 @@
+pub mod album;
+pub mod api_keys;
+pub mod audio;
+pub mod conditioning;
+pub mod docs;
+pub mod health;
+pub mod image;
+pub mod jobs;
+pub mod library;
+pub mod metrics;
+pub mod playlist_history;
+pub mod playlist_seed;
+pub mod share;
 pub mod statistics;
 pub mod success;
-
+pub mod users;
+
+use crate::config::SpotifyConfig;
+use crate::i18n::{Locale, Message, SESSION_LANGUAGE_KEY};
+use crate::metrics::SPOTIFY_TRACK_SEARCHES_TOTAL;
+use crate::middleware::api_key::Caller;
+use crate::middleware::rate_limit::charge_prompts;
+use crate::models::library::{NewLibraryTrack, TrackSource};
 use crate::models::playlist::*;
+use crate::models::playlist_history::PlaylistGeneration;
+use crate::services::audio_cache_service::AudioCacheService;
+use crate::services::music_generator;
+use crate::services::spotify_playlist_service::SpotifyPlaylistService;
 use crate::AppState;
 use actix_session::Session;
-use actix_web::{web, Error, HttpResponse};
+use actix_web::{web, Error, HttpRequest, HttpResponse};
 use rspotify::{
-    model::{PlayableId, SearchResult, SearchType},
+    model::{SearchResult, SearchType},
     prelude::*,
-    scopes, AuthCodeSpotify, Credentials, OAuth,
+    scopes, AuthCodeSpotify, OAuth,
 };
-use serde::{Deserialize, Serialize};
-use std::env;
-use uuid::Uuid;
-
-const SUCCESS_HTML: &str = r#"<!DOCTYPE html>
-<html>
-<head>
-    <title>Success!</title>
-    <meta charset="UTF-8">
-    <link href="https://fonts.googleapis.com/css2?family=Roboto:wght@300;400;500;700&display=swap" rel="stylesheet">
-    <style>
-        * {
-            margin: 0;
-            padding: 0;
-            box-sizing: border-box;
-        }
-        body {
-            font-family: 'Roboto', sans-serif;
-            background: linear-gradient(135deg, #1DB954 0%, #191414 100%);
-            min-height: 100vh;
-            display: flex;
-            align-items: center;
-            justify-content: center;
-            color: white;
-        }
-        .container {
-            background: rgba(255, 255, 255, 0.1);
-            backdrop-filter: blur(10px);
-            padding: 40px;
-            border-radius: 16px;
-            text-align: center;
-            box-shadow: 0 8px 32px rgba(0, 0, 0, 0.1);
-            max-width: 600px;
-            width: 90%;
-            animation: fadeIn 0.5s ease-out;
-        }
-        @keyframes fadeIn {
-            from { opacity: 0; transform: translateY(20px); }
-            to { opacity: 1; transform: translateY(0); }
-        }
-        h1 {
-            font-size: 2.5em;
-            margin-bottom: 20px;
-            color: #1DB954;
-        }
-        p {
-            font-size: 1.2em;
-            margin-bottom: 30px;
-            line-height: 1.6;
-        }
-        .button {
-            display: inline-block;
-            padding: 12px 24px;
-            background: #1DB954;
-            color: white;
-            text-decoration: none;
-            border-radius: 50px;
-            font-weight: 500;
-            margin: 10px;
-            transition: all 0.3s ease;
-            border: none;
-            cursor: pointer;
-        }
-        .button:hover {
-            transform: translateY(-2px);
-            box-shadow: 0 5px 15px rgba(29, 185, 84, 0.3);
-            background: #1ed760;
-        }
-    </style>
-</head>
-<body>
-    <div class="container">
-        <h1>✨ Success!</h1>
-        <p>Your new playlist has been created in your Spotify account.</p>
-        <a href="{}" class="button" target="_blank">Open in Spotify</a>
-        <a href="/" class="button">Create Another Playlist</a>
-    </div>
-</body>
-</html>"#;
-
-const ERROR_HTML: &str = r#"<!DOCTYPE html>
-<html>
-<head>
-    <title>Error</title>
-    <link href="https://fonts.googleapis.com/css2?family=Roboto:wght@300;400;500;700&display=swap" rel="stylesheet">
-    <style>
-        * {
-            margin: 0;
-            padding: 0;
-            box-sizing: border-box;
-        }
-        body {
-            font-family: 'Roboto', sans-serif;
-            background: linear-gradient(135deg, #ff4b4b 0%, #191414 100%);
-            min-height: 100vh;
-            display: flex;
-            align-items: center;
-            justify-content: center;
-            color: white;
-        }
-        .container {
-            background: rgba(255, 255, 255, 0.1);
-            backdrop-filter: blur(10px);
-            padding: 40px;
-            border-radius: 16px;
-            text-align: center;
-            box-shadow: 0 8px 32px rgba(0, 0, 0, 0.1);
-            max-width: 600px;
-            width: 90%;
-            animation: fadeIn 0.5s ease-out;
-        }
-        @keyframes fadeIn {
-            from { opacity: 0; transform: translateY(20px); }
-            to { opacity: 1; transform: translateY(0); }
-        }
-        h1 {
-            font-size: 2.5em;
-            margin-bottom: 20px;
-            color: #ff4b4b;
-        }
-        p {
-            font-size: 1.2em;
-            margin-bottom: 30px;
-            line-height: 1.6;
-        }
-        .button {
-            display: inline-block;
-            padding: 12px 24px;
-            background: #ff4b4b;
-            color: white;
-            text-decoration: none;
-            border-radius: 50px;
-            font-weight: 500;
-            transition: all 0.3s ease;
-        }
-        .button:hover {
-            transform: translateY(-2px);
-            box-shadow: 0 5px 15px rgba(255, 75, 75, 0.3);
-            background: #ff6b6b;
-        }
-    </style>
-</head>
-<body>
-    <div class="container">
-        <h1>❌ Error</h1>
-        <p>{}</p>
-        <p>{}</p>
-        <a href="/" class="button">Try Again</a>
-    </div>
-</body>
-</html>"#;
-
-pub async fn index() -> impl actix_web::Responder {
+use tracing::Instrument;
+
+/// Catalog messages the main page shows. Each fills its `{{id}}` placeholder in the
+/// markup and is also handed to the page script as `MESSAGES[id]`.
+const INDEX_MESSAGES: [&str; 31] = [
+    "index-title",
+    "index-describe-heading",
+    "index-describe-intro",
+    "index-prompt-placeholder",
+    "index-generate",
+    "index-customize-heading",
+    "index-playlist-name",
+    "index-playlist-name-placeholder",
+    "index-playlist-description",
+    "index-playlist-description-placeholder",
+    "index-create-playlist",
+    "index-crafting",
+    "index-history-heading",
+    "index-history-intro",
+    "index-view-history",
+    "index-prompt-missing",
+    "index-ai-crafting",
+    "index-prompt-failed",
+    "index-connecting-spotify",
+    "index-popup-blocked",
+    "index-auth-window-closed",
+    "index-auth-url-missing",
+    "index-history-connect-failed",
+    "index-retry",
+    "index-no-recent-tracks",
+    "index-no-recent-tracks-hint",
+    "index-suggested-songs",
+    "index-generate-first",
+    "index-playlist-name-missing",
+    "index-creating-playlist",
+    "index-create-playlist-failed",
+];
+
+pub async fn index(locale: Locale) -> impl actix_web::Responder {
     HttpResponse::Ok()
-        .content_type("text/html")
-        .body(include_str!("../templates/index.html"))
+        .content_type("text/html; charset=utf-8")
+        .body(render_index(locale))
+}
+
+fn render_index(locale: Locale) -> String {
+    let mut page = include_str!("../templates/index.html").replace("{{lang}}", locale.tag());
+    let mut messages = serde_json::Map::new();
+
+    for id in INDEX_MESSAGES {
+        let text = locale.text(id);
+        page = page.replace(&format!("{{{{{}}}}}", id), &escape_html(&text));
+        messages.insert(id.to_string(), text.into());
+    }
+
+    page.replace(
+        "{{messages}}",
+        &success::script_json(&serde_json::Value::Object(messages)),
+    )
 }
 
 pub async fn recommend_songs(_req: web::Json<RecommendationRequest>) -> impl actix_web::Responder {
@@ -173,49 +99,60 @@ pub async fn recommend_songs(_req: web::Json<RecommendationRequest>) -> impl act
     HttpResponse::Ok().json(serde_json::json!({"message": "Not implemented"}))
 }
 
+/// Escape text for use in HTML content and attribute values
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Spotify calls are impossible without the app's client credentials
+fn spotify_not_configured(locale: Locale) -> HttpResponse {
+    tracing::error!("Spotify credentials are not configured");
+    HttpResponse::ServiceUnavailable().json(serde_json::json!({
+        "success": false,
+        "error": locale.text("spotify-not-configured")
+    }))
+}
+
 pub async fn spotify_callback(
     data: web::Data<AppState>,
     query: web::Query<CallbackQuery>,
     session: Session,
+    locale: Locale,
 ) -> Result<HttpResponse, Error> {
-    println!("Received callback query: {:?}", query);
+    tracing::info!(
+        for_history = query.for_history.unwrap_or(false),
+        has_state = query.state.is_some(),
+        "Received Spotify callback"
+    );
+
+    let Some(spotify_config) = data.spotify_config.as_ref() else {
+        return Ok(spotify_not_configured(locale));
+    };
 
     if query.for_history.unwrap_or(false) {
-        return handle_history_callback(&query.code).await;
+        return handle_history_callback(spotify_config, &query.code, locale).await;
     }
 
     // Try to get session ID from state parameter first
     let session_id = if let Some(state) = &query.state {
-        println!("Found state parameter: {}", state);
+        tracing::debug!(session_id = %state, "Found state parameter");
         state.clone()
     } else {
         match session.get::<String>("tracks_session_id")? {
             Some(id) => {
-                println!("Found session ID in cookie: {}", id);
+                tracing::debug!(session_id = %id, "Found session ID in cookie");
                 id
             }
             None => {
-                eprintln!("No session ID found in cookie or state");
-                return Ok(HttpResponse::Ok().content_type("text/html").body(format!(
-                    r#"
-                    <!DOCTYPE html>
-                    <html>
-                    <head>
-                        <title>Error</title>
-                        <script>
-                            window.opener.postMessage({{
-                                type: "PLAYLIST_ERROR",
-                                error: "Session expired. Please try again."
-                            }}, "*");
-                            window.close();
-                        </script>
-                    </head>
-                    <body>
-                        <p>Session expired...</p>
-                    </body>
-                    </html>
-                    "#
-                )));
+                tracing::warn!("No session ID found in cookie or state");
+                return Ok(success::render_error_page(
+                    locale,
+                    &locale.text("session-expired"),
+                ));
             }
         }
     };
@@ -225,58 +162,30 @@ pub async fn spotify_callback(
         let pending_tracks = data.pending_tracks.lock().unwrap();
         match pending_tracks.get(&session_id) {
             Some(request) => {
-                println!(
-                    "Found tracks in pending_tracks for session ID: {}",
-                    session_id
-                );
+                tracing::debug!(session_id = %session_id, "Found pending tracks");
                 Some(request.clone())
             }
             None => {
-                eprintln!(
-                    "No tracks found in pending_tracks for session ID: {}",
-                    session_id
-                );
-                return Ok(HttpResponse::Ok().content_type("text/html").body(format!(
-                    r#"
-                    <!DOCTYPE html>
-                    <html>
-                    <head>
-                        <title>Error</title>
-                        <script>
-                            window.opener.postMessage({{
-                                type: "PLAYLIST_ERROR",
-                                error: "No playlist data found. Please try again."
-                            }}, "*");
-                            window.close();
-                        </script>
-                    </head>
-                    <body>
-                        <p>No playlist data found...</p>
-                    </body>
-                    </html>
-                    "#
-                )));
+                tracing::warn!(session_id = %session_id, "No pending tracks for session");
+                return Ok(success::render_error_page(
+                    locale,
+                    &locale.text("no-pending-playlist"),
+                ));
             }
         }
     };
 
     match playlist_request {
         Some(request) if !request.tracks.is_empty() => {
-            println!(
-                "Processing playlist request with {} tracks",
-                request.tracks.len()
-            );
+            tracing::info!(tracks = request.tracks.len(), "Processing playlist request");
 
-            let spotify_client_id = env::var("SPOTIFY_CLIENT_ID").expect("SPOTIFY_CLIENT_ID must be set");
-            let spotify_client_secret = env::var("SPOTIFY_CLIENT_SECRET").expect("SPOTIFY_CLIENT_SECRET must be set");
-            let spotify_redirect_uri = env::var("SPOTIFY_REDIRECT_URI").expect("SPOTIFY_REDIRECT_URI must be set");
-
-            let creds = Credentials::new(&spotify_client_id, &spotify_client_secret);
+            let creds = spotify_config.credentials();
             let oauth = OAuth {
-                redirect_uri: spotify_redirect_uri.clone(),
+                redirect_uri: spotify_config.redirect_uri.clone(),
                 scopes: scopes!(
                     "playlist-modify-public",
                     "playlist-modify-private",
+                    "ugc-image-upload",
                     "user-read-private",
                     "user-read-email"
                 ),
@@ -286,39 +195,71 @@ pub async fn spotify_callback(
 
             let spotify = AuthCodeSpotify::new(creds, oauth);
 
-            match spotify.request_token(&query.code).await {
+            match spotify
+                .request_token(&query.code)
+                .instrument(tracing::info_span!("spotify.request_token"))
+                .await
+            {
                 Ok(()) => {
                     // Clean up session data after successful token exchange
                     if let Some(session_id) = session.get::<String>("tracks_session_id")? {
                         let mut pending_tracks = data.pending_tracks.lock().unwrap();
                         pending_tracks.remove(&session_id);
-                        println!("Cleaned up session data for ID: {}", session_id);
+                        tracing::debug!(session_id = %session_id, "Cleaned up session data");
                     }
                     session.remove("tracks_session_id");
 
                     // Get user profile and create playlist
-                    match spotify.me().await {
+                    match spotify
+                        .me()
+                        .instrument(tracing::info_span!("spotify.me"))
+                        .await
+                    {
                         Ok(user) => {
-                            match spotify
-                                .user_playlist_create(
-                                    user.id,
-                                    &request.playlist_name,
-                                    Some(false), // not public
-                                    Some(false), // not collaborative
-                                    request.playlist_description.as_deref(),
-                                )
-                                .await
+                            // Remember who this is so AI quotas can be tracked per user
+                            session.insert("spotify_user_id", user.id.id())?;
+
+                            // A failure here must not cost the user their playlist
+                            let mut locale = locale;
+                            let public_by_default =
+                                match data.user_service.record_login(&user).await {
+                                    Ok(account) => {
+                                        if let Some(language) = &account.preferences.language {
+                                            session.insert(SESSION_LANGUAGE_KEY, language)?;
+                                            locale = Locale::negotiate(Some(language), None);
+                                        }
+                                        account.preferences.public_playlists.unwrap_or(false)
+                                    }
+                                    Err(e) => {
+                                        tracing::warn!(error = %e, "Failed to record user login");
+                                        false
+                                    }
+                                };
+
+                            let action = if request.target_playlist.is_some() {
+                                "update"
+                            } else {
+                                "create"
+                            };
+
+                            match SpotifyPlaylistService::open_playlist(
+                                &spotify,
+                                user.id,
+                                &request,
+                                public_by_default,
+                            )
+                            .instrument(tracing::info_span!("spotify.open_playlist", action))
+                            .await
                             {
                                 Ok(playlist) => {
                                     let mut spotify_track_ids = Vec::new();
+                                    let mut album_art = Vec::new();
+                                    let mut matched = Vec::new();
+                                    let mut missing = Vec::new();
 
                                     // Search for each track
                                     for track in request.tracks {
-                                        println!(
-                                            "Searching for track: {} by {}",
-                                            track.name, track.artist
-                                        );
-                                        if let Ok(SearchResult::Tracks(page)) = spotify
+                                        let search = spotify
                                             .search(
                                                 &format!(
                                                     "track:{} artist:{}",
@@ -330,77 +271,123 @@ pub async fn spotify_callback(
                                                 Some(1),
                                                 None,
                                             )
-                                            .await
-                                        {
-                                            if let Some(found_track) = page.items.first() {
-                                                if let Some(track_id) = &found_track.id {
-                                                    spotify_track_ids.push(track_id.clone());
-                                                    println!("Found track: {}", found_track.name);
-                                                }
+                                            .instrument(tracing::info_span!(
+                                                "spotify.search",
+                                                track = %track.name,
+                                                artist = %track.artist
+                                            ))
+                                            .await;
+
+                                        let found = match &search {
+                                            Ok(SearchResult::Tracks(page)) => {
+                                                page.items.first().and_then(|found_track| {
+                                                    found_track
+                                                        .id
+                                                        .clone()
+                                                        .map(|id| (id, found_track))
+                                                })
                                             }
-                                        }
+                                            _ => None,
+                                        };
+                                        let result = match (&search, &found) {
+                                            (Err(_), _) => "error",
+                                            (_, Some(_)) => "hit",
+                                            (_, None) => "miss",
+                                        };
+                                        SPOTIFY_TRACK_SEARCHES_TOTAL
+                                            .with_label_values(&[result])
+                                            .inc();
+
+                                        let Some((track_id, found_track)) = found else {
+                                            missing.push(track);
+                                            continue;
+                                        };
+
+                                        spotify_track_ids.push(track_id);
+                                        // Spotify lists album images largest first
+                                        let images = &found_track.album.images;
+                                        album_art.extend(images.first().map(|i| i.url.clone()));
+                                        matched.push(success::MatchedTrack {
+                                            name: found_track.name.clone(),
+                                            artist: found_track
+                                                .artists
+                                                .iter()
+                                                .map(|artist| artist.name.as_str())
+                                                .collect::<Vec<_>>()
+                                                .join(", "),
+                                            album_art: images.last().map(|i| i.url.clone()),
+                                            url: found_track
+                                                .external_urls
+                                                .get("spotify")
+                                                .cloned()
+                                                .unwrap_or_default(),
+                                        });
+                                        tracing::debug!(
+                                            track = %found_track.name,
+                                            "Found track"
+                                        );
                                     }
 
                                     if spotify_track_ids.is_empty() {
-                                        return Ok(HttpResponse::Ok().content_type("text/html").body(format!(
-                                            r#"
-                                            <!DOCTYPE html>
-                                            <html>
-                                            <head>
-                                                <title>Error</title>
-                                                <script>
-                                                    window.opener.postMessage({{
-                                                        type: "PLAYLIST_ERROR",
-                                                        error: "Could not find any matching tracks on Spotify"
-                                                    }}, "*");
-                                                    window.close();
-                                                </script>
-                                            </head>
-                                            <body>
-                                                <p>Redirecting back...</p>
-                                            </body>
-                                            </html>
-                                            "#
-                                        )));
+                                        return Ok(success::render_error_page(
+                                            locale,
+                                            &locale.text("no-matching-tracks"),
+                                        ));
                                     }
 
-                                    // Add tracks in batches
-                                    for chunk in spotify_track_ids.chunks(100) {
-                                        let track_ids: Vec<PlayableId> = chunk
-                                            .iter()
-                                            .map(|id| PlayableId::Track(id.clone()))
-                                            .collect();
-
-                                        if let Err(e) = spotify
-                                            .playlist_add_items(
-                                                playlist.id.clone(),
-                                                track_ids,
-                                                None,
-                                            )
-                                            .await
-                                        {
-                                            eprintln!("Error adding tracks to playlist: {}", e);
-                                            return Ok(HttpResponse::Ok().content_type("text/html").body(format!(
-                                                r#"
-                                                <!DOCTYPE html>
-                                                <html>
-                                                <head>
-                                                    <title>Error</title>
-                                                    <script>
-                                                        window.opener.postMessage({{
-                                                            type: "PLAYLIST_ERROR",
-                                                            error: "Failed to add tracks to playlist: {}"
-                                                        }}, "*");
-                                                        window.close();
-                                                    </script>
-                                                </head>
-                                                <body>
-                                                    <p>Redirecting back...</p>
-                                                </body>
-                                                </html>
-                                                "#,
-                                                e
-                                            )));
+                                    let written = SpotifyPlaylistService::write_tracks(
+                                        &spotify,
+                                        playlist.id.as_ref(),
+                                        &spotify_track_ids,
+                                        request.mode,
+                                    )
+                                    .instrument(tracing::info_span!("spotify.add_tracks"))
+                                    .await;
+
+                                    let added = match written {
+                                        Ok(added) => added,
+                                        Err(e) => {
+                                            tracing::error!(
+                                                error = %e,
+                                                "Error adding tracks to playlist"
+                                            );
+                                            return Ok(success::render_error_page(
+                                                locale,
+                                                &locale.message(
+                                                    &Message::new("add-tracks-failed")
+                                                        .arg("error", e.to_string()),
+                                                ),
+                                            ));
+                                        }
+                                    };
+
+                                    // New playlists get a rendered cover unless asked not to
+                                    let cover_style = request.cover.unwrap_or(
+                                        if request.target_playlist.is_some() {
+                                            CoverStyle::None
+                                        } else {
+                                            CoverStyle::Gradient
+                                        },
+                                    );
+                                    if cover_style != CoverStyle::None {
+                                        let uploaded = async {
+                                            let image = data
+                                                .cover_service
+                                                .render(cover_style, &playlist.name, &album_art)
+                                                .await?;
+                                            data.cover_service
+                                                .upload(&spotify, playlist.id.as_ref(), image)
+                                                .await
+                                        }
+                                        .instrument(tracing::info_span!("spotify.upload_cover"))
+                                        .await;
+
+                                        // The tracks are in place, so a missing cover is not fatal
+                                        if let Err(e) = uploaded {
+                                            tracing::warn!(
+                                                error = %e,
+                                                "Failed to upload playlist cover"
+                                            );
                                         }
                                     }
 
@@ -410,275 +397,87 @@ pub async fn spotify_callback(
                                         .map(String::as_str)
                                         .unwrap_or("");
 
-                                    println!("Successfully created playlist: {}", playlist_url);
-                                    Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(format!(
-                                        r#"
-                                        <!DOCTYPE html>
-                                        <html>
-                                        <head>
-                                            <title>Playlist Oluşturuldu!</title>
-                                            <meta charset="UTF-8">
-                                            <link href="https://fonts.googleapis.com/css2?family=Roboto:wght@300;400;500;700&display=swap" rel="stylesheet">
-                                            <style>
-                                                * {{
-                                                    margin: 0;
-                                                    padding: 0;
-                                                    box-sizing: border-box;
-                                                }}
-                                                body {{
-                                                    font-family: 'Roboto', sans-serif;
-                                                    background: linear-gradient(135deg, #1DB954 0%, #191414 100%);
-                                                    min-height: 100vh;
-                                                    display: flex;
-                                                    align-items: center;
-                                                    justify-content: center;
-                                                    color: white;
-                                                }}
-                                                .container {{
-                                                    background: rgba(255, 255, 255, 0.1);
-                                                    backdrop-filter: blur(10px);
-                                                    padding: 40px;
-                                                    border-radius: 16px;
-                                                    text-align: center;
-                                                    box-shadow: 0 8px 32px rgba(0, 0, 0, 0.1);
-                                                    max-width: 600px;
-                                                    width: 90%;
-                                                    animation: fadeIn 0.5s ease-out;
-                                                }}
-                                                @keyframes fadeIn {{
-                                                    from {{ opacity: 0; transform: translateY(20px); }}
-                                                    to {{ opacity: 1; transform: translateY(0); }}
-                                                }}
-                                                .success-icon {{
-                                                    font-size: 64px;
-                                                    margin-bottom: 20px;
-                                                    animation: bounce 1s ease infinite;
-                                                }}
-                                                @keyframes bounce {{
-                                                    0%, 100% {{ transform: translateY(0); }}
-                                                    50% {{ transform: translateY(-10px); }}
-                                                }}
-                                                h1 {{
-                                                    font-size: 2.5em;
-                                                    margin-bottom: 20px;
-                                                    color: #1DB954;
-                                                }}
-                                                p {{
-                                                    font-size: 1.2em;
-                                                    margin-bottom: 30px;
-                                                    line-height: 1.6;
-                                                    opacity: 0.9;
-                                                }}
-                                                .buttons {{
-                                                    display: flex;
-                                                    gap: 15px;
-                                                    justify-content: center;
-                                                    flex-wrap: wrap;
-                                                }}
-                                                .button {{
-                                                    display: inline-flex;
-                                                    align-items: center;
-                                                    padding: 12px 24px;
-                                                    background: #1DB954;
-                                                    color: white;
-                                                    text-decoration: none;
-                                                    border-radius: 50px;
-                                                    font-weight: 500;
-                                                    transition: all 0.3s ease;
-                                                    border: none;
-                                                    cursor: pointer;
-                                                    font-size: 1.1em;
-                                                }}
-                                                .button:hover {{
-                                                    transform: translateY(-2px);
-                                                    box-shadow: 0 5px 15px rgba(29, 185, 84, 0.3);
-                                                    background: #1ed760;
-                                                }}
-                                                .button.secondary {{
-                                                    background: rgba(255, 255, 255, 0.1);
-                                                }}
-                                                .button.secondary:hover {{
-                                                    background: rgba(255, 255, 255, 0.2);
-                                                }}
-                                                .button img {{
-                                                    width: 24px;
-                                                    height: 24px;
-                                                    margin-right: 8px;
-                                                }}
-                                            </style>
-                                        </head>
-                                        <body>
-                                            <div class="container">
-                                                <div class="success-icon">🎵</div>
-                                                <h1>Playlist Oluşturuldu!</h1>
-                                                <p>Harika! Yeni playlist'iniz başarıyla Spotify hesabınıza eklendi.</p>
-                                                <div class="buttons">
-                                                    <a href="{}" class="button" target="_blank">
-                                                        <img src="https://storage.googleapis.com/pr-newsroom-wp/1/2018/11/Spotify_Logo_RGB_White.png" alt="Spotify">
-                                                        Spotify'da Aç
-                                                    </a>
-                                                    <a href="/" class="button secondary" onclick="redirectToHome(event)">
-                                                        Yeni Playlist Oluştur
-                                                    </a>
-                                                </div>
-                                            </div>
-                                            <script>
-                                                function redirectToHome(event) {{
-                                                    event.preventDefault();
-                                                    if (window.opener) {{
-                                                        window.opener.location.href = '/';
-                                                        window.close();
-                                                    }} else {{
-                                                        window.location.href = '/';
-                                                    }}
-                                                }}
-
-                                                window.opener.postMessage({{
-                                                    type: "PLAYLIST_CREATED",
-                                                    playlistUrl: "{}"
-                                                }}, "*");
-                                            </script>
-                                        </body>
-                                        </html>
-                                        "#,
-                                        playlist_url,
-                                        playlist_url
-                                    )))
+                                    tracing::info!(
+                                        playlist_url = %playlist_url,
+                                        action,
+                                        mode = ?request.mode,
+                                        tracks = added,
+                                        "Wrote playlist"
+                                    );
+                                    Ok(success::render_success_page(
+                                        locale,
+                                        &success::CreatedPlaylist {
+                                            id: playlist.id.id().to_string(),
+                                            name: playlist.name.clone(),
+                                            url: playlist_url.to_string(),
+                                            updated: request.target_playlist.is_some(),
+                                            added,
+                                            matched,
+                                            missing,
+                                        },
+                                    ))
                                 }
                                 Err(e) => {
-                                    eprintln!("Error creating playlist: {}", e);
-                                    Ok(HttpResponse::Ok().content_type("text/html").body(format!(
-                                        r#"
-                                        <!DOCTYPE html>
-                                        <html>
-                                        <head>
-                                            <title>Error</title>
-                                            <script>
-                                                window.opener.postMessage({{
-                                                    type: "PLAYLIST_ERROR",
-                                                    error: "Failed to create playlist: {}"
-                                                }}, "*");
-                                                window.close();
-                                            </script>
-                                        </head>
-                                        <body>
-                                            <p>Redirecting back...</p>
-                                        </body>
-                                        </html>
-                                        "#,
-                                        e
-                                    )))
+                                    tracing::error!(error = %e, action, "Error opening playlist");
+                                    Ok(success::render_error_page(
+                                        locale,
+                                        &locale.message(
+                                            &Message::new("open-playlist-failed")
+                                                .arg("action", action)
+                                                .arg("error", e.to_string()),
+                                        ),
+                                    ))
                                 }
                             }
                         }
                         Err(e) => {
-                            eprintln!("Error getting user profile: {}", e);
-                            Ok(HttpResponse::Ok().content_type("text/html").body(format!(
-                                r#"
-                                <!DOCTYPE html>
-                                <html>
-                                <head>
-                                    <title>Error</title>
-                                    <script>
-                                        window.opener.postMessage({{
-                                            type: "PLAYLIST_ERROR",
-                                            error: "Failed to get user profile: {}"
-                                        }}, "*");
-                                        window.close();
-                                    </script>
-                                </head>
-                                <body>
-                                    <p>Redirecting back...</p>
-                                </body>
-                                </html>
-                                "#,
-                                e
-                            )))
+                            tracing::error!(error = %e, "Error getting user profile");
+                            Ok(success::render_error_page(
+                                locale,
+                                &locale.message(
+                                    &Message::new("user-profile-failed")
+                                        .arg("error", e.to_string()),
+                                ),
+                            ))
                         }
                     }
                 }
                 Err(e) => {
-                    eprintln!("Error exchanging code for token: {}", e);
-                    Ok(HttpResponse::Ok().content_type("text/html").body(format!(
-                        r#"
-                        <!DOCTYPE html>
-                        <html>
-                        <head>
-                            <title>Error</title>
-                            <script>
-                                window.opener.postMessage({{
-                                    type: "PLAYLIST_ERROR",
-                                    error: "Failed to authenticate with Spotify: {}"
-                                }}, "*");
-                                window.close();
-                            </script>
-                        </head>
-                        <body>
-                            <p>Redirecting back...</p>
-                        </body>
-                        </html>
-                        "#,
-                        e
-                    )))
+                    tracing::error!(error = %e, "Error exchanging code for token");
+                    Ok(success::render_error_page(
+                        locale,
+                        &locale.message(
+                            &Message::new("spotify-auth-failed").arg("error", e.to_string()),
+                        ),
+                    ))
                 }
             }
         }
         Some(_) => {
-            eprintln!("Empty tracks list received");
-            Ok(HttpResponse::Ok().content_type("text/html").body(format!(
-                r#"
-                <!DOCTYPE html>
-                <html>
-                <head>
-                    <title>Error</title>
-                    <script>
-                        window.opener.postMessage({{
-                            type: "PLAYLIST_ERROR",
-                            error: "No tracks selected. Please select some songs before creating a playlist."
-                        }}, "*");
-                        window.close();
-                    </script>
-                </head>
-                <body>
-                    <p>Redirecting back...</p>
-                </body>
-                </html>
-                "#
-            )))
+            tracing::warn!("Empty tracks list received");
+            Ok(success::render_error_page(
+                locale,
+                &locale.text("no-tracks-selected"),
+            ))
         }
         None => {
-            eprintln!("No tracks found in session");
-            Ok(HttpResponse::Ok().content_type("text/html").body(format!(
-                r#"
-                <!DOCTYPE html>
-                <html>
-                <head>
-                    <title>Error</title>
-                    <script>
-                        window.opener.postMessage({{
-                            type: "PLAYLIST_ERROR",
-                            error: "Please go back and select songs before creating a playlist"
-                        }}, "*");
-                        window.close();
-                    </script>
-                </head>
-                <body>
-                    <p>Redirecting back...</p>
-                </body>
-                </html>
-                "#
-            )))
+            tracing::warn!("No tracks found in session");
+            Ok(success::render_error_page(
+                locale,
+                &locale.text("no-tracks-in-session"),
+            ))
         }
     }
 }
 
-pub async fn handle_history_callback(code: &str) -> Result<HttpResponse, Error> {
-    let creds = Credentials::new(
-        "ae95afc24c12492a952e3d586ab8dcca",
-        "0c4fc4b5032b4b4fac846d69073d3d54",
-    );
+pub async fn handle_history_callback(
+    spotify_config: &SpotifyConfig,
+    code: &str,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let creds = spotify_config.credentials();
     let oauth = OAuth {
-        redirect_uri: "http://127.0.0.1:8081/callback".to_string(),
+        redirect_uri: spotify_config.redirect_uri.clone(),
         scopes: scopes!(
             "user-read-private",
             "user-read-email",
@@ -687,11 +486,19 @@ pub async fn handle_history_callback(code: &str) -> Result<HttpResponse, Error>
         ..Default::default()
     };
 
-    let mut spotify = AuthCodeSpotify::new(creds, oauth);
+    let spotify = AuthCodeSpotify::new(creds, oauth);
 
     // Exchange the code for an access token
-    match spotify.request_token(code).await {
-        Ok(()) => match spotify.current_user_recently_played(Some(20), None).await {
+    match spotify
+        .request_token(code)
+        .instrument(tracing::info_span!("spotify.request_token"))
+        .await
+    {
+        Ok(()) => match spotify
+            .current_user_recently_played(Some(20), None)
+            .instrument(tracing::info_span!("spotify.recently_played"))
+            .await
+        {
             Ok(history) => {
                 let tracks: Vec<RecentTrack> = history
                     .items
@@ -717,15 +524,17 @@ pub async fn handle_history_callback(code: &str) -> Result<HttpResponse, Error>
                     })
                     .collect();
 
-                let tracks_json =
-                    serde_json::to_string(&tracks).unwrap_or_else(|_| "[]".to_string());
+                // Track and artist names are user data and must not close the script tag
+                let tracks_json = success::script_json(
+                    &serde_json::to_value(&tracks).unwrap_or_else(|_| serde_json::json!([])),
+                );
 
                 Ok(HttpResponse::Ok().content_type("text/html").body(format!(
                     r#"
                         <!DOCTYPE html>
-                        <html>
+                        <html lang="{lang}">
                         <head>
-                            <title>History Loaded</title>
+                            <title>{title}</title>
                             <meta charset="UTF-8">
                             <script>
                                 window.opener.postMessage({{ 
@@ -736,364 +545,550 @@ pub async fn handle_history_callback(code: &str) -> Result<HttpResponse, Error>
                             </script>
                         </head>
                         <body>
-                            <p>Loading your history...</p>
+                            <p>{loading}</p>
                         </body>
                         </html>
-                        "#
+                        "#,
+                    lang = locale.tag(),
+                    title = escape_html(&locale.text("history-loaded-title")),
+                    loading = escape_html(&locale.text("history-loading")),
                 )))
             }
             Err(e) => {
-                eprintln!("Error fetching recently played: {}", e);
-                Ok(HttpResponse::Ok()
-                        .content_type("text/html")
-                        .body(format!(
-                            r#"
-                            <!DOCTYPE html>
-                            <html>
-                            <head>
-                                <title>Error Loading History</title>
-                                <meta charset="UTF-8">
-                                <script>
-                                    window.opener.postMessage({{ 
-                                        type: "HISTORY_ERROR", 
-                                        error: "Failed to load your recently played tracks. Please try again."
-                                    }}, "*");
-                                    window.close();
-                                </script>
-                            </head>
-                            <body>
-                                <h3>Error Loading History</h3>
-                                <p>There was a problem loading your Spotify history.</p>
-                                <p>The window will close automatically.</p>
-                            </body>
-                            </html>
-                            "#
-                        )))
+                tracing::error!(error = %e, "Error fetching recently played");
+                Ok(history_error_page(
+                    locale,
+                    "history-error-title",
+                    "history-load-failed",
+                    "history-error-detail",
+                ))
             }
         },
         Err(e) => {
-            eprintln!("Error requesting token: {}", e);
-            Ok(HttpResponse::Ok().content_type("text/html").body(format!(
-                r#"
-                <!DOCTYPE html>
-                <html>
-                <head>
-                    <title>Authentication Error</title>
-                    <meta charset="UTF-8">
-                    <script>
-                        window.opener.postMessage({{ 
-                            type: "HISTORY_ERROR", 
-                            error: "Failed to authenticate with Spotify. Please try again."
-                        }}, "*");
-                        window.close();
-                    </script>
-                </head>
-                <body>
-                    <h3>Authentication Error</h3>
-                    <p>There was a problem logging in to Spotify.</p>
-                    <p>The window will close automatically.</p>
-                </body>
-                </html>
-                "#
-            )))
+            tracing::error!(error = %e, "Error requesting token");
+            Ok(history_error_page(
+                locale,
+                "history-auth-error-title",
+                "spotify-auth-retry",
+                "history-auth-error-detail",
+            ))
         }
     }
 }
 
+/// Popup page that reports a failed history load to the opener and closes itself
+fn history_error_page(
+    locale: Locale,
+    title: &'static str,
+    error: &'static str,
+    detail: &'static str,
+) -> HttpResponse {
+    let error = success::script_json(&serde_json::json!(locale.text(error)));
+
+    HttpResponse::Ok().content_type("text/html").body(format!(
+        r#"
+            <!DOCTYPE html>
+            <html lang="{lang}">
+            <head>
+                <title>{title}</title>
+                <meta charset="UTF-8">
+                <script>
+                    window.opener.postMessage({{ 
+                        type: "HISTORY_ERROR", 
+                        error: {error}
+                    }}, "*");
+                    window.close();
+                </script>
+            </head>
+            <body>
+                <h3>{title}</h3>
+                <p>{detail}</p>
+                <p>{closing}</p>
+            </body>
+            </html>
+            "#,
+        lang = locale.tag(),
+        title = escape_html(&locale.text(title)),
+        detail = escape_html(&locale.text(detail)),
+        closing = escape_html(&locale.text("window-closing")),
+    ))
+}
+
 pub async fn get_history_auth_url() -> Result<HttpResponse, Error> {
     // ... existing get_history_auth_url implementation ...
     Ok(HttpResponse::Ok().json(serde_json::json!({"message": "Not implemented"})))
 }
 
-#[derive(Debug, Deserialize)]
-pub struct GeminiPromptRequest {
-    prompt: String,
-}
-
+/// Ask Gemini for a playlist matching a free-text prompt
+#[utoipa::path(
+    post,
+    path = "/api/v1/playlists/suggestions",
+    tag = "playlists",
+    security((), ("api_key" = [])),
+    request_body = GeminiPromptRequest,
+    responses(
+        (status = 200, description = "Suggested playlist", body = GeminiPromptResponse),
+        (status = 400, description = "Empty prompt or no tracks found", body = ErrorResponse),
+        (status = 429, description = "Rate limited", body = ErrorResponse),
+        (status = 500, description = "Gemini request failed", body = ErrorResponse)
+    )
+)]
 pub async fn process_gemini_prompt(
     req: web::Json<GeminiPromptRequest>,
     data: web::Data<AppState>,
+    caller: Caller,
+    locale: Locale,
 ) -> Result<HttpResponse, Error> {
-    println!("Received prompt request: {}", req.prompt);
+    tracing::debug!(prompt = %req.prompt, "Received prompt request");
 
     if req.prompt.trim().is_empty() {
         return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-            "error": "Please provide a prompt for the playlist"
+            "success": false,
+            "error": locale.text("playlist-prompt-required")
         })));
     }
 
-    match data.gemini_service.generate_playlist(&req.prompt).await {
-        Ok(playlist) => {
+    Ok(suggest_playlist(&data, &req, locale, caller.user_id.as_deref(), None, locale).await)
+}
+
+/// Ask Gemini for a playlist written in `language` and, for signed-in callers, keep it
+/// in their history. `locale` only picks the language of error messages.
+pub(crate) async fn suggest_playlist(
+    data: &AppState,
+    request: &GeminiPromptRequest,
+    language: Locale,
+    user_id: Option<&str>,
+    source_generation_id: Option<&str>,
+    locale: Locale,
+) -> HttpResponse {
+    let started = std::time::Instant::now();
+
+    match data
+        .gemini_service
+        .generate_playlist(&request.prompt, language)
+        .await
+    {
+        Ok(mut playlist) => {
             if playlist.tracks.is_empty() {
-                return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                    "error": "No tracks were generated. Please try a different prompt."
-                })));
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "success": false,
+                    "error": locale.text("no-tracks-generated")
+                }));
             }
 
-            println!(
-                "Successfully generated playlist with {} tracks",
-                playlist.tracks.len()
-            );
-            Ok(HttpResponse::Ok().json(playlist))
+            tracing::info!(tracks = playlist.tracks.len(), "Generated playlist");
+
+            if let Some(user_id) = user_id {
+                let latency_ms = started.elapsed().as_millis() as i64;
+                match data
+                    .playlist_history_service
+                    .record(
+                        user_id,
+                        &request.prompt,
+                        PlaylistGeneration::parameters(request, language),
+                        &playlist,
+                        latency_ms,
+                        source_generation_id,
+                    )
+                    .await
+                {
+                    Ok(generation) => playlist.generation_id = Some(generation.id),
+                    // The suggestion itself succeeded, so only log the failure
+                    Err(e) => tracing::warn!(error = %e, "Failed to save playlist to history"),
+                }
+            }
+
+            HttpResponse::Ok().json(playlist)
         }
         Err(e) => {
-            eprintln!("Error generating playlist: {}", e);
-            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to generate playlist. Please try again."
-            })))
+            tracing::error!(error = %e, "Error generating playlist");
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": locale.text("playlist-generation-failed")
+            }))
         }
     }
 }
 
+/// Reject targets and flag combinations Spotify would refuse after the user signed in
+fn validate_playlist_target(request: &CreatePlaylistRequest) -> Result<(), Message> {
+    if let Some(target) = &request.target_playlist {
+        if SpotifyPlaylistService::parse_playlist_id(target).is_none() {
+            return Err(Message::new("invalid-playlist-reference").arg("field", "target_playlist"));
+        }
+    }
+
+    if request.public == Some(true) && request.collaborative == Some(true) {
+        return Err(Message::new("collaborative-public"));
+    }
+
+    Ok(())
+}
+
+/// Queue a playlist and start the Spotify sign-in that creates or updates it
+#[utoipa::path(
+    post,
+    path = "/api/v1/playlists",
+    tag = "playlists",
+    request_body = CreatePlaylistRequest,
+    responses(
+        (status = 200, description = "Spotify authorization URL", body = PlaylistAuthResponse),
+        (status = 400, description = "Invalid target playlist or flags", body = ErrorResponse),
+        (status = 500, description = "Authorization URL could not be built", body = ErrorResponse),
+        (status = 503, description = "Spotify is not configured", body = ErrorResponse)
+    )
+)]
 pub async fn create_spotify_playlist_handler(
     data: web::Data<AppState>,
     req: web::Json<CreatePlaylistRequest>,
     session: Session,
+    locale: Locale,
 ) -> Result<HttpResponse, Error> {
-    println!("Starting create_spotify_playlist_handler");
+    let Some(spotify_config) = data.spotify_config.as_ref() else {
+        return Ok(spotify_not_configured(locale));
+    };
+
+    if let Err(message) = validate_playlist_target(&req) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": locale.message(&message)
+        })));
+    }
 
     // Generate a unique session ID
     let session_id = uuid::Uuid::new_v4().to_string();
-    println!("Generated session ID: {}", session_id);
 
     // Store playlist request in application state
     {
         let mut pending_tracks = data.pending_tracks.lock().unwrap();
         pending_tracks.insert(session_id.clone(), req.0.clone());
-        println!(
-            "Stored tracks in pending_tracks with session ID: {}",
-            session_id
-        );
+        tracing::debug!(session_id = %session_id, "Stored pending tracks");
     }
 
     // Store session ID in both cookie and state parameter
     session.insert("tracks_session_id", &session_id)?;
-    println!("Stored session ID in cookie: {}", session_id);
 
-    let spotify_client_id = env::var("SPOTIFY_CLIENT_ID").expect("SPOTIFY_CLIENT_ID must be set");
-    let spotify_client_secret = env::var("SPOTIFY_CLIENT_SECRET").expect("SPOTIFY_CLIENT_SECRET must be set");
-    let spotify_redirect_uri = env::var("SPOTIFY_REDIRECT_URI").expect("SPOTIFY_REDIRECT_URI must be set");
+    let mut scopes = scopes!(
+        "playlist-modify-public",
+        "playlist-modify-private",
+        "ugc-image-upload",
+        "user-read-private",
+        "user-read-email",
+        "user-read-recently-played"
+    );
+    if req.target_playlist.is_some() {
+        // Merging reads the current tracks, which may live in a private playlist
+        scopes.extend(scopes!(
+            "playlist-read-private",
+            "playlist-read-collaborative"
+        ));
+    }
 
-    let creds = Credentials::new(&spotify_client_id, &spotify_client_secret);
+    let creds = spotify_config.credentials();
     let oauth = OAuth {
-        redirect_uri: spotify_redirect_uri.clone(),
-        scopes: scopes!(
-            "playlist-modify-public",
-            "playlist-modify-private",
-            "user-read-private",
-            "user-read-email",
-            "user-read-recently-played"
-        ),
+        redirect_uri: spotify_config.redirect_uri.clone(),
+        scopes,
         state: session_id.clone(),
         ..Default::default()
     };
 
     let spotify = AuthCodeSpotify::new(creds, oauth);
     match spotify.get_authorize_url(false) {
-        Ok(auth_url) => {
-            println!("Generated Spotify auth URL successfully");
-            Ok(HttpResponse::Ok().json(serde_json::json!({
-                "auth_url": auth_url,
-                "session_id": session_id
-            })))
-        }
+        Ok(auth_url) => Ok(HttpResponse::Ok().json(PlaylistAuthResponse {
+            auth_url,
+            session_id,
+        })),
         Err(e) => {
-            eprintln!("Error getting authorization URL: {}", e);
+            tracing::error!(error = %e, "Error getting authorization URL");
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Failed to get authorization URL: {}", e)
+                "success": false,
+                "error": locale.message(
+                    &Message::new("authorize-url-failed").arg("error", e.to_string())
+                )
             })))
         }
     }
 }
 
-async fn create_spotify_playlist(
-    code: &str,
-    tracks: Vec<Track>,
-    playlist_name: String,
-    playlist_description: Option<String>,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let spotify_client_id = env::var("SPOTIFY_CLIENT_ID").expect("SPOTIFY_CLIENT_ID must be set");
-    let spotify_client_secret = env::var("SPOTIFY_CLIENT_SECRET").expect("SPOTIFY_CLIENT_SECRET must be set");
-    let spotify_redirect_uri = env::var("SPOTIFY_REDIRECT_URI").expect("SPOTIFY_REDIRECT_URI must be set");
-
-    let creds = Credentials::new(&spotify_client_id, &spotify_client_secret);
-    let oauth = OAuth {
-        redirect_uri: spotify_redirect_uri.clone(),
-        scopes: scopes!(
-            "playlist-modify-public",
-            "playlist-modify-private",
-            "user-read-private",
-            "user-read-email",
-            "user-read-recently-played"
-        ),
-        ..Default::default()
-    };
-
-    let spotify = AuthCodeSpotify::new(creds, oauth);
-
-    // Exchange the code for an access token
-    spotify.request_token(code).await?;
-
-    // Get user profile
-    let user = spotify.me().await?;
-
-    // Create a new playlist
-    let playlist = spotify
-        .user_playlist_create(
-            user.id,
-            &playlist_name,
-            Some(false), // not public
-            Some(false), // not collaborative
-            playlist_description.as_deref(),
-        )
-        .await?;
-
-    // Search for tracks and add them to the playlist
-    let mut spotify_track_ids = Vec::new();
-
-    for track in tracks {
-        println!("Processing track: {} by {}", track.name, track.artist);
-        let search_query = format!("{} {}", track.name, track.artist);
-
-        if let SearchResult::Tracks(page) = spotify
-            .search(&search_query, SearchType::Track, None, None, Some(1), None)
-            .await?
-        {
-            if let Some(found_track) = page.items.first() {
-                if let Some(track_id) = &found_track.id {
-                    spotify_track_ids.push(track_id.clone());
-                    println!("Added track to playlist queue: {}", found_track.name);
-                }
-            }
-        }
-    }
-
-    // Add tracks to playlist if we found any
-    if spotify_track_ids.is_empty() {
-        return Err("No matching tracks found on Spotify".into());
-    }
-
-    // Add tracks in batches of 100 (Spotify API limit)
-    for chunk in spotify_track_ids.chunks(100) {
-        let track_ids: Vec<PlayableId> = chunk
-            .iter()
-            .map(|id| PlayableId::Track(id.clone()))
-            .collect();
-
-        spotify
-            .playlist_add_items(playlist.id.clone(), track_ids, None)
-            .await?;
-    }
-
-    Ok(playlist
-        .external_urls
-        .get("spotify")
-        .map(String::as_str)
-        .unwrap_or("")
-        .to_string())
-}
-
 // ==================== AI MUSIC GENERATION HANDLERS ====================
 
-/// Health check endpoint for AI music service
-pub async fn ai_music_health_check(app_state: web::Data<AppState>) -> Result<HttpResponse, Error> {
-    match app_state.musicgen_service.health_check().await {
-        Ok(is_healthy) => {
-            if is_healthy {
-                Ok(HttpResponse::Ok().json(serde_json::json!({
-                    "status": "ok",
-                    "message": "AI Music service is running"
-                })))
-            } else {
-                Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-                    "status": "error",
-                    "message": "AI Music service is not healthy"
-                })))
-            }
-        }
-        Err(e) => Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-            "status": "error",
-            "message": format!("AI Music service is not available: {}", e)
-        }))),
-    }
-}
-
 /// Generate a single AI song from a text prompt
+#[utoipa::path(
+    post,
+    path = "/api/v1/ai-music",
+    tag = "ai-music",
+    security((), ("api_key" = [])),
+    request_body = AiMusicRequest,
+    responses(
+        (status = 200, description = "Generated song", body = AiMusicResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 429, description = "Rate limited or daily quota used up", body = ErrorResponse),
+        (status = 500, description = "Generation failed", body = ErrorResponse)
+    )
+)]
 pub async fn generate_ai_music(
     app_state: web::Data<AppState>,
     request: web::Json<AiMusicRequest>,
+    caller: Caller,
+    locale: Locale,
 ) -> Result<HttpResponse, Error> {
-    println!("Received AI music generation request: {:?}", request);
+    tracing::debug!(
+        prompt = %request.prompt,
+        duration = ?request.duration,
+        "Received AI music request"
+    );
 
-    // Validate prompt
-    if request.prompt.trim().is_empty() {
+    // Validate request
+    if let Err(message) = validate_ai_music_request(&request) {
         return Ok(HttpResponse::BadRequest().json(serde_json::json!({
             "success": false,
-            "error": "Prompt cannot be empty"
+            "error": locale.message(&message)
         })));
     }
 
     // Generate the AI music
     match app_state
         .musicgen_service
-        .generate_song(&request.prompt, request.duration)
+        .generate_song(&request.prompt, request.duration, &request.controls)
         .await
     {
-        Ok(response) => {
-            println!("Successfully generated AI music: {:?}", response);
+        Ok(mut response) => {
+            let user_id = caller.user_id.clone();
+            if let Err(e) = app_state
+                .audio_cache_service
+                .record_owner(&response.file_id, user_id.as_deref())
+                .await
+            {
+                tracing::warn!(
+                    file_id = %response.file_id,
+                    error = %e,
+                    "Failed to record file owner"
+                );
+            }
+            response.file_path = AudioCacheService::public_path(&response.file_id);
+
+            if let Some(options) = &request.post_processing {
+                if let Err(e) = app_state
+                    .audio_cache_service
+                    .post_process_song(
+                        app_state.musicgen_service.as_ref(),
+                        &mut response,
+                        options,
+                        user_id.as_deref(),
+                    )
+                    .await
+                {
+                    tracing::error!(error = %e, "Error post-processing AI music");
+                    return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                        "success": false,
+                        "error": locale.message(
+                            &Message::new("post-processing-failed").arg("error", e.to_string())
+                        )
+                    })));
+                }
+            }
+
+            let parameters =
+                NewLibraryTrack::parameters(&request.controls, request.post_processing.as_ref());
+            app_state
+                .library_service
+                .save(
+                    user_id.as_deref(),
+                    vec![NewLibraryTrack::from_song(
+                        &response,
+                        TrackSource::Text,
+                        parameters,
+                    )],
+                )
+                .await;
+
             Ok(HttpResponse::Ok().json(response))
         }
         Err(e) => {
-            eprintln!("Error generating AI music: {}", e);
+            tracing::error!(error = %e, "Error generating AI music");
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "success": false,
-                "error": format!("Failed to generate AI music: {}", e)
+                "error": locale.message(
+                    &Message::new("music-generation-failed").arg("error", e.to_string())
+                )
             })))
         }
     }
 }
 
+/// Check a single generation request before it is sent to MusicGen
+pub(crate) fn validate_ai_music_request(request: &AiMusicRequest) -> Result<(), Message> {
+    if request.prompt.trim().is_empty() {
+        return Err(Message::new("prompt-empty"));
+    }
+
+    validate_duration(request.duration)?;
+    request.controls.validate()?;
+
+    if let Some(options) = &request.post_processing {
+        options.validate()?;
+    }
+
+    Ok(())
+}
+
+/// Most prompts one batch may carry, so a single request cannot queue unbounded work
+pub(crate) const MAX_BATCH_PROMPTS: usize = 10;
+
+/// Check that a batch has between one and `MAX_BATCH_PROMPTS` prompts and none are blank
+pub(crate) fn validate_batch_prompts(request: &AiMusicBatchRequest) -> Result<(), Message> {
+    if request.prompts.is_empty() {
+        return Err(Message::new("prompts-empty"));
+    }
+
+    if request.prompts.len() > MAX_BATCH_PROMPTS {
+        return Err(Message::new("field-out-of-range")
+            .arg("field", "prompts")
+            .arg("min", 1)
+            .arg("max", MAX_BATCH_PROMPTS));
+    }
+
+    for (idx, prompt_item) in request.prompts.iter().enumerate() {
+        if prompt_item.prompt.trim().is_empty() {
+            return Err(Message::new("prompt-at-index-empty").arg("index", idx));
+        }
+    }
+
+    validate_duration(request.duration)?;
+    request.controls.validate()?;
+
+    if let Some(options) = &request.post_processing {
+        options.validate()?;
+    }
+
+    Ok(())
+}
+
 /// Generate multiple AI songs from a list of prompts (batch generation)
+#[utoipa::path(
+    post,
+    path = "/api/v1/ai-music/batches",
+    tag = "ai-music",
+    security((), ("api_key" = [])),
+    request_body = AiMusicBatchRequest,
+    responses(
+        (status = 200, description = "At least one song succeeded", body = AiMusicBatchResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 429, description = "Rate limited or daily quota used up", body = ErrorResponse),
+        (status = 500, description = "Every prompt failed", body = ErrorResponse)
+    )
+)]
 pub async fn generate_ai_music_batch(
+    req: HttpRequest,
     app_state: web::Data<AppState>,
     request: web::Json<AiMusicBatchRequest>,
+    caller: Caller,
+    locale: Locale,
 ) -> Result<HttpResponse, Error> {
-    println!("Received AI music batch generation request with {} prompts", request.prompts.len());
+    tracing::info!(
+        prompts = request.prompts.len(),
+        "Received AI music batch request"
+    );
 
     // Validate request
-    if request.prompts.is_empty() {
+    if let Err(message) = validate_batch_prompts(&request) {
         return Ok(HttpResponse::BadRequest().json(serde_json::json!({
             "success": false,
-            "error": "Prompts list cannot be empty"
+            "error": locale.message(&message)
         })));
     }
 
-    // Check for empty prompts
-    for (idx, prompt_item) in request.prompts.iter().enumerate() {
-        if prompt_item.prompt.trim().is_empty() {
-            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+    if let Some(response) = charge_prompts(&req, request.prompts.len(), locale).await {
+        return Ok(response);
+    }
+
+    let request = request.into_inner();
+    let duration = request.duration.unwrap_or(DEFAULT_AI_MUSIC_DURATION);
+    let parameters =
+        NewLibraryTrack::parameters(&request.controls, request.post_processing.as_ref());
+
+    // Generate the AI music batch; failed prompts are reported alongside the songs
+    let mut response =
+        music_generator::generate_batch(app_state.musicgen_service.as_ref(), &request).await;
+
+    if !response.success {
+        tracing::error!("Every prompt in the AI music batch failed");
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "success": false,
+            "error": locale.text("batch-generation-failed"),
+            "failures": response.failures
+        })));
+    }
+
+    tracing::info!(
+        generated = response.count,
+        prompts = request.prompts.len(),
+        "Generated AI music batch"
+    );
+
+    let user_id = caller.user_id.clone();
+    for song in response.songs.iter_mut() {
+        if let Err(e) = app_state
+            .audio_cache_service
+            .record_owner(&song.file_id, user_id.as_deref())
+            .await
+        {
+            tracing::warn!(file_id = %song.file_id, error = %e, "Failed to record file owner");
+        }
+        song.file_path = AudioCacheService::public_path(&song.file_id);
+    }
+
+    if let Some(options) = &request.post_processing {
+        if let Err(e) = app_state
+            .audio_cache_service
+            .post_process_batch(
+                app_state.musicgen_service.as_ref(),
+                &mut response,
+                options,
+                user_id.as_deref(),
+            )
+            .await
+        {
+            tracing::error!(error = %e, "Error post-processing AI music batch");
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "success": false,
-                "error": format!("Prompt at index {} cannot be empty", idx)
+                "error": locale.message(
+                    &Message::new("batch-post-processing-failed").arg("error", e.to_string())
+                )
             })));
         }
     }
 
-    // Generate the AI music batch
-    match app_state
-        .musicgen_service
-        .generate_batch(request.into_inner())
-        .await
-    {
-        Ok(response) => {
-            println!("Successfully generated {} AI songs", response.songs.len());
-            Ok(HttpResponse::Ok().json(response))
-        }
-        Err(e) => {
-            eprintln!("Error generating AI music batch: {}", e);
-            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "success": false,
-                "error": format!("Failed to generate AI music batch: {}", e)
-            })))
+    app_state
+        .library_service
+        .save(
+            user_id.as_deref(),
+            NewLibraryTrack::from_batch(&response, duration, TrackSource::Batch, parameters),
+        )
+        .await;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_is_rendered_in_the_request_locale() {
+        for locale in Locale::ALL {
+            let page = render_index(locale);
+
+            assert!(page.contains(&format!(r#"<html lang="{}">"#, locale.tag())));
+            assert!(
+                !page.contains("{{"),
+                "unfilled placeholder in {}",
+                locale.tag()
+            );
+            assert!(page.contains(&escape_html(&locale.text("index-describe-heading"))));
         }
+
+        assert!(render_index(Locale::Tr).contains("Popup penceresi engellendi"));
+        assert!(!render_index(Locale::En).contains("Popup penceresi engellendi"));
     }
 }
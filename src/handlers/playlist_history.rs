@@ -0,0 +1,254 @@
+use crate::i18n::{Locale, Message};
+use crate::middleware::api_key::Caller;
+use crate::models::playlist_history::{
+    PlaylistGenerationDeletedResponse, PlaylistHistoryQuery, PlaylistHistoryResponse,
+};
+use crate::AppState;
+use actix_web::{web, Error, HttpResponse};
+
+use super::playlist_seed::suggest_from_seed;
+use super::suggest_playlist;
+
+fn login_required(locale: Locale) -> HttpResponse {
+    HttpResponse::Unauthorized().json(serde_json::json!({
+        "success": false,
+        "error": locale.text("login-required-history")
+    }))
+}
+
+fn generation_not_found(locale: Locale) -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "success": false,
+        "error": locale.text("history-entry-not-found")
+    }))
+}
+
+fn history_error(locale: Locale, action: &'static str, e: impl std::fmt::Display) -> HttpResponse {
+    tracing::error!(error = %e, "Error trying to {} playlist history", action);
+    HttpResponse::InternalServerError().json(serde_json::json!({
+        "success": false,
+        "error": locale.message(&Message::new("history-failed").arg("action", action))
+    }))
+}
+
+/// List the signed-in user's playlist suggestions, newest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/playlists/suggestions",
+    tag = "playlists",
+    security((), ("api_key" = [])),
+    params(PlaylistHistoryQuery),
+    responses(
+        (status = 200, description = "One page of suggestions", body = PlaylistHistoryResponse),
+        (status = 401, description = "Not signed in with Spotify", body = ErrorResponse),
+        (status = 500, description = "History could not be loaded", body = ErrorResponse)
+    )
+)]
+pub async fn list_generations(
+    app_state: web::Data<AppState>,
+    query: web::Query<PlaylistHistoryQuery>,
+    caller: Caller,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(user_id) = caller.user_id else {
+        return Ok(login_required(locale));
+    };
+
+    match app_state
+        .playlist_history_service
+        .list(&user_id, &query)
+        .await
+    {
+        Ok((total, generations)) => Ok(HttpResponse::Ok().json(PlaylistHistoryResponse {
+            success: true,
+            total,
+            generations,
+        })),
+        Err(e) => Ok(history_error(locale, "load", e)),
+    }
+}
+
+/// Fetch one suggestion with its prompt, tracks and timing
+#[utoipa::path(
+    get,
+    path = "/api/v1/playlists/suggestions/{id}",
+    tag = "playlists",
+    security((), ("api_key" = [])),
+    params(("id" = String, Path, description = "History entry ID")),
+    responses(
+        (status = 200, description = "The suggestion", body = PlaylistGeneration),
+        (status = 401, description = "Not signed in with Spotify", body = ErrorResponse),
+        (status = 404, description = "Not in the user's history", body = ErrorResponse),
+        (status = 500, description = "History could not be loaded", body = ErrorResponse)
+    )
+)]
+pub async fn get_generation(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    caller: Caller,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(user_id) = caller.user_id else {
+        return Ok(login_required(locale));
+    };
+
+    match app_state
+        .playlist_history_service
+        .get(&user_id, &path)
+        .await
+    {
+        Ok(Some(generation)) => Ok(HttpResponse::Ok().json(generation)),
+        Ok(None) => Ok(generation_not_found(locale)),
+        Err(e) => Ok(history_error(locale, "load", e)),
+    }
+}
+
+/// Send a stored prompt and its options to Gemini again, saving the result as a new entry.
+/// Entries built from a Spotify playlist reload that playlist and answer like the seeded endpoint.
+#[utoipa::path(
+    post,
+    path = "/api/v1/playlists/suggestions/{id}/rerun",
+    tag = "playlists",
+    security((), ("api_key" = [])),
+    params(("id" = String, Path, description = "History entry ID")),
+    responses(
+        (status = 200, description = "New suggestion", body = GeminiPromptResponse),
+        (status = 400, description = "No tracks found", body = ErrorResponse),
+        (status = 401, description = "Not signed in with Spotify", body = ErrorResponse),
+        (status = 404, description = "Not in the user's history", body = ErrorResponse),
+        (status = 429, description = "Rate limited or daily quota used up", body = ErrorResponse),
+        (status = 500, description = "Gemini request failed", body = ErrorResponse)
+    )
+)]
+pub async fn rerun_generation(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    caller: Caller,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(user_id) = caller.user_id else {
+        return Ok(login_required(locale));
+    };
+
+    let generation = match app_state
+        .playlist_history_service
+        .get(&user_id, &path)
+        .await
+    {
+        Ok(Some(generation)) => generation,
+        Ok(None) => return Ok(generation_not_found(locale)),
+        Err(e) => return Ok(history_error(locale, "load", e)),
+    };
+
+    // Entries saved before the language was stored are replayed in the caller's language
+    let language = generation.language().unwrap_or(locale);
+
+    tracing::info!(
+        generation_id = %generation.id,
+        seeded = generation.is_seeded(),
+        "Re-running playlist suggestion"
+    );
+
+    if generation.is_seeded() {
+        let request = match generation.request() {
+            Ok(request) => request,
+            Err(e) => return Ok(history_error(locale, "replay", e)),
+        };
+        return Ok(suggest_from_seed(
+            &app_state,
+            &request,
+            language,
+            Some(&user_id),
+            Some(&generation.id),
+            locale,
+        )
+        .await);
+    }
+
+    let request = match generation.request() {
+        Ok(request) => request,
+        Err(e) => return Ok(history_error(locale, "replay", e)),
+    };
+    Ok(suggest_playlist(
+        &app_state,
+        &request,
+        language,
+        Some(&user_id),
+        Some(&generation.id),
+        locale,
+    )
+    .await)
+}
+
+/// Copy a suggestion under a new ID without calling Gemini, e.g. to edit it separately
+#[utoipa::path(
+    post,
+    path = "/api/v1/playlists/suggestions/{id}/duplicate",
+    tag = "playlists",
+    security((), ("api_key" = [])),
+    params(("id" = String, Path, description = "History entry ID")),
+    responses(
+        (status = 201, description = "The copy", body = PlaylistGeneration),
+        (status = 401, description = "Not signed in with Spotify", body = ErrorResponse),
+        (status = 404, description = "Not in the user's history", body = ErrorResponse),
+        (status = 500, description = "History could not be updated", body = ErrorResponse)
+    )
+)]
+pub async fn duplicate_generation(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    caller: Caller,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(user_id) = caller.user_id else {
+        return Ok(login_required(locale));
+    };
+
+    match app_state
+        .playlist_history_service
+        .duplicate(&user_id, &path)
+        .await
+    {
+        Ok(Some(generation)) => Ok(HttpResponse::Created().json(generation)),
+        Ok(None) => Ok(generation_not_found(locale)),
+        Err(e) => Ok(history_error(locale, "update", e)),
+    }
+}
+
+/// Remove a suggestion from the history
+#[utoipa::path(
+    delete,
+    path = "/api/v1/playlists/suggestions/{id}",
+    tag = "playlists",
+    security((), ("api_key" = [])),
+    params(("id" = String, Path, description = "History entry ID")),
+    responses(
+        (status = 200, description = "Entry deleted", body = PlaylistGenerationDeletedResponse),
+        (status = 401, description = "Not signed in with Spotify", body = ErrorResponse),
+        (status = 404, description = "Not in the user's history", body = ErrorResponse),
+        (status = 500, description = "History could not be updated", body = ErrorResponse)
+    )
+)]
+pub async fn delete_generation(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    caller: Caller,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(user_id) = caller.user_id else {
+        return Ok(login_required(locale));
+    };
+
+    match app_state
+        .playlist_history_service
+        .delete(&user_id, &path)
+        .await
+    {
+        Ok(true) => Ok(HttpResponse::Ok().json(PlaylistGenerationDeletedResponse {
+            success: true,
+            id: path.into_inner(),
+        })),
+        Ok(false) => Ok(generation_not_found(locale)),
+        Err(e) => Ok(history_error(locale, "update", e)),
+    }
+}
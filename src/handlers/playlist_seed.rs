@@ -0,0 +1,170 @@
+use crate::i18n::{Locale, Message};
+use crate::middleware::api_key::Caller;
+use crate::models::playlist::{PlaylistSeedRequest, SeededPlaylistResponse, Track};
+use crate::models::playlist_history::PlaylistGeneration;
+use crate::services::spotify_playlist_service::SpotifyPlaylistService;
+use crate::AppState;
+use actix_web::{web, Error, HttpResponse};
+
+use super::spotify_not_configured;
+
+const DEFAULT_SEED_SUGGESTIONS: usize = 10;
+const MAX_SEED_SUGGESTIONS: usize = 30;
+
+/// Extend a public Spotify playlist, or turn it into a variation, with Gemini suggestions
+#[utoipa::path(
+    post,
+    path = "/api/v1/playlists/seeded-suggestions",
+    tag = "playlists",
+    security((), ("api_key" = [])),
+    request_body = PlaylistSeedRequest,
+    responses(
+        (status = 200, description = "Suggested tracks", body = SeededPlaylistResponse),
+        (status = 400, description = "Invalid request or empty playlist", body = ErrorResponse),
+        (status = 429, description = "Rate limited or daily quota used up", body = ErrorResponse),
+        (status = 500, description = "Gemini request failed", body = ErrorResponse),
+        (status = 502, description = "Playlist could not be read from Spotify", body = ErrorResponse),
+        (status = 503, description = "Spotify is not configured", body = ErrorResponse)
+    )
+)]
+pub async fn suggest_from_playlist(
+    app_state: web::Data<AppState>,
+    request: web::Json<PlaylistSeedRequest>,
+    caller: Caller,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    Ok(suggest_from_seed(
+        &app_state,
+        &request,
+        locale,
+        caller.user_id.as_deref(),
+        None,
+        locale,
+    )
+    .await)
+}
+
+/// Build suggestions in `language` from a Spotify playlist and, for signed-in callers,
+/// keep them in their history. `locale` only picks the language of error messages.
+pub(crate) async fn suggest_from_seed(
+    app_state: &AppState,
+    request: &PlaylistSeedRequest,
+    language: Locale,
+    user_id: Option<&str>,
+    source_generation_id: Option<&str>,
+    locale: Locale,
+) -> HttpResponse {
+    let Some(spotify_config) = app_state.spotify_config.as_ref() else {
+        return spotify_not_configured(locale);
+    };
+
+    let Some(playlist_id) = SpotifyPlaylistService::parse_playlist_id(&request.playlist) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": locale.message(
+                &Message::new("invalid-playlist-reference").arg("field", "playlist")
+            )
+        }));
+    };
+
+    let track_count = request.track_count.unwrap_or(DEFAULT_SEED_SUGGESTIONS);
+    if track_count == 0 || track_count > MAX_SEED_SUGGESTIONS {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": locale.message(
+                &Message::new("field-out-of-range")
+                    .arg("field", "track_count")
+                    .arg("min", 1)
+                    .arg("max", MAX_SEED_SUGGESTIONS)
+            )
+        }));
+    }
+
+    let direction = request
+        .prompt
+        .as_deref()
+        .map(str::trim)
+        .filter(|prompt| !prompt.is_empty());
+
+    let seed = match SpotifyPlaylistService::fetch_seed(spotify_config, playlist_id).await {
+        Ok(seed) => seed,
+        Err(e) => {
+            tracing::warn!(playlist = %request.playlist, error = %e, "Error loading seed playlist");
+            return HttpResponse::BadGateway().json(serde_json::json!({
+                "success": false,
+                "error": locale.text("seed-playlist-unavailable")
+            }));
+        }
+    };
+
+    if seed.tracks.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": locale.text("seed-playlist-empty")
+        }));
+    }
+
+    tracing::info!(
+        playlist = %seed.id,
+        seed_tracks = seed.tracks.len(),
+        mode = ?request.mode,
+        "Generating playlist from seed"
+    );
+
+    let started = std::time::Instant::now();
+
+    match app_state
+        .gemini_service
+        .generate_seeded_playlist(&seed, request.mode, direction, track_count, language)
+        .await
+    {
+        Ok(playlist) => {
+            let mut generation_id = None;
+            if let Some(user_id) = user_id {
+                let latency_ms = started.elapsed().as_millis() as i64;
+                match app_state
+                    .playlist_history_service
+                    .record(
+                        user_id,
+                        direction.unwrap_or_default(),
+                        PlaylistGeneration::parameters(request, language),
+                        &playlist,
+                        latency_ms,
+                        source_generation_id,
+                    )
+                    .await
+                {
+                    Ok(generation) => generation_id = Some(generation.id),
+                    // The suggestion itself succeeded, so only log the failure
+                    Err(e) => tracing::warn!(error = %e, "Failed to save playlist to history"),
+                }
+            }
+
+            HttpResponse::Ok().json(SeededPlaylistResponse {
+                success: true,
+                playlist_name: playlist.playlist_name,
+                playlist_description: playlist.playlist_description,
+                // Same shape the web client sends back; Spotify search fills in the rest
+                tracks: playlist
+                    .tracks
+                    .into_iter()
+                    .map(|track| Track {
+                        name: track.title,
+                        artist: track.artist,
+                        url: String::new(),
+                        spotify_id: None,
+                    })
+                    .collect(),
+                seed,
+                generation_id,
+            })
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error generating playlist from seed");
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": locale.text("playlist-generation-failed")
+            }))
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use crate::i18n::{Locale, Message};
+use crate::models::share::{QrFormat, QrOptions, QrQuery};
+use crate::services::qr_service::QrService;
+use crate::services::spotify_playlist_service::SpotifyPlaylistService;
+use crate::AppState;
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use rspotify::model::PlaylistId;
+use rspotify::prelude::*;
+
+use super::escape_html;
+
+/// QR code size on the share card
+const CARD_QR_SIZE: u32 = 180;
+
+/// Pages and cards are translated, so shared caches must keep one copy per language
+const VARY: (&str, &str) = ("Vary", "Accept-Language, Cookie");
+
+/// What the share page and card show about a playlist
+struct SharedPlaylist {
+    url: String,
+    title: String,
+    subtitle: String,
+    description: Option<String>,
+    cover_url: Option<String>,
+}
+
+impl SharedPlaylist {
+    /// Look the playlist up with the app's credentials. Private playlists cannot be read
+    /// that way, so their links still work but show a generic title.
+    async fn load(app_state: &AppState, playlist_id: PlaylistId<'static>, locale: Locale) -> Self {
+        let url = playlist_url(&playlist_id);
+
+        let playlist = match app_state.spotify_config.as_ref() {
+            Some(config) => SpotifyPlaylistService::fetch_playlist(config, playlist_id.as_ref())
+                .await
+                .map_err(|e| {
+                    tracing::debug!(
+                        playlist = %playlist_id.id(),
+                        error = %e,
+                        "Shared playlist not readable"
+                    )
+                })
+                .ok(),
+            None => None,
+        };
+
+        let Some(playlist) = playlist else {
+            return Self {
+                url,
+                // Shown when the playlist is private or Spotify cannot be reached
+                title: locale.text("shared-playlist-fallback-title"),
+                subtitle: locale.text("shared-playlist-fallback-subtitle"),
+                description: None,
+                cover_url: None,
+            };
+        };
+
+        let count = playlist.tracks.total;
+        let subtitle = locale.message(&match playlist.owner.display_name {
+            Some(owner) => Message::new("shared-playlist-tracks-by")
+                .arg("owner", owner)
+                .arg("count", count),
+            None => Message::new("shared-playlist-tracks").arg("count", count),
+        });
+
+        Self {
+            url,
+            title: playlist.name,
+            subtitle,
+            description: playlist
+                .description
+                .as_deref()
+                .map(unescape_spotify_text)
+                .filter(|description| !description.is_empty()),
+            cover_url: playlist.images.first().map(|image| image.url.clone()),
+        }
+    }
+}
+
+fn playlist_url(playlist_id: &PlaylistId) -> String {
+    format!("https://open.spotify.com/playlist/{}", playlist_id.id())
+}
+
+/// Spotify returns playlist descriptions with HTML entities already applied
+fn unescape_spotify_text(text: &str) -> String {
+    text.replace("&#x27;", "'")
+        .replace("&#39;", "'")
+        .replace("&quot;", "\"")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+fn invalid_playlist_id(locale: Locale) -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "success": false,
+        "error": locale.text("invalid-playlist-id")
+    }))
+}
+
+/// A page for a playlist's share link, with Open Graph tags so chat apps and social
+/// networks show its card as a preview
+pub async fn share_page(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(playlist_id) = SpotifyPlaylistService::parse_playlist_id(&path) else {
+        return Ok(invalid_playlist_id(locale));
+    };
+
+    let playlist = SharedPlaylist::load(&app_state, playlist_id.clone(), locale).await;
+
+    // Crawlers need absolute URLs; this honours X-Forwarded-* behind a proxy
+    let connection = req.connection_info();
+    let base_url = format!("{}://{}", connection.scheme(), connection.host());
+    let page_url = format!("{}/share/{}", base_url, playlist_id.id());
+    let card_url = format!("{}/card.jpg", page_url);
+
+    let title = escape_html(&playlist.title);
+    let subtitle = escape_html(&playlist.subtitle);
+    let description = escape_html(
+        playlist
+            .description
+            .as_deref()
+            .unwrap_or(&playlist.subtitle),
+    );
+    let spotify_url = escape_html(&playlist.url);
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .insert_header(("Cache-Control", "public, max-age=3600"))
+        .insert_header(VARY)
+        .body(format!(
+            r#"<!DOCTYPE html>
+<html lang="{lang}">
+<head>
+    <title>{title}</title>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <meta name="description" content="{description}">
+    <meta property="og:type" content="music.playlist">
+    <meta property="og:site_name" content="Melanify">
+    <meta property="og:title" content="{title}">
+    <meta property="og:description" content="{description}">
+    <meta property="og:url" content="{page_url}">
+    <meta property="og:image" content="{card_url}">
+    <meta property="og:image:type" content="image/jpeg">
+    <meta property="og:image:width" content="1200">
+    <meta property="og:image:height" content="630">
+    <meta property="og:image:alt" content="{title}">
+    <meta name="twitter:card" content="summary_large_image">
+    <meta name="twitter:title" content="{title}">
+    <meta name="twitter:description" content="{description}">
+    <meta name="twitter:image" content="{card_url}">
+    <link href="https://fonts.googleapis.com/css2?family=Roboto:wght@300;400;500;700&display=swap" rel="stylesheet">
+    <style>
+        * {{
+            margin: 0;
+            padding: 0;
+            box-sizing: border-box;
+        }}
+        body {{
+            font-family: 'Roboto', sans-serif;
+            background: linear-gradient(135deg, #1DB954 0%, #191414 100%);
+            min-height: 100vh;
+            display: flex;
+            align-items: center;
+            justify-content: center;
+            color: white;
+        }}
+        .container {{
+            background: rgba(255, 255, 255, 0.1);
+            backdrop-filter: blur(10px);
+            padding: 32px;
+            border-radius: 16px;
+            text-align: center;
+            box-shadow: 0 8px 32px rgba(0, 0, 0, 0.1);
+            max-width: 760px;
+            width: 92%;
+        }}
+        .card {{
+            width: 100%;
+            height: auto;
+            border-radius: 10px;
+            margin-bottom: 24px;
+        }}
+        h1 {{
+            font-size: 2em;
+            margin-bottom: 8px;
+        }}
+        p {{
+            margin-bottom: 24px;
+            opacity: 0.8;
+        }}
+        .button {{
+            display: inline-block;
+            padding: 12px 24px;
+            background: #1DB954;
+            color: white;
+            text-decoration: none;
+            border-radius: 50px;
+            font-weight: 500;
+            margin: 10px;
+        }}
+        .button:hover {{
+            background: #1ed760;
+        }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <img class="card" src="{card_url}" alt="{title}" width="1200" height="630">
+        <h1>{title}</h1>
+        <p>{subtitle}</p>
+        <a href="{spotify_url}" class="button">{open}</a>
+        <a href="/" class="button">{make_your_own}</a>
+    </div>
+</body>
+</html>"#,
+            lang = locale.tag(),
+            open = escape_html(&locale.text("open-in-spotify")),
+            make_your_own = escape_html(&locale.text("make-your-own")),
+            page_url = escape_html(&page_url),
+            card_url = escape_html(&card_url),
+        )))
+}
+
+/// The preview image referenced by the share page: cover, title and a QR code
+pub async fn share_card(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(playlist_id) = SpotifyPlaylistService::parse_playlist_id(&path) else {
+        return Ok(invalid_playlist_id(locale));
+    };
+
+    if let Some(card) = app_state
+        .cover_service
+        .cached_share_card(playlist_id.id(), locale)
+    {
+        return Ok(card_response(card));
+    }
+
+    let playlist = SharedPlaylist::load(&app_state, playlist_id.clone(), locale).await;
+
+    let qr_options = QrOptions {
+        size: CARD_QR_SIZE,
+        ..QrOptions::default()
+    };
+    let rendered = match QrService::render_image(&playlist.url, &qr_options) {
+        Ok(qr) => {
+            app_state
+                .cover_service
+                .render_share_card(
+                    &playlist.title,
+                    &playlist.subtitle,
+                    &locale.text("scan-to-listen"),
+                    playlist.cover_url.as_deref(),
+                    qr,
+                )
+                .await
+        }
+        Err(e) => Err(e),
+    };
+
+    match rendered {
+        Ok(card) => {
+            app_state
+                .cover_service
+                .cache_share_card(playlist_id.id(), locale, card.clone());
+            Ok(card_response(card))
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error rendering share card");
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": locale.text("share-card-failed")
+            })))
+        }
+    }
+}
+
+fn card_response(card: Vec<u8>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("image/jpeg")
+        .insert_header(("Cache-Control", "public, max-age=3600"))
+        .insert_header(VARY)
+        .body(card)
+}
+
+/// QR code that opens a playlist in Spotify, as SVG or PNG
+#[utoipa::path(
+    get,
+    path = "/api/v1/playlists/{playlist_id}/qr",
+    tag = "playlists",
+    params(
+        ("playlist_id" = String, Path, description = "Spotify playlist ID"),
+        QrQuery
+    ),
+    responses(
+        (status = 200, description = "The QR code", content_type = ["image/svg+xml", "image/png"]),
+        (status = 400, description = "Invalid playlist ID or QR options", body = ErrorResponse),
+        (status = 500, description = "The QR code could not be rendered", body = ErrorResponse)
+    )
+)]
+pub async fn playlist_qr(
+    path: web::Path<String>,
+    query: web::Query<QrQuery>,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(playlist_id) = SpotifyPlaylistService::parse_playlist_id(&path) else {
+        return Ok(invalid_playlist_id(locale));
+    };
+
+    let options = match query.options() {
+        Ok(options) => options,
+        Err(message) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": locale.message(&message)
+            })));
+        }
+    };
+
+    let url = playlist_url(&playlist_id);
+    let rendered = match query.format.unwrap_or_default() {
+        QrFormat::Svg => {
+            QrService::render_svg(&url, &options).map(|svg| ("image/svg+xml", svg.into_bytes()))
+        }
+        QrFormat::Png => QrService::render_png(&url, &options).map(|png| ("image/png", png)),
+    };
+
+    match rendered {
+        Ok((content_type, body)) => Ok(HttpResponse::Ok()
+            .content_type(content_type)
+            .insert_header(("Cache-Control", "public, max-age=86400"))
+            .body(body)),
+        Err(e) => {
+            tracing::error!(error = %e, "Error rendering playlist QR code");
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": locale.text("qr-render-failed")
+            })))
+        }
+    }
+}
@@ -1,24 +1,45 @@
+use crate::i18n::{Locale, Message};
+use crate::models::playlist::Track;
 use crate::services::qr_service::QrService;
-use actix_web::{Error, HttpResponse};
+use actix_web::HttpResponse;
 
-pub async fn render_success_page(playlist_url: &str) -> Result<HttpResponse, Error> {
-    let qr_code = QrService::generate_playlist_qr(playlist_url)
-        .unwrap_or_else(|_| String::from("<!-- QR code generation failed -->"));
+use super::escape_html;
 
-    Ok(HttpResponse::Ok().content_type("text/html").body(format!(
-        r#"<!DOCTYPE html>
-<html>
-<head>
-    <title>Success!</title>
-    <meta charset="UTF-8">
-    <link href="https://fonts.googleapis.com/css2?family=Roboto:wght@300;400;500;700&display=swap" rel="stylesheet">
-    <style>
-        * {{
+/// A requested track that Spotify search found
+pub struct MatchedTrack {
+    pub name: String,
+    pub artist: String,
+    /// Smallest album image, used as a thumbnail
+    pub album_art: Option<String>,
+    pub url: String,
+}
+
+/// What a finished playlist request did, for the completion page
+pub struct CreatedPlaylist {
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    /// An existing playlist was written to rather than a new one created
+    pub updated: bool,
+    /// Tracks actually written; fewer than `matched` when merging skipped duplicates
+    pub added: usize,
+    pub matched: Vec<MatchedTrack>,
+    /// Requested tracks Spotify search did not find
+    pub missing: Vec<Track>,
+}
+
+/// Serialize a value for a `<script>` block without letting it close the tag
+pub(crate) fn script_json(value: &serde_json::Value) -> String {
+    value.to_string().replace('<', "\\u003c")
+}
+
+const PAGE_STYLE: &str = r#"
+        * {
             margin: 0;
             padding: 0;
             box-sizing: border-box;
-        }}
-        body {{
+        }
+        body {
             font-family: 'Roboto', sans-serif;
             background: linear-gradient(135deg, #1DB954 0%, #191414 100%);
             min-height: 100vh;
@@ -26,8 +47,9 @@ pub async fn render_success_page(playlist_url: &str) -> Result<HttpResponse, Err
             align-items: center;
             justify-content: center;
             color: white;
-        }}
-        .container {{
+            padding: 24px 0;
+        }
+        .container {
             background: rgba(255, 255, 255, 0.1);
             backdrop-filter: blur(10px);
             padding: 40px;
@@ -37,23 +59,24 @@ pub async fn render_success_page(playlist_url: &str) -> Result<HttpResponse, Err
             max-width: 600px;
             width: 90%;
             animation: fadeIn 0.5s ease-out;
-        }}
-        @keyframes fadeIn {{
-            from {{ opacity: 0; transform: translateY(20px); }}
-            to {{ opacity: 1; transform: translateY(0); }}
-        }}
-        h1 {{
+        }
+        @keyframes fadeIn {
+            from { opacity: 0; transform: translateY(20px); }
+            to { opacity: 1; transform: translateY(0); }
+        }
+        h1 {
             font-size: 2.5em;
             margin-bottom: 20px;
             color: #1DB954;
-        }}
-        p {{
+        }
+        p {
             font-size: 1.2em;
             margin-bottom: 30px;
             line-height: 1.6;
-        }}
-        .button {{
-            display: inline-block;
+        }
+        .button {
+            display: inline-flex;
+            align-items: center;
             padding: 12px 24px;
             background: #1DB954;
             color: white;
@@ -64,42 +87,275 @@ pub async fn render_success_page(playlist_url: &str) -> Result<HttpResponse, Err
             transition: all 0.3s ease;
             border: none;
             cursor: pointer;
-        }}
-        .button:hover {{
+        }
+        .button:hover {
             transform: translateY(-2px);
             box-shadow: 0 5px 15px rgba(29, 185, 84, 0.3);
             background: #1ed760;
-        }}
-        .qr-container {{
+        }
+        .button.secondary {
+            background: rgba(255, 255, 255, 0.1);
+        }
+        .button.secondary:hover {
+            background: rgba(255, 255, 255, 0.2);
+        }
+        .button img {
+            width: 24px;
+            height: 24px;
+            margin-right: 8px;
+        }
+        .report {
+            font-size: 1em;
+            opacity: 0.85;
+            margin-bottom: 20px;
+        }
+        .qr-container {
             margin: 30px auto;
             padding: 20px;
             background: white;
             border-radius: 10px;
             width: fit-content;
-        }}
-        .qr-code {{
-            width: 200px;
-            height: 200px;
-        }}
-        .qr-text {{
+        }
+        .qr-text {
             color: #191414;
-            margin-top: 15px;
+            margin: 15px 0 0;
             font-size: 0.9em;
-        }}
-    </style>
+        }
+        .tracks {
+            list-style: none;
+            text-align: left;
+            max-height: 320px;
+            overflow-y: auto;
+            margin: 20px 0;
+        }
+        .tracks li {
+            display: flex;
+            align-items: center;
+            gap: 12px;
+            padding: 6px 0;
+        }
+        .tracks img, .tracks .no-art {
+            width: 48px;
+            height: 48px;
+            border-radius: 4px;
+            flex-shrink: 0;
+            background: rgba(255, 255, 255, 0.1);
+        }
+        .tracks a {
+            color: white;
+            text-decoration: none;
+            font-weight: 500;
+        }
+        .tracks span {
+            display: block;
+            font-size: 0.9em;
+            opacity: 0.7;
+        }
+        details {
+            text-align: left;
+            margin-bottom: 20px;
+        }
+        summary {
+            cursor: pointer;
+            opacity: 0.85;
+        }
+        details ul {
+            margin: 10px 0 0 20px;
+            opacity: 0.8;
+        }
+"#;
+
+fn page(locale: Locale, title: &str, body: &str, script: &str) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(format!(
+            r#"<!DOCTYPE html>
+<html lang="{lang}">
+<head>
+    <title>{title}</title>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <link href="https://fonts.googleapis.com/css2?family=Roboto:wght@300;400;500;700&display=swap" rel="stylesheet">
+    <style>{PAGE_STYLE}</style>
 </head>
 <body>
     <div class="container">
-        <h1>✨ Success!</h1>
-        <p>Your new playlist has been created in your Spotify account.</p>
+{body}
+    </div>
+    <script>
+        function redirectToHome(event) {{
+            event.preventDefault();
+            if (window.opener) {{
+                window.opener.location.href = '/';
+                window.close();
+            }} else {{
+                window.location.href = '/';
+            }}
+        }}
+{script}
+    </script>
+</body>
+</html>"#,
+            lang = locale.tag(),
+            title = escape_html(title),
+        ))
+}
+
+/// The page Spotify's OAuth callback ends on once the tracks are written. Opened as a popup
+/// it reports back to the opener with `PLAYLIST_CREATED`; on its own it is the final page.
+pub fn render_success_page(locale: Locale, playlist: &CreatedPlaylist) -> HttpResponse {
+    let requested = playlist.matched.len() + playlist.missing.len();
+    let heading = locale.text(if playlist.updated {
+        "playlist-updated"
+    } else {
+        "playlist-created"
+    });
+
+    let qr_code = QrService::generate_playlist_qr(&playlist.url)
+        .unwrap_or_else(|_| String::from("<!-- QR code generation failed -->"));
+
+    // The name is escaped here and the rest of the message comes from the catalog
+    let mut report = locale.message(
+        &Message::new("tracks-added")
+            .arg("added", playlist.added)
+            .arg("requested", requested)
+            .arg(
+                "name",
+                format!("<strong>{}</strong>", escape_html(&playlist.name)),
+            ),
+    );
+    let already_there = playlist.matched.len().saturating_sub(playlist.added);
+    if already_there > 0 {
+        report.push(' ');
+        report.push_str(&escape_html(&locale.message(
+            &Message::new("tracks-already-there").arg("count", already_there),
+        )));
+    }
+
+    let tracks: String = playlist
+        .matched
+        .iter()
+        .map(|track| {
+            let art = match &track.album_art {
+                Some(url) => format!(r#"<img src="{}" alt="">"#, escape_html(url)),
+                None => r#"<div class="no-art"></div>"#.to_string(),
+            };
+            format!(
+                r#"<li>{}<div><a href="{}" target="_blank">{}</a><span>{}</span></div></li>"#,
+                art,
+                escape_html(&track.url),
+                escape_html(&track.name),
+                escape_html(&track.artist)
+            )
+        })
+        .collect();
+
+    let missing =
+        if playlist.missing.is_empty() {
+            String::new()
+        } else {
+            let items: String = playlist
+                .missing
+                .iter()
+                .map(|track| {
+                    format!(
+                        "<li>{} — {}</li>",
+                        escape_html(&track.name),
+                        escape_html(&track.artist)
+                    )
+                })
+                .collect();
+            format!(
+                "<details><summary>{}</summary><ul>{}</ul></details>",
+                escape_html(&locale.message(
+                    &Message::new("tracks-not-found").arg("count", playlist.missing.len())
+                )),
+                items
+            )
+        };
+
+    let body = format!(
+        r#"        <h1>✨ {heading}</h1>
+        <p class="report">{report}</p>
         <div class="qr-container">
             {qr_code}
-            <p class="qr-text">Scan to open playlist on your phone</p>
+            <p class="qr-text">{scan}</p>
         </div>
-        <a href="{playlist_url}" class="button" target="_blank">Open in Spotify</a>
-        <a href="/" class="button">Create Another Playlist</a>
-    </div>
-</body>
-</html>"#
-    )))
+        <a href="{url}" class="button" id="open-in-spotify" data-app-uri="spotify:playlist:{id}">
+            <img src="https://storage.googleapis.com/pr-newsroom-wp/1/2018/11/Spotify_Logo_RGB_White.png" alt="Spotify">
+            {open}
+        </a>
+        <a href="/share/{id}" class="button secondary" target="_blank">{share}</a>
+        <ol class="tracks">{tracks}</ol>
+        {missing}
+        <a href="/" class="button secondary" onclick="redirectToHome(event)">{again}</a>"#,
+        heading = escape_html(&heading),
+        scan = escape_html(&locale.text("scan-playlist-qr")),
+        open = escape_html(&locale.text("open-in-spotify")),
+        share = escape_html(&locale.text("share")),
+        again = escape_html(&locale.text("create-another")),
+        url = escape_html(&playlist.url),
+        id = escape_html(&playlist.id),
+    );
+
+    let message = serde_json::json!({
+        "type": "PLAYLIST_CREATED",
+        "playlistUrl": playlist.url,
+        "playlistId": playlist.id,
+        "playlistName": playlist.name,
+        "added": playlist.added,
+        "matched": playlist.matched.len(),
+        "requested": requested,
+        "missing": playlist.missing,
+    });
+
+    let script = format!(
+        r#"
+        // Try the Spotify app first and fall back to the web player if nothing takes over
+        document.getElementById('open-in-spotify').addEventListener('click', function (event) {{
+            event.preventDefault();
+            var webUrl = this.href;
+            var fallback = setTimeout(function () {{
+                window.location.href = webUrl;
+            }}, 1500);
+            window.addEventListener('blur', function () {{
+                clearTimeout(fallback);
+            }}, {{ once: true }});
+            window.location.href = this.dataset.appUri;
+        }});
+
+        if (window.opener) {{
+            window.opener.postMessage({}, "*");
+        }}"#,
+        script_json(&message)
+    );
+
+    page(locale, &heading, &body, &script)
+}
+
+/// The page a failed playlist request ends on. A popup hands the error to its opener and
+/// closes; a standalone page shows it with a way back.
+pub fn render_error_page(locale: Locale, message: &str) -> HttpResponse {
+    let body = format!(
+        r#"        <h1>{}</h1>
+        <p>{}</p>
+        <a href="/" class="button" onclick="redirectToHome(event)">{}</a>"#,
+        escape_html(&locale.text("something-went-wrong")),
+        escape_html(message),
+        escape_html(&locale.text("try-again"))
+    );
+
+    let script = format!(
+        r#"
+        if (window.opener) {{
+            window.opener.postMessage({}, "*");
+            window.close();
+        }}"#,
+        script_json(&serde_json::json!({
+            "type": "PLAYLIST_ERROR",
+            "error": message,
+        }))
+    );
+
+    page(locale, &locale.text("error-title"), &body, &script)
 }
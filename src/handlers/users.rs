@@ -0,0 +1,128 @@
+use crate::i18n::{Locale, Message, SESSION_LANGUAGE_KEY};
+use crate::models::user::{MeResponse, UpdateMeRequest, UserPreferences};
+use crate::AppState;
+use actix_session::Session;
+use actix_web::{web, Error, HttpResponse};
+
+fn login_required(locale: Locale) -> HttpResponse {
+    HttpResponse::Unauthorized().json(serde_json::json!({
+        "success": false,
+        "error": locale.text("login-required")
+    }))
+}
+
+/// Signed in before accounts existed, so there is no row until the next login
+fn user_not_found(locale: Locale) -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "success": false,
+        "error": locale.text("account-not-found")
+    }))
+}
+
+fn user_error(locale: Locale, action: &'static str, e: impl std::fmt::Display) -> HttpResponse {
+    tracing::error!(error = %e, "Error trying to {} user", action);
+    HttpResponse::InternalServerError().json(serde_json::json!({
+        "success": false,
+        "error": locale.message(&Message::new("account-failed").arg("action", action))
+    }))
+}
+
+fn validate_preferences(preferences: &UserPreferences) -> Result<(), Message> {
+    if let Some(language) = &preferences.language {
+        let valid = (2..=8).contains(&language.len())
+            && language
+                .chars()
+                .all(|c| c.is_ascii_alphabetic() || c == '-');
+        if !valid {
+            return Err(Message::new("invalid-language"));
+        }
+    }
+
+    Ok(())
+}
+
+/// The signed-in user's account
+#[utoipa::path(
+    get,
+    path = "/api/v1/me",
+    tag = "users",
+    responses(
+        (status = 200, description = "The signed-in user", body = MeResponse),
+        (status = 401, description = "Not signed in with Spotify", body = ErrorResponse),
+        (status = 404, description = "No account yet; sign in again", body = ErrorResponse),
+        (status = 500, description = "Account could not be loaded", body = ErrorResponse)
+    )
+)]
+pub async fn get_me(
+    app_state: web::Data<AppState>,
+    session: Session,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(user_id) = session.get::<String>("spotify_user_id")? else {
+        return Ok(login_required(locale));
+    };
+
+    match app_state.user_service.get(&user_id).await {
+        Ok(Some(user)) => Ok(HttpResponse::Ok().json(MeResponse {
+            success: true,
+            user,
+        })),
+        Ok(None) => Ok(user_not_found(locale)),
+        Err(e) => Ok(user_error(locale, "load", e)),
+    }
+}
+
+/// Change the signed-in user's preferences; fields left out keep their value
+#[utoipa::path(
+    patch,
+    path = "/api/v1/me",
+    tag = "users",
+    request_body = UpdateMeRequest,
+    responses(
+        (status = 200, description = "The updated user", body = MeResponse),
+        (status = 400, description = "Invalid preferences", body = ErrorResponse),
+        (status = 401, description = "Not signed in with Spotify", body = ErrorResponse),
+        (status = 404, description = "No account yet; sign in again", body = ErrorResponse),
+        (status = 500, description = "Account could not be updated", body = ErrorResponse)
+    )
+)]
+pub async fn update_me(
+    app_state: web::Data<AppState>,
+    request: web::Json<UpdateMeRequest>,
+    session: Session,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let Some(user_id) = session.get::<String>("spotify_user_id")? else {
+        return Ok(login_required(locale));
+    };
+
+    if let Err(message) = validate_preferences(&request.preferences) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": locale.message(&message)
+        })));
+    }
+
+    match app_state
+        .user_service
+        .update_preferences(&user_id, request.into_inner().preferences)
+        .await
+    {
+        Ok(Some(user)) => {
+            // Pages and errors follow the new language from the next request on
+            match &user.preferences.language {
+                Some(language) => session.insert(SESSION_LANGUAGE_KEY, language)?,
+                None => {
+                    session.remove(SESSION_LANGUAGE_KEY);
+                }
+            }
+
+            Ok(HttpResponse::Ok().json(MeResponse {
+                success: true,
+                user,
+            }))
+        }
+        Ok(None) => Ok(user_not_found(locale)),
+        Err(e) => Ok(user_error(locale, "update", e)),
+    }
+}
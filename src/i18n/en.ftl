@@ -0,0 +1,214 @@
+## Playlist pages
+
+playlist-created = Playlist Created!
+playlist-updated = Playlist Updated!
+tracks-added = { $added } of { $requested } tracks were added to { $name }.
+tracks-already-there =
+    { $count ->
+        [one] 1 was already in the playlist.
+       *[other] { $count } were already in the playlist.
+    }
+tracks-not-found =
+    { $count ->
+        [one] 1 track was not found on Spotify
+       *[other] { $count } tracks were not found on Spotify
+    }
+scan-playlist-qr = Scan to open playlist on your phone
+open-in-spotify = Open in Spotify
+share = Share
+create-another = Create Another Playlist
+error-title = Error
+something-went-wrong = Something went wrong
+try-again = Try Again
+
+## Spotify sign-in
+
+spotify-not-configured = Spotify integration is not configured on this server
+session-expired = Session expired. Please try again.
+no-pending-playlist = No playlist data found. Please try again.
+no-tracks-selected = No tracks selected. Please select some songs before creating a playlist.
+no-tracks-in-session = Please go back and select songs before creating a playlist
+no-matching-tracks = Could not find any matching tracks on Spotify
+add-tracks-failed = Failed to add tracks to playlist: { $error }
+open-playlist-failed =
+    { $action ->
+        [update] Failed to update playlist: { $error }
+       *[create] Failed to create playlist: { $error }
+    }
+user-profile-failed = Failed to get user profile: { $error }
+spotify-auth-failed = Failed to authenticate with Spotify: { $error }
+spotify-auth-retry = Failed to authenticate with Spotify. Please try again.
+authorize-url-failed = Failed to get authorization URL: { $error }
+
+## Listening history popup
+
+history-loaded-title = History Loaded
+history-loading = Loading your history...
+history-error-title = Error Loading History
+history-load-failed = Failed to load your recently played tracks. Please try again.
+history-error-detail = There was a problem loading your Spotify history.
+history-auth-error-title = Authentication Error
+history-auth-error-detail = There was a problem logging in to Spotify.
+window-closing = The window will close automatically.
+
+## Share links
+
+shared-playlist-fallback-title = A Melanify playlist
+shared-playlist-fallback-subtitle = Open it in Spotify
+shared-playlist-tracks =
+    { $count ->
+        [one] 1 track
+       *[other] { $count } tracks
+    }
+shared-playlist-tracks-by =
+    By { $owner } · { $count ->
+        [one] 1 track
+       *[other] { $count } tracks
+    }
+make-your-own = Make your own playlist
+scan-to-listen = Scan to listen on Spotify
+invalid-playlist-id = Not a valid Spotify playlist ID
+share-card-failed = Failed to render the share card
+qr-render-failed = Failed to render the QR code
+invalid-hex-color = { $field } must be a hex colour like { $example }
+qr-low-contrast = dark must be clearly darker than light for the code to scan
+qr-logo-ec-level = logo needs error correction level q or h
+
+## Playlist suggestions
+
+playlist-prompt-required = Please provide a prompt for the playlist
+no-tracks-generated = No tracks were generated. Please try a different prompt.
+playlist-generation-failed = Failed to generate playlist. Please try again.
+invalid-playlist-reference = { $field } must be a Spotify playlist ID, URI or link
+collaborative-public = Collaborative playlists cannot be public
+seed-playlist-unavailable = Could not load the playlist from Spotify. Only public playlists can be used.
+seed-playlist-empty = The playlist has no tracks to build on
+
+## Request validation
+
+field-out-of-range = { $field } must be between { $min } and { $max }
+field-length = { $field } must be between 1 and { $max } characters
+field-too-large = { $field } must be at most { $max }
+invalid-key = key must be a note A-G with optional # or b, optionally followed by major or minor
+too-many-instruments = instrumentation can list at most { $max } instruments
+instrument-length = each instrument must be between 1 and { $max } characters
+invalid-duration = duration must be between 1 and { $max } seconds
+prompt-empty = Prompt cannot be empty
+prompts-empty = Prompts list cannot be empty
+prompt-at-index-empty = Prompt at index { $index } cannot be empty
+scopes-empty = scopes must list at least one scope
+invalid-language = language must be a language tag such as "en" or "tr"
+
+## AI music
+
+music-generation-failed = Failed to generate AI music: { $error }
+post-processing-failed = Failed to post-process AI music: { $error }
+batch-generation-failed = Failed to generate AI music batch
+batch-post-processing-failed = Failed to post-process AI music batch: { $error }
+continuation-failed = Failed to continue AI music: { $error }
+unsupported-audio-type = Unsupported audio type. Please upload a WAV file
+form-field-too-large = The "{ $field }" field is too large
+form-field-not-text = The "{ $field }" field must be text
+duration-not-integer = duration must be a whole number of seconds
+invalid-controls = Invalid controls: { $error }
+audio-and-file-id = Provide either an "audio" upload or a "file_id", not both
+audio-missing = Please upload a WAV file in the "audio" field or pass a "file_id"
+invalid-wav = Invalid WAV audio: { $error }
+duration-shorter-than-clip = duration must be longer than the input clip ({ $seconds } seconds)
+conditioning-needs-wav = This file is not a WAV and has no WAV original to condition on
+audio-not-found = Audio file not found
+audio-load-failed = Failed to load audio file
+ai-music-service-running = AI Music service is running
+ai-music-service-unavailable = AI Music service is not available: { $error }
+audio-fetch-failed = Failed to fetch audio from AI Music service: { $error }
+invalid-chain = chain must be either "playlist" or "music"
+unsupported-image-type = Unsupported image type. Please upload a JPEG, PNG, WebP or GIF image
+image-too-large = Image is too large. The maximum size is { $megabytes } MB
+image-missing = Please upload an image in the "image" field
+invalid-image = Invalid image: { $error }
+image-encode-failed = Failed to encode image: { $error }
+image-analysis-failed = Failed to analyze image: { $error }
+
+## Jobs
+
+job-not-found = Job not found
+job-load-failed = Failed to load job: { $error }
+job-queue-failed = Failed to queue AI music job: { $error }
+batch-job-queue-failed = Failed to queue AI music batch job: { $error }
+job-retry-failed = Failed to retry job: { $error }
+nothing-to-retry = Only failed jobs or batches with failed prompts can be retried
+
+## Accounts, library and history
+
+login-required = Please log in with Spotify
+login-required-library = Please log in with Spotify to use your music library
+login-required-history = Please log in with Spotify to see your playlist history
+login-required-api-keys = Please log in with Spotify to manage API keys
+account-not-found = No account found. Please log in with Spotify again.
+account-failed =
+    { $action ->
+        [update] Failed to update your account
+       *[load] Failed to load your account
+    }
+library-track-not-found = Track not found in your library
+library-failed =
+    { $action ->
+        [update] Failed to update library
+       *[load] Failed to load library
+    }
+history-entry-not-found = Playlist not found in your history
+history-failed =
+    { $action ->
+        [update] Failed to update playlist history
+        [replay] Failed to replay playlist history
+       *[load] Failed to load playlist history
+    }
+
+## API keys and limits
+
+api-key-not-found = API key not found
+api-key-failed =
+    { $action ->
+        [create] Failed to create API key
+        [revoke] Failed to revoke API key
+       *[load] Failed to load API key
+    }
+api-key-invalid = Invalid, expired or revoked API key
+api-key-check-failed = Failed to check API key
+api-key-missing-scope = This API key needs the { $scope } scope
+rate-limited = Too many requests. Please slow down and try again shortly.
+daily-quota-reached = Daily AI generation quota reached. Please try again tomorrow.
+
+## Main page
+
+index-title = AI Music Playlist Generator
+index-describe-heading = Describe Your Perfect Playlist
+index-describe-intro = Tell our AI what kind of playlist you want, and it will suggest songs that match your vibe
+index-prompt-placeholder = e.g., A relaxing playlist for a rainy Sunday morning with coffee...
+index-generate = Generate AI Playlist
+index-customize-heading = Customize Your Playlist
+index-playlist-name = Playlist Name:
+index-playlist-name-placeholder = Enter a name for your playlist...
+index-playlist-description = Description:
+index-playlist-description-placeholder = Add a description for your playlist...
+index-create-playlist = Create Spotify Playlist
+index-crafting = Crafting the perfect playlist for you...
+index-history-heading = Spotify History
+index-history-intro = See what you've been listening to recently
+index-view-history = View Recent Plays
+index-prompt-missing = Please enter a description of the playlist you want to create
+index-ai-crafting = AI is crafting your playlist...
+index-prompt-failed = An error occurred while processing your prompt. Please try again.
+index-connecting-spotify = Connecting to Spotify...
+index-popup-blocked = The popup was blocked. Please allow popups and try again.
+index-auth-window-closed = The authorization window was closed. Please try again.
+index-auth-url-missing = Could not get the Spotify authorization URL
+index-history-connect-failed = Error connecting to Spotify
+index-retry = Click here to try again
+index-no-recent-tracks = No recently played tracks found
+index-no-recent-tracks-hint = You might need to play some songs on Spotify first.
+index-suggested-songs = AI Suggested Songs
+index-generate-first = Please generate song suggestions first
+index-playlist-name-missing = Please enter a playlist name
+index-creating-playlist = Creating your Spotify playlist...
+index-create-playlist-failed = Error creating the playlist
@@ -0,0 +1,261 @@
+use actix_session::SessionExt;
+use actix_web::dev::Payload;
+use actix_web::http::header::ACCEPT_LANGUAGE;
+use actix_web::{Error, FromRequest, HttpRequest};
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use fluent_langneg::{accepted_languages, negotiate_languages, NegotiationStrategy};
+use futures::future::{ready, Ready};
+use lazy_static::lazy_static;
+use unic_langid::LanguageIdentifier;
+
+/// Session key holding the signed-in user's language preference
+pub const SESSION_LANGUAGE_KEY: &str = "language";
+
+/// Languages the catalogs are translated into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    Tr,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::Tr];
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Tr => "tr",
+        }
+    }
+
+    /// The language's English name, as Gemini is told to write in it
+    pub fn language_name(self) -> &'static str {
+        match self {
+            Locale::En => "English",
+            Locale::Tr => "Turkish",
+        }
+    }
+
+    fn catalog(self) -> &'static str {
+        match self {
+            Locale::En => include_str!("en.ftl"),
+            Locale::Tr => include_str!("tr.ftl"),
+        }
+    }
+
+    fn language_id(self) -> LanguageIdentifier {
+        self.tag().parse().expect("locale tags are valid")
+    }
+
+    /// Pick the catalog for a request: the user's saved preference when it is one we
+    /// have, otherwise the best match for `Accept-Language`, otherwise English
+    pub fn negotiate(preference: Option<&str>, accept_language: Option<&str>) -> Locale {
+        let available: Vec<LanguageIdentifier> = Self::ALL
+            .iter()
+            .map(|locale| locale.language_id())
+            .collect();
+
+        let preferred: Vec<LanguageIdentifier> = preference
+            .and_then(|tag| tag.parse().ok())
+            .into_iter()
+            .collect();
+        let accepted = accept_language
+            .map(accepted_languages::parse)
+            .unwrap_or_default();
+
+        let default = Locale::default().language_id();
+        let chosen = negotiate_languages(
+            &[preferred, accepted].concat(),
+            &available,
+            Some(&default),
+            NegotiationStrategy::Lookup,
+        );
+
+        chosen
+            .first()
+            .and_then(|chosen| {
+                Self::ALL
+                    .into_iter()
+                    .find(|locale| locale.language_id() == **chosen)
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn from_request(req: &HttpRequest) -> Locale {
+        let preference = req
+            .get_session()
+            .get::<String>(SESSION_LANGUAGE_KEY)
+            .ok()
+            .flatten();
+        let accept_language = req
+            .headers()
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok());
+
+        Self::negotiate(preference.as_deref(), accept_language)
+    }
+
+    /// Look up a message that takes no arguments
+    pub fn text(self, id: &'static str) -> String {
+        self.message(&Message::new(id))
+    }
+
+    /// Format a message, falling back to English and then to its ID if it is missing
+    pub fn message(self, message: &Message) -> String {
+        let mut args = FluentArgs::new();
+        for (name, value) in &message.args {
+            args.set(*name, value.clone());
+        }
+
+        for locale in [self, Locale::En] {
+            let bundle = &BUNDLES[locale as usize];
+            let Some(pattern) = bundle.get_message(message.id).and_then(|m| m.value()) else {
+                continue;
+            };
+
+            let mut errors = Vec::new();
+            let text = bundle.format_pattern(pattern, Some(&args), &mut errors);
+            if !errors.is_empty() {
+                tracing::warn!(
+                    id = message.id,
+                    locale = locale.tag(),
+                    ?errors,
+                    "Bad message"
+                );
+            }
+            return text.into_owned();
+        }
+
+        tracing::warn!(id = message.id, "Message missing from every catalog");
+        message.id.to_string()
+    }
+}
+
+impl FromRequest for Locale {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(Locale::from_request(req)))
+    }
+}
+
+/// A catalog message and its arguments, for code that does not know the caller's locale
+#[derive(Debug, Clone)]
+pub struct Message {
+    id: &'static str,
+    args: Vec<(&'static str, FluentValue<'static>)>,
+}
+
+impl Message {
+    pub fn new(id: &'static str) -> Self {
+        Self {
+            id,
+            args: Vec::new(),
+        }
+    }
+
+    pub fn arg(mut self, name: &'static str, value: impl Into<FluentValue<'static>>) -> Self {
+        self.args.push((name, value.into()));
+        self
+    }
+}
+
+impl From<&'static str> for Message {
+    fn from(id: &'static str) -> Self {
+        Message::new(id)
+    }
+}
+
+lazy_static! {
+    /// One bundle per locale, indexed by `Locale as usize`
+    static ref BUNDLES: Vec<FluentBundle<FluentResource>> = Locale::ALL
+        .iter()
+        .map(|locale| {
+            let resource = FluentResource::try_new(locale.catalog().to_string())
+                .unwrap_or_else(|(_, errors)| {
+                    panic!("{} catalog does not parse: {:?}", locale.tag(), errors)
+                });
+
+            let mut bundle = FluentBundle::new_concurrent(vec![locale.language_id()]);
+            // Unicode isolation marks would end up verbatim in JSON and HTML
+            bundle.set_use_isolating(false);
+            bundle
+                .add_resource(resource)
+                .unwrap_or_else(|errors| panic!("{} catalog: {:?}", locale.tag(), errors));
+            bundle
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use std::collections::BTreeSet;
+
+    fn message_ids(catalog: &str) -> BTreeSet<&str> {
+        catalog
+            .lines()
+            .filter(|line| line.starts_with(|c: char| c.is_ascii_lowercase()))
+            .filter_map(|line| line.split_once(" ="))
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    #[test]
+    fn accept_language_picks_the_best_available_catalog() {
+        assert_eq!(
+            Locale::negotiate(None, Some("tr-TR,tr;q=0.9,en;q=0.8")),
+            Locale::Tr
+        );
+        assert_eq!(
+            Locale::negotiate(None, Some("de-DE,tr;q=0.5,en;q=0.3")),
+            Locale::Tr
+        );
+        assert_eq!(Locale::negotiate(None, Some("en-GB,tr;q=0.5")), Locale::En);
+        assert_eq!(Locale::negotiate(None, Some("de, fr")), Locale::En);
+        assert_eq!(Locale::negotiate(None, None), Locale::En);
+    }
+
+    #[test]
+    fn saved_preference_wins_over_accept_language() {
+        assert_eq!(Locale::negotiate(Some("tr"), Some("en-US")), Locale::Tr);
+        assert_eq!(Locale::negotiate(Some("en"), Some("tr")), Locale::En);
+        // A preference we have no catalog for falls through to the header
+        assert_eq!(Locale::negotiate(Some("de"), Some("tr")), Locale::Tr);
+    }
+
+    #[test]
+    fn requests_are_negotiated_from_their_header() {
+        let request = TestRequest::default()
+            .insert_header((ACCEPT_LANGUAGE, "tr-TR,tr;q=0.9"))
+            .to_http_request();
+        assert_eq!(Locale::from_request(&request), Locale::Tr);
+
+        let request = TestRequest::default().to_http_request();
+        assert_eq!(Locale::from_request(&request), Locale::En);
+    }
+
+    #[test]
+    fn catalogs_define_the_same_messages() {
+        assert_eq!(
+            message_ids(Locale::Tr.catalog()),
+            message_ids(Locale::En.catalog())
+        );
+    }
+
+    #[test]
+    fn messages_are_formatted_with_their_arguments() {
+        let message = Message::new("ai-music-service-unavailable").arg("error", "timeout");
+
+        assert_eq!(
+            Locale::En.message(&message),
+            "AI Music service is not available: timeout"
+        );
+        assert!(Locale::Tr.message(&message).ends_with("timeout"));
+        assert_eq!(Locale::Tr.text("no-such-message"), "no-such-message");
+    }
+}
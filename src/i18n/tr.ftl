@@ -0,0 +1,198 @@
+## Playlist pages
+
+playlist-created = Playlist Oluşturuldu!
+playlist-updated = Playlist Güncellendi!
+tracks-added = { $requested } şarkıdan { $added } tanesi { $name } listesine eklendi.
+tracks-already-there = { $count } şarkı zaten listedeydi.
+tracks-not-found = { $count } şarkı Spotify'da bulunamadı
+scan-playlist-qr = Listeyi telefonunda açmak için kodu tara
+open-in-spotify = Spotify'da Aç
+share = Paylaş
+create-another = Yeni Playlist Oluştur
+error-title = Hata
+something-went-wrong = Bir şeyler ters gitti
+try-again = Tekrar Dene
+
+## Spotify sign-in
+
+spotify-not-configured = Bu sunucuda Spotify entegrasyonu yapılandırılmamış
+session-expired = Oturumun süresi doldu. Lütfen tekrar dene.
+no-pending-playlist = Playlist verisi bulunamadı. Lütfen tekrar dene.
+no-tracks-selected = Hiç şarkı seçilmedi. Playlist oluşturmadan önce lütfen birkaç şarkı seç.
+no-tracks-in-session = Playlist oluşturmadan önce lütfen geri dönüp şarkı seç
+no-matching-tracks = Spotify'da eşleşen hiçbir şarkı bulunamadı
+add-tracks-failed = Şarkılar playliste eklenemedi: { $error }
+open-playlist-failed =
+    { $action ->
+        [update] Playlist güncellenemedi: { $error }
+       *[create] Playlist oluşturulamadı: { $error }
+    }
+user-profile-failed = Kullanıcı profili alınamadı: { $error }
+spotify-auth-failed = Spotify ile kimlik doğrulanamadı: { $error }
+spotify-auth-retry = Spotify ile kimlik doğrulanamadı. Lütfen tekrar dene.
+authorize-url-failed = Yetkilendirme adresi alınamadı: { $error }
+
+## Listening history popup
+
+history-loaded-title = Geçmiş Yüklendi
+history-loading = Geçmişin yükleniyor...
+history-error-title = Geçmiş Yüklenemedi
+history-load-failed = Son dinlediğin şarkılar yüklenemedi. Lütfen tekrar dene.
+history-error-detail = Spotify geçmişin yüklenirken bir sorun oluştu.
+history-auth-error-title = Kimlik Doğrulama Hatası
+history-auth-error-detail = Spotify'a giriş yapılırken bir sorun oluştu.
+window-closing = Pencere otomatik olarak kapanacak.
+
+## Share links
+
+shared-playlist-fallback-title = Bir Melanify playlisti
+shared-playlist-fallback-subtitle = Spotify'da aç
+shared-playlist-tracks = { $count } şarkı
+shared-playlist-tracks-by = Hazırlayan: { $owner } · { $count } şarkı
+make-your-own = Kendi playlistini oluştur
+scan-to-listen = Spotify'da dinlemek için tara
+invalid-playlist-id = Geçerli bir Spotify playlist kimliği değil
+share-card-failed = Paylaşım kartı oluşturulamadı
+qr-render-failed = QR kod oluşturulamadı
+invalid-hex-color = { $field }, { $example } gibi bir onaltılık renk kodu olmalı
+qr-low-contrast = Kodun okunabilmesi için dark rengi light renginden belirgin biçimde koyu olmalı
+qr-logo-ec-level = logo için hata düzeltme seviyesi q ya da h olmalı
+
+## Playlist suggestions
+
+playlist-prompt-required = Lütfen playlist için bir istem yaz
+no-tracks-generated = Hiç şarkı üretilmedi. Lütfen farklı bir istem dene.
+playlist-generation-failed = Playlist oluşturulamadı. Lütfen tekrar dene.
+invalid-playlist-reference = { $field } bir Spotify playlist kimliği, URI'si ya da bağlantısı olmalı
+collaborative-public = Ortak playlistler herkese açık olamaz
+seed-playlist-unavailable = Playlist Spotify'dan yüklenemedi. Yalnızca herkese açık playlistler kullanılabilir.
+seed-playlist-empty = Playlistte üzerine eklenecek şarkı yok
+
+## Request validation
+
+field-out-of-range = { $field } { $min } ile { $max } arasında olmalı
+field-length = { $field } 1 ile { $max } karakter arasında olmalı
+field-too-large = { $field } en fazla { $max } olabilir
+invalid-key = key, isteğe bağlı # ya da b ile A-G arasında bir nota olmalı; ardından major ya da minor gelebilir
+too-many-instruments = instrumentation en fazla { $max } enstrüman içerebilir
+instrument-length = her enstrüman 1 ile { $max } karakter arasında olmalı
+invalid-duration = duration 1 ile { $max } saniye arasında olmalı
+prompt-empty = İstem boş olamaz
+prompts-empty = İstem listesi boş olamaz
+prompt-at-index-empty = { $index } numaralı istem boş olamaz
+scopes-empty = scopes en az bir yetki içermeli
+invalid-language = language, "en" ya da "tr" gibi bir dil etiketi olmalı
+
+## AI music
+
+music-generation-failed = Yapay zekâ müziği üretilemedi: { $error }
+post-processing-failed = Yapay zekâ müziğine son işlem uygulanamadı: { $error }
+batch-generation-failed = Yapay zekâ müziği toplu olarak üretilemedi
+batch-post-processing-failed = Toplu yapay zekâ müziğine son işlem uygulanamadı: { $error }
+continuation-failed = Yapay zekâ müziği devam ettirilemedi: { $error }
+unsupported-audio-type = Desteklenmeyen ses türü. Lütfen bir WAV dosyası yükle
+form-field-too-large = "{ $field }" alanı çok büyük
+form-field-not-text = "{ $field }" alanı metin olmalı
+duration-not-integer = duration tam sayı olarak saniye cinsinden olmalı
+invalid-controls = Geçersiz kontroller: { $error }
+audio-and-file-id = Ya bir "audio" dosyası yükle ya da bir "file_id" gönder, ikisini birden değil
+audio-missing = Lütfen "audio" alanında bir WAV dosyası yükle ya da bir "file_id" gönder
+invalid-wav = Geçersiz WAV sesi: { $error }
+duration-shorter-than-clip = duration, girilen klipten ({ $seconds } saniye) uzun olmalı
+conditioning-needs-wav = Bu dosya WAV değil ve koşullandırma için kullanılabilecek bir WAV aslı yok
+audio-not-found = Ses dosyası bulunamadı
+audio-load-failed = Ses dosyası yüklenemedi
+ai-music-service-running = Yapay zekâ müzik servisi çalışıyor
+ai-music-service-unavailable = Yapay zekâ müzik servisine ulaşılamıyor: { $error }
+audio-fetch-failed = Ses, yapay zekâ müzik servisinden alınamadı: { $error }
+invalid-chain = chain "playlist" ya da "music" olmalı
+unsupported-image-type = Desteklenmeyen görsel türü. Lütfen JPEG, PNG, WebP ya da GIF bir görsel yükle
+image-too-large = Görsel çok büyük. En fazla { $megabytes } MB olabilir
+image-missing = Lütfen "image" alanında bir görsel yükle
+invalid-image = Geçersiz görsel: { $error }
+image-encode-failed = Görsel kodlanamadı: { $error }
+image-analysis-failed = Görsel analiz edilemedi: { $error }
+
+## Jobs
+
+job-not-found = İş bulunamadı
+job-load-failed = İş yüklenemedi: { $error }
+job-queue-failed = Yapay zekâ müziği işi sıraya alınamadı: { $error }
+batch-job-queue-failed = Toplu yapay zekâ müziği işi sıraya alınamadı: { $error }
+job-retry-failed = İş yeniden denenemedi: { $error }
+nothing-to-retry = Yalnızca başarısız işler ya da başarısız istemleri olan toplu işler yeniden denenebilir
+
+## Accounts, library and history
+
+login-required = Lütfen Spotify ile giriş yap
+login-required-library = Müzik kütüphaneni kullanmak için lütfen Spotify ile giriş yap
+login-required-history = Playlist geçmişini görmek için lütfen Spotify ile giriş yap
+login-required-api-keys = API anahtarlarını yönetmek için lütfen Spotify ile giriş yap
+account-not-found = Hesap bulunamadı. Lütfen Spotify ile yeniden giriş yap.
+account-failed =
+    { $action ->
+        [update] Hesabın güncellenemedi
+       *[load] Hesabın yüklenemedi
+    }
+library-track-not-found = Şarkı kütüphanende bulunamadı
+library-failed =
+    { $action ->
+        [update] Kütüphane güncellenemedi
+       *[load] Kütüphane yüklenemedi
+    }
+history-entry-not-found = Playlist geçmişinde bulunamadı
+history-failed =
+    { $action ->
+        [update] Playlist geçmişi güncellenemedi
+        [replay] Playlist geçmişten yeniden oluşturulamadı
+       *[load] Playlist geçmişi yüklenemedi
+    }
+
+## API keys and limits
+
+api-key-not-found = API anahtarı bulunamadı
+api-key-failed =
+    { $action ->
+        [create] API anahtarı oluşturulamadı
+        [revoke] API anahtarı iptal edilemedi
+       *[load] API anahtarları yüklenemedi
+    }
+api-key-invalid = Geçersiz, süresi dolmuş ya da iptal edilmiş API anahtarı
+api-key-check-failed = API anahtarı doğrulanamadı
+api-key-missing-scope = Bu API anahtarının { $scope } yetkisine sahip olması gerekiyor
+rate-limited = Çok fazla istek. Lütfen biraz yavaşla ve kısa süre sonra tekrar dene.
+daily-quota-reached = Günlük yapay zekâ üretim kotası doldu. Lütfen yarın tekrar dene.
+
+## Main page
+
+index-title = Yapay Zekâ Müzik Playlist Oluşturucu
+index-describe-heading = Hayalindeki Playlist'i Anlat
+index-describe-intro = Yapay zekâya nasıl bir playlist istediğini anlat, havana uyan şarkılar önersin
+index-prompt-placeholder = ör. Yağmurlu bir pazar sabahı kahve eşliğinde dinlenecek sakin bir playlist...
+index-generate = Yapay Zekâ ile Playlist Oluştur
+index-customize-heading = Playlist'ini Özelleştir
+index-playlist-name = Playlist Adı:
+index-playlist-name-placeholder = Playlist'in için bir ad gir...
+index-playlist-description = Açıklama:
+index-playlist-description-placeholder = Playlist'in için bir açıklama ekle...
+index-create-playlist = Spotify Playlist Oluştur
+index-crafting = Senin için en uygun playlist hazırlanıyor...
+index-history-heading = Spotify Geçmişi
+index-history-intro = Son zamanlarda neler dinlediğini gör
+index-view-history = Son Dinlenenleri Göster
+index-prompt-missing = Lütfen oluşturmak istediğin playlist'i anlat
+index-ai-crafting = Yapay zekâ playlist'ini hazırlıyor...
+index-prompt-failed = İsteğin işlenirken bir hata oluştu. Lütfen tekrar dene.
+index-connecting-spotify = Spotify'a bağlanılıyor...
+index-popup-blocked = Popup penceresi engellendi. Lütfen popup engelini kaldır ve tekrar dene.
+index-auth-window-closed = Yetkilendirme penceresi kapatıldı. Lütfen tekrar dene.
+index-auth-url-missing = Spotify yetkilendirme URL'i alınamadı
+index-history-connect-failed = Spotify'a bağlanırken hata oluştu
+index-retry = Tekrar denemek için tıkla
+index-no-recent-tracks = Son dinlenen şarkı bulunamadı
+index-no-recent-tracks-hint = Önce Spotify'da birkaç şarkı dinlemen gerekebilir.
+index-suggested-songs = Yapay Zekânın Önerdiği Şarkılar
+index-generate-first = Lütfen önce şarkı önerisi oluştur
+index-playlist-name-missing = Lütfen bir playlist adı gir
+index-creating-playlist = Spotify playlist'in oluşturuluyor...
+index-create-playlist-failed = Playlist oluşturulurken hata
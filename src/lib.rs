@@ -1,10 +1,32 @@
+pub mod audio;
+pub mod config;
+pub mod db;
 pub mod handlers;
+pub mod i18n;
+pub mod logging;
+pub mod metrics;
+pub mod middleware;
 pub mod models;
+pub mod routes;
 pub mod services;
 
 use actix_web::web;
+use config::SpotifyConfig;
+use middleware::api_key::ApiKeyAuth;
+use middleware::rate_limit::{RateLimit, RateLimiter};
+use models::api_key::ApiScope;
+use services::api_key_service::ApiKeyService;
+use services::audio_cache_service::AudioCacheService;
+use services::cover_service::CoverService;
 use services::gemini_service::GeminiService;
-use services::musicgen_service::MusicGenService;
+use services::health_service::HealthService;
+use services::job_service::JobService;
+use services::library_service::LibraryService;
+use services::music_generator::MusicGenerator;
+use services::playlist_history_service::PlaylistHistoryService;
+use services::quota_service::QuotaService;
+use services::user_service::UserService;
+use sqlx::SqlitePool;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
@@ -12,19 +34,53 @@ use std::sync::{Arc, Mutex};
 pub struct AppState {
     pub pending_tracks: Arc<Mutex<HashMap<String, models::playlist::CreatePlaylistRequest>>>,
     pub gemini_service: GeminiService,
-    pub musicgen_service: MusicGenService,
+    /// `None` when the Spotify app credentials are not configured
+    pub spotify_config: Option<SpotifyConfig>,
+    pub musicgen_service: Arc<dyn MusicGenerator>,
     pub auth_states: Arc<Mutex<HashMap<String, String>>>,
+    pub db: SqlitePool,
+    pub rate_limiter: RateLimiter,
+    pub quota_service: QuotaService,
+    pub job_service: JobService,
+    pub audio_cache_service: AudioCacheService,
+    pub library_service: LibraryService,
+    pub health_service: HealthService,
+    pub api_key_service: ApiKeyService,
+    pub user_service: UserService,
+    pub playlist_history_service: PlaylistHistoryService,
+    pub cover_service: CoverService,
 }
 
 pub fn configure_app(config: &mut web::ServiceConfig) {
+    // Registered first: the catch-all scope below would otherwise shadow it
+    config.configure(routes::v1::config);
+
     config.service(
         web::scope("")
-            .route(
-                "/process-prompt",
-                web::post().to(handlers::process_gemini_prompt),
+            .service(
+                web::resource("/process-prompt")
+                    .wrap(RateLimit::new("process_prompt"))
+                    .wrap(ApiKeyAuth::new(ApiScope::PlaylistGenerate))
+                    .route(web::post().to(handlers::process_gemini_prompt)),
             )
             .route("/", web::get().to(handlers::index))
             .route("/callback", web::get().to(handlers::spotify_callback))
+            // Public share links for created playlists
+            .service(
+                web::resource("/share/{playlist_id}")
+                    .wrap(RateLimit::per_ip("share"))
+                    .route(web::get().to(handlers::share::share_page)),
+            )
+            .service(
+                web::resource("/share/{playlist_id}/card.jpg")
+                    .wrap(RateLimit::per_ip("share"))
+                    .route(web::get().to(handlers::share::share_card)),
+            )
+            .service(
+                web::resource("/share/{playlist_id}/qr")
+                    .wrap(RateLimit::per_ip("share"))
+                    .route(web::get().to(handlers::share::playlist_qr)),
+            )
             .route(
                 "/history-auth",
                 web::get().to(handlers::get_history_auth_url),
@@ -34,17 +90,96 @@ pub fn configure_app(config: &mut web::ServiceConfig) {
                 web::post().to(handlers::create_spotify_playlist_handler),
             )
             // AI Music Generation routes
-            .route(
-                "/generate-ai-music",
-                web::post().to(handlers::generate_ai_music),
+            .service(
+                web::resource("/generate-ai-music")
+                    .wrap(RateLimit::new("generate_ai_music"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::generate_ai_music)),
             )
-            .route(
-                "/generate-ai-music-batch",
-                web::post().to(handlers::generate_ai_music_batch),
+            .service(
+                web::resource("/generate-ai-music/melody")
+                    .wrap(RateLimit::new("generate_ai_music"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::conditioning::generate_from_melody)),
+            )
+            .service(
+                web::resource("/generate-ai-music/continue")
+                    .wrap(RateLimit::new("generate_ai_music"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::conditioning::continue_ai_music)),
+            )
+            .service(
+                web::resource("/generate-ai-music-batch")
+                    .wrap(RateLimit::new("generate_ai_music_batch"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::generate_ai_music_batch)),
+            )
+            .service(
+                web::resource("/generate-ai-album")
+                    .wrap(RateLimit::new("generate_ai_album"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::album::generate_ai_album)),
+            )
+            .service(
+                web::resource("/analyze-image")
+                    .wrap(RateLimit::new("analyze_image"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::image::analyze_image)),
+            )
+            // Asynchronous AI music jobs
+            .service(
+                web::resource("/jobs/ai-music")
+                    .wrap(RateLimit::new("generate_ai_music"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::jobs::submit_ai_music_job)),
+            )
+            .service(
+                web::resource("/jobs/ai-music-batch")
+                    .wrap(RateLimit::new("generate_ai_music_batch"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::jobs::submit_ai_music_batch_job)),
+            )
+            .service(
+                web::resource("/jobs/{id}")
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::get().to(handlers::jobs::get_job)),
+            )
+            .service(
+                web::resource("/jobs/{id}/retry")
+                    .wrap(RateLimit::new("generate_ai_music_batch"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::jobs::retry_job)),
+            )
+            .service(
+                web::resource("/jobs/{id}/events")
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::get().to(handlers::jobs::job_events)),
+            )
+            .service(
+                web::resource("/ai-music/{file_id}")
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::get().to(handlers::audio::stream_ai_music)),
+            )
+            .service(
+                web::resource("/me")
+                    .route(web::get().to(handlers::users::get_me))
+                    .route(web::patch().to(handlers::users::update_me)),
+            )
+            // Library of generated songs for signed-in users
+            .route("/library", web::get().to(handlers::library::list_library))
+            .service(
+                web::resource("/library/{file_id}")
+                    .route(web::get().to(handlers::library::get_library_track))
+                    .route(web::patch().to(handlers::library::update_library_track))
+                    .route(web::delete().to(handlers::library::delete_library_track)),
             )
             .route(
                 "/ai-music-health",
-                web::get().to(handlers::ai_music_health_check),
-            ),
+                web::get().to(handlers::health::ai_music_health_check),
+            )
+            // Liveness and readiness probes
+            .route("/healthz", web::get().to(handlers::health::healthz))
+            .route("/readyz", web::get().to(handlers::health::readyz))
+            .route("/metrics", web::get().to(handlers::metrics::metrics)),
     );
 }
@@ -0,0 +1,223 @@
+use crate::config::load_secret;
+use std::io::{self, IsTerminal, Write};
+use std::sync::Arc;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::EnvFilter;
+
+/// Secrets whose values must never reach the logs
+const SECRET_VARS: [&str; 2] = ["GEMINI_API_KEY", "SPOTIFY_CLIENT_SECRET"];
+
+/// Query parameters masked wherever a URL carries them (`?code=`, `&key=`)
+const SECRET_QUERY_PARAMS: [&str; 5] = [
+    "key",
+    "access_token",
+    "refresh_token",
+    "client_secret",
+    "code",
+];
+
+/// Log fields masked in both output formats: `name=value` and `"name":"value"`
+const SECRET_FIELDS: [&str; 3] = ["access_token", "refresh_token", "client_secret"];
+
+/// Authorization header scheme whose token is masked
+const BEARER_MARKER: &str = "Bearer ";
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Install the global subscriber.
+///
+/// RUST_LOG picks the level (default `info`), and LOG_FORMAT=json switches to
+/// one JSON object per line for production log shipping.
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new("info,sqlx=warn,actix_server=warn"));
+    let writer = RedactingMakeWriter::from_env();
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_ansi(io::stdout().is_terminal())
+        .with_writer(writer);
+
+    let json = std::env::var("LOG_FORMAT")
+        .map(|format| format.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+
+    if json {
+        builder
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .init();
+    } else {
+        builder.init();
+    }
+}
+
+/// Mask configured secrets and anything that looks like a credential in `text`
+pub fn redact(text: &str, secrets: &[String]) -> String {
+    let mut redacted = text.to_string();
+
+    for secret in secrets {
+        redacted = redacted.replace(secret.as_str(), REDACTED);
+    }
+
+    for param in SECRET_QUERY_PARAMS {
+        for separator in ['?', '&'] {
+            redacted = mask_after(&redacted, &format!("{}{}=", separator, param), |_| true);
+        }
+    }
+
+    for field in SECRET_FIELDS {
+        // Only whole field names, so e.g. `my_access_token=` is left alone
+        redacted = mask_after(&redacted, &format!("{}=", field), |previous| {
+            previous.is_none_or(|c| c.is_whitespace() || c == '{')
+        });
+        redacted = mask_after(&redacted, &format!("\"{}\":\"", field), |_| true);
+    }
+
+    mask_after(&redacted, BEARER_MARKER, |_| true)
+}
+
+/// Replace the value following each occurrence of `marker`, up to the next delimiter.
+/// `boundary` sees the character before the marker and decides whether it counts.
+fn mask_after(text: &str, marker: &str, boundary: impl Fn(Option<char>) -> bool) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(position) = rest.find(marker) {
+        let value_start = position + marker.len();
+        let previous = rest[..position]
+            .chars()
+            .last()
+            .or_else(|| output.chars().last());
+        output.push_str(&rest[..value_start]);
+
+        if !boundary(previous) {
+            rest = &rest[value_start..];
+            continue;
+        }
+
+        let value = &rest[value_start..];
+        let value_end = value
+            .find(|c: char| c.is_whitespace() || matches!(c, '&' | '"' | '\'' | ',' | ')' | '}'))
+            .unwrap_or(value.len());
+
+        if value_end > 0 && !value[..value_end].starts_with(REDACTED) {
+            output.push_str(REDACTED);
+        } else {
+            output.push_str(&value[..value_end]);
+        }
+        rest = &value[value_end..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Hands out stdout writers that redact every formatted log line
+#[derive(Clone)]
+pub struct RedactingMakeWriter {
+    secrets: Arc<Vec<String>>,
+}
+
+impl RedactingMakeWriter {
+    pub fn from_env() -> Self {
+        let secrets = SECRET_VARS
+            .iter()
+            .filter_map(|name| load_secret(name))
+            .map(|secret| secret.expose().to_string())
+            // Very short values would mask unrelated text
+            .filter(|value| value.len() >= 8)
+            .collect();
+
+        Self {
+            secrets: Arc::new(secrets),
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for RedactingMakeWriter {
+    type Writer = RedactingWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter {
+            secrets: Arc::clone(&self.secrets),
+        }
+    }
+}
+
+pub struct RedactingWriter {
+    secrets: Arc<Vec<String>>,
+}
+
+impl Write for RedactingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // The formatter writes each event as a single buffer, so whole lines are redacted
+        let line = String::from_utf8_lossy(buf);
+        io::stdout().write_all(redact(&line, &self.secrets).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_parameters_are_masked_in_urls() {
+        let line = "GET /callback?code=abc123&state=xyz https://x.test/v1?key=AIza&alt=json";
+
+        assert_eq!(
+            redact(line, &[]),
+            "GET /callback?code=[REDACTED]&state=xyz https://x.test/v1?key=[REDACTED]&alt=json"
+        );
+    }
+
+    #[test]
+    fn fields_that_only_share_a_query_parameter_name_are_kept() {
+        let line = r#"Rate limit hit route="share" key=ip:203.0.113.7 status_code=500"#;
+
+        assert_eq!(redact(line, &[]), line);
+    }
+
+    #[test]
+    fn secret_fields_are_masked_in_text_and_json_alike() {
+        assert_eq!(
+            redact("Token refreshed access_token=abc refresh_token=def", &[]),
+            "Token refreshed access_token=[REDACTED] refresh_token=[REDACTED]"
+        );
+        assert_eq!(
+            redact(r#"{"message":"Token refreshed","access_token":"abc"}"#, &[]),
+            r#"{"message":"Token refreshed","access_token":"[REDACTED]"}"#
+        );
+        assert_eq!(
+            redact(r#"{"uri":"/callback?code=abc&state=xyz"}"#, &[]),
+            r#"{"uri":"/callback?code=[REDACTED]&state=xyz"}"#
+        );
+    }
+
+    #[test]
+    fn longer_field_names_are_not_mistaken_for_secret_fields() {
+        let line = "cached_access_token=abc";
+
+        assert_eq!(redact(line, &[]), line);
+    }
+
+    #[test]
+    fn bearer_tokens_and_configured_secrets_are_masked() {
+        let secrets = vec!["super-secret-value".to_string()];
+
+        assert_eq!(
+            redact(
+                "Authorization: Bearer mel_abc123 using super-secret-value",
+                &secrets
+            ),
+            "Authorization: Bearer [REDACTED] using [REDACTED]"
+        );
+    }
+}
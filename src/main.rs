@@ -3,11 +3,25 @@ use actix_session::config::PersistentSession;
 use actix_session::storage::CookieSessionStore;
 use actix_session::SessionMiddleware;
 use actix_web::cookie::{time::Duration, Key};
-use actix_web::middleware::Logger;
 use actix_web::{web, App, HttpServer};
 use dotenv::dotenv;
+use spotify_ai_playlist::config::{self, SpotifyConfig};
+use spotify_ai_playlist::db;
+use spotify_ai_playlist::logging;
+use spotify_ai_playlist::middleware::metrics::RequestMetrics;
+use spotify_ai_playlist::middleware::rate_limit::RateLimiter;
+use spotify_ai_playlist::middleware::request_id::RequestTracing;
+use spotify_ai_playlist::services::api_key_service::ApiKeyService;
+use spotify_ai_playlist::services::audio_cache_service::AudioCacheService;
+use spotify_ai_playlist::services::cover_service::CoverService;
 use spotify_ai_playlist::services::gemini_service::GeminiService;
-use spotify_ai_playlist::services::musicgen_service::MusicGenService;
+use spotify_ai_playlist::services::health_service::HealthService;
+use spotify_ai_playlist::services::job_service::JobService;
+use spotify_ai_playlist::services::library_service::LibraryService;
+use spotify_ai_playlist::services::music_generator;
+use spotify_ai_playlist::services::playlist_history_service::PlaylistHistoryService;
+use spotify_ai_playlist::services::quota_service::QuotaService;
+use spotify_ai_playlist::services::user_service::UserService;
 use spotify_ai_playlist::{configure_app, AppState};
 use std::collections::HashMap;
 use std::env;
@@ -16,24 +30,75 @@ use std::sync::{Arc, Mutex};
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
-    env_logger::init();
+    logging::init();
 
     // Get environment variables
     let host = env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
     let port = env::var("PORT").unwrap_or_else(|_| "8081".to_string());
-    let gemini_api_key = env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY must be set");
-    let frontend_url = env::var("FRONTEND_URL").unwrap_or_else(|_| format!("http://{}:{}", host, port));
+    let gemini_api_key = config::require_secret("GEMINI_API_KEY");
+    let spotify_config = SpotifyConfig::from_env();
+    if spotify_config.is_none() {
+        tracing::warn!(
+            "Spotify is not configured: set SPOTIFY_CLIENT_ID, SPOTIFY_CLIENT_SECRET and SPOTIFY_REDIRECT_URI"
+        );
+    }
+    let frontend_url =
+        env::var("FRONTEND_URL").unwrap_or_else(|_| format!("http://{}:{}", host, port));
+    let database_url =
+        env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite://melanify.db".to_string());
 
-    println!("Server starting at http://{}:{}", host, port);
+    let pool = db::init_pool(&database_url)
+        .await
+        .expect("Failed to open SQLite database");
+
+    tracing::info!("Server starting at http://{}:{}", host, port);
 
     // Generate a random secret key for session encryption
     let secret_key = Key::generate();
 
+    let musicgen_service = music_generator::from_env();
+
+    let audio_cache_service = AudioCacheService::from_env(pool.clone());
+
+    let library_service = LibraryService::new(pool.clone(), audio_cache_service.clone());
+
+    let gemini_service = GeminiService::new(gemini_api_key);
+
+    let (job_service, job_receiver) = JobService::new(
+        pool.clone(),
+        audio_cache_service.clone(),
+        library_service.clone(),
+        gemini_service.clone(),
+    );
+    job_service
+        .start_workers(job_receiver, Arc::clone(&musicgen_service))
+        .await
+        .expect("Failed to start AI music job workers");
+
+    let health_service = HealthService::from_env(
+        pool.clone(),
+        gemini_service.clone(),
+        Arc::clone(&musicgen_service),
+        spotify_config.clone(),
+    );
+
     let app_state = AppState {
         pending_tracks: Arc::new(Mutex::new(HashMap::new())),
-        gemini_service: GeminiService::new(gemini_api_key.clone()),
-        musicgen_service: MusicGenService::new(),
+        gemini_service,
+        spotify_config,
+        musicgen_service,
         auth_states: Arc::new(Mutex::new(HashMap::new())),
+        quota_service: QuotaService::from_env(pool.clone()),
+        rate_limiter: RateLimiter::from_env(),
+        job_service,
+        audio_cache_service,
+        library_service,
+        health_service,
+        api_key_service: ApiKeyService::new(pool.clone()),
+        user_service: UserService::new(pool.clone()),
+        playlist_history_service: PlaylistHistoryService::new(pool.clone()),
+        cover_service: CoverService::from_env(),
+        db: pool,
     };
 
     let bind_addr = format!("{}:{}", host, port);
@@ -43,16 +108,17 @@ async fn main() -> std::io::Result<()> {
             .allowed_origin(&frontend_url)
             .allowed_origin("http://localhost:3000")
             .allowed_origin("https://relaxed-mooncake-8e5630.netlify.app")
-            .allowed_methods(vec!["GET", "POST"])
+            .allowed_methods(vec!["GET", "POST", "PATCH", "DELETE"])
             .allowed_headers(vec![
                 "Content-Type",
                 "Authorization",
                 "Cookie",
                 "X-CSRF-Token",
+                "X-Request-Id",
                 "Accept",
                 "Origin",
             ])
-            .expose_headers(vec!["Set-Cookie"])
+            .expose_headers(vec!["Set-Cookie", "Retry-After", "X-Request-Id"])
             .supports_credentials()
             .max_age(3600);
 
@@ -72,7 +138,8 @@ async fn main() -> std::io::Result<()> {
             )
             .wrap(cors)
             .app_data(web::Data::new(app_state.clone()))
-            .wrap(Logger::default())
+            .wrap(RequestMetrics)
+            .wrap(RequestTracing)
             .configure(configure_app)
     })
     .bind(&bind_addr)?
@@ -0,0 +1,64 @@
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+    Encoder, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder,
+};
+
+lazy_static! {
+    pub static ref HTTP_REQUESTS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "http_requests_total",
+        "HTTP requests handled, by route pattern and status",
+        &["method", "route", "status"]
+    )
+    .unwrap();
+    pub static ref HTTP_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "http_request_duration_seconds",
+        "Time spent handling HTTP requests",
+        &["method", "route"]
+    )
+    .unwrap();
+    pub static ref GEMINI_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "gemini_request_duration_seconds",
+        "Latency of Gemini API calls",
+        &["outcome"],
+        vec![0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0]
+    )
+    .unwrap();
+    pub static ref GEMINI_TOKENS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "gemini_tokens_total",
+        "Tokens reported by Gemini usage metadata",
+        &["kind"]
+    )
+    .unwrap();
+    pub static ref SPOTIFY_TRACK_SEARCHES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "spotify_track_searches_total",
+        "Spotify lookups of suggested tracks, by whether a match was found",
+        &["result"]
+    )
+    .unwrap();
+    pub static ref MUSICGEN_JOB_DURATION: HistogramVec = register_histogram_vec!(
+        "musicgen_job_duration_seconds",
+        "Time from a worker picking up an AI music job to it finishing",
+        &["kind", "status"],
+        vec![1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1200.0]
+    )
+    .unwrap();
+    pub static ref MUSICGEN_JOBS: IntGaugeVec = register_int_gauge_vec!(
+        "musicgen_jobs",
+        "AI music jobs waiting in the queue or being generated",
+        &["status"]
+    )
+    .unwrap();
+    pub static ref PENDING_OAUTH_HANDOFFS: IntGauge = register_int_gauge!(
+        "pending_oauth_handoffs",
+        "Playlists waiting in pending_tracks for the Spotify OAuth callback"
+    )
+    .unwrap();
+}
+
+/// Render every registered metric in the Prometheus text format
+pub fn render() -> Result<String, prometheus::Error> {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&prometheus::gather(), &mut buffer)?;
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
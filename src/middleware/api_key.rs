@@ -0,0 +1,189 @@
+use crate::i18n::{Locale, Message};
+use crate::models::api_key::ApiScope;
+use crate::AppState;
+use actix_session::SessionExt;
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use actix_web::{
+    web, Error, FromRequest, HttpMessage, HttpRequest, HttpResponse, HttpResponseBuilder,
+};
+use futures::future::{ready, LocalBoxFuture, Ready};
+use std::rc::Rc;
+
+/// The API key a request was authenticated with, available from request extensions
+#[derive(Debug, Clone)]
+pub struct ApiKeyIdentity {
+    pub key_id: String,
+    pub user_id: String,
+}
+
+/// The Spotify user a request acts for: the owner of its API key if it sent one,
+/// otherwise whoever is signed in to the session
+pub fn caller_user_id(req: &HttpRequest) -> Result<Option<String>, Error> {
+    if let Some(identity) = req.extensions().get::<ApiKeyIdentity>() {
+        return Ok(Some(identity.user_id.clone()));
+    }
+
+    Ok(req.get_session().get::<String>("spotify_user_id")?)
+}
+
+/// Extractor for handlers that work for both API keys and browser sessions
+#[derive(Debug, Clone)]
+pub struct Caller {
+    pub user_id: Option<String>,
+}
+
+impl FromRequest for Caller {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(caller_user_id(req).map(|user_id| Caller { user_id }))
+    }
+}
+
+fn bearer_token(req: &ServiceRequest) -> Option<String> {
+    req.headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+}
+
+fn auth_error(mut response: HttpResponseBuilder, challenge: &str, message: String) -> HttpResponse {
+    response
+        .insert_header((WWW_AUTHENTICATE, challenge))
+        .json(serde_json::json!({
+            "success": false,
+            "error": message
+        }))
+}
+
+/// Middleware that accepts `Authorization: Bearer <api key>` on a route and requires
+/// the key to carry `scope`. Requests without the header fall through to the session.
+pub struct ApiKeyAuth {
+    scope: ApiScope,
+}
+
+impl ApiKeyAuth {
+    pub fn new(scope: ApiScope) -> Self {
+        Self { scope }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ApiKeyAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = ApiKeyAuthMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ApiKeyAuthMiddleware {
+            service: Rc::new(service),
+            scope: self.scope,
+        }))
+    }
+}
+
+pub struct ApiKeyAuthMiddleware<S> {
+    service: Rc<S>,
+    scope: ApiScope,
+}
+
+impl<S, B> Service<ServiceRequest> for ApiKeyAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let scope = self.scope;
+
+        Box::pin(async move {
+            let (Some(token), Some(app_state)) = (
+                bearer_token(&req),
+                req.app_data::<web::Data<AppState>>().cloned(),
+            ) else {
+                return service.call(req).await.map(|res| res.map_into_left_body());
+            };
+            let locale = Locale::from_request(req.request());
+
+            let api_key = match app_state.api_key_service.authenticate(&token).await {
+                Ok(Some(api_key)) => api_key,
+                Ok(None) => {
+                    let response = auth_error(
+                        HttpResponse::Unauthorized(),
+                        r#"Bearer error="invalid_token""#,
+                        locale.text("api-key-invalid"),
+                    );
+                    return Ok(req.into_response(response).map_into_right_body());
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "Error checking API key");
+                    let response = HttpResponse::InternalServerError().json(serde_json::json!({
+                        "success": false,
+                        "error": locale.text("api-key-check-failed")
+                    }));
+                    return Ok(req.into_response(response).map_into_right_body());
+                }
+            };
+
+            if !api_key.scopes.contains(&scope) {
+                tracing::info!(key_id = %api_key.id, scope = scope.as_str(), "API key lacks scope");
+                let response = auth_error(
+                    HttpResponse::Forbidden(),
+                    &format!(
+                        r#"Bearer error="insufficient_scope", scope="{}""#,
+                        scope.as_str()
+                    ),
+                    locale.message(
+                        &Message::new("api-key-missing-scope").arg("scope", scope.as_str()),
+                    ),
+                );
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+
+            tracing::debug!(key_id = %api_key.id, "Authenticated API key");
+            req.extensions_mut().insert(ApiKeyIdentity {
+                key_id: api_key.id,
+                user_id: api_key.user_id,
+            });
+
+            service.call(req).await.map(|res| res.map_into_left_body())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn bearer_token_reads_only_the_bearer_scheme() {
+        let token = |value: &str| {
+            bearer_token(
+                &TestRequest::default()
+                    .insert_header((AUTHORIZATION, value))
+                    .to_srv_request(),
+            )
+        };
+
+        assert_eq!(token("Bearer mel_abc ").as_deref(), Some("mel_abc"));
+        assert_eq!(token("Basic dXNlcjpwYXNz"), None);
+        assert_eq!(token("bearer mel_abc"), None);
+        assert_eq!(bearer_token(&TestRequest::default().to_srv_request()), None);
+    }
+}
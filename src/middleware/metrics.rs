@@ -0,0 +1,76 @@
+use crate::metrics::{HTTP_REQUESTS_TOTAL, HTTP_REQUEST_DURATION};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::Error;
+use futures::future::{ready, LocalBoxFuture, Ready};
+use std::rc::Rc;
+use std::time::Instant;
+
+/// Middleware that counts requests and records their latency per route pattern
+pub struct RequestMetrics;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestMetrics
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestMetricsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestMetricsMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct RequestMetricsMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestMetricsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let method = req.method().to_string();
+        let started = Instant::now();
+
+        Box::pin(async move {
+            let response = service.call(req).await;
+
+            // Label by route pattern rather than path so IDs don't explode the series count
+            let (route, status) = match &response {
+                Ok(res) => (
+                    res.request()
+                        .match_pattern()
+                        .unwrap_or_else(|| "unmatched".to_string()),
+                    res.status().as_u16().to_string(),
+                ),
+                Err(e) => (
+                    "unmatched".to_string(),
+                    e.as_response_error().status_code().as_u16().to_string(),
+                ),
+            };
+
+            HTTP_REQUESTS_TOTAL
+                .with_label_values(&[&method, &route, &status])
+                .inc();
+            HTTP_REQUEST_DURATION
+                .with_label_values(&[&method, &route])
+                .observe(started.elapsed().as_secs_f64());
+
+            response
+        })
+    }
+}
@@ -0,0 +1,4 @@
+pub mod api_key;
+pub mod metrics;
+pub mod rate_limit;
+pub mod request_id;
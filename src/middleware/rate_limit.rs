@@ -0,0 +1,474 @@
+use crate::i18n::Locale;
+use crate::middleware::api_key::caller_user_id;
+use crate::services::quota_service::QuotaService;
+use crate::AppState;
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{web, Error, HttpMessage, HttpRequest, HttpResponse};
+use futures::future::{ready, LocalBoxFuture, Ready};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::env;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Buckets are pruned once the table grows past this many entries
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+
+/// Token bucket settings for a single route
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitPolicy {
+    pub capacity: f64,
+    pub refill_per_second: f64,
+}
+
+impl RateLimitPolicy {
+    /// Allow `requests` per minute with bursts of the same size
+    pub fn per_minute(requests: u32) -> Self {
+        Self {
+            capacity: requests as f64,
+            refill_per_second: requests as f64 / 60.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn full(policy: &RateLimitPolicy) -> Self {
+        Self {
+            tokens: policy.capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, policy: &RateLimitPolicy) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * policy.refill_per_second).min(policy.capacity);
+        self.last_refill = now;
+    }
+}
+
+/// Shared token bucket store for all rate-limited routes
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    policies: Arc<HashMap<&'static str, RateLimitPolicy>>,
+    buckets: Arc<Mutex<HashMap<(&'static str, String), TokenBucket>>>,
+    trust_forwarded_for: bool,
+}
+
+impl RateLimiter {
+    pub fn new(
+        policies: HashMap<&'static str, RateLimitPolicy>,
+        trust_forwarded_for: bool,
+    ) -> Self {
+        Self {
+            policies: Arc::new(policies),
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+            trust_forwarded_for,
+        }
+    }
+
+    /// Build the limiter from RATE_LIMIT_* (requests per minute) and TRUST_X_FORWARDED_FOR
+    pub fn from_env() -> Self {
+        let routes = [
+            ("process_prompt", "RATE_LIMIT_PROCESS_PROMPT", 10),
+            ("generate_ai_music", "RATE_LIMIT_GENERATE_AI_MUSIC", 5),
+            (
+                "generate_ai_music_batch",
+                "RATE_LIMIT_GENERATE_AI_MUSIC_BATCH",
+                2,
+            ),
+            ("analyze_image", "RATE_LIMIT_ANALYZE_IMAGE", 5),
+            ("generate_ai_album", "RATE_LIMIT_GENERATE_AI_ALBUM", 1),
+            ("share", "RATE_LIMIT_SHARE", 60),
+        ];
+
+        let policies = routes
+            .into_iter()
+            .map(|(route, var, default)| {
+                let per_minute = env::var(var)
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(default);
+                (route, RateLimitPolicy::per_minute(per_minute))
+            })
+            .collect();
+
+        let trust_forwarded_for = env::var("TRUST_X_FORWARDED_FOR")
+            .map(|value| value == "true" || value == "1")
+            .unwrap_or(false);
+
+        Self::new(policies, trust_forwarded_for)
+    }
+
+    /// Take a token for `key` on `route`.
+    /// Returns the number of seconds to wait when the bucket is empty.
+    pub fn check(&self, route: &'static str, key: &str) -> Result<(), u64> {
+        let Some(policy) = self.policies.get(route) else {
+            return Ok(());
+        };
+
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() > MAX_TRACKED_BUCKETS {
+            buckets.retain(|(route, _), bucket| match self.policies.get(route) {
+                Some(policy) => {
+                    bucket.refill(policy);
+                    bucket.tokens < policy.capacity
+                }
+                None => false,
+            });
+        }
+
+        let bucket = buckets
+            .entry((route, key.to_string()))
+            .or_insert_with(|| TokenBucket::full(policy));
+        bucket.refill(policy);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else if policy.refill_per_second > 0.0 {
+            let wait = (1.0 - bucket.tokens) / policy.refill_per_second;
+            Err(wait.ceil().max(1.0) as u64)
+        } else {
+            Err(60)
+        }
+    }
+
+    /// Pick the client address, honouring X-Forwarded-For only when configured to
+    fn client_ip(&self, req: &ServiceRequest) -> String {
+        if self.trust_forwarded_for {
+            if let Some(ip) = req.connection_info().realip_remote_addr() {
+                return ip.to_string();
+            }
+        }
+
+        req.peer_addr()
+            .map(|addr| addr.ip().to_string())
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+}
+
+fn too_many_requests(retry_after: u64, message: String) -> HttpResponse {
+    HttpResponse::TooManyRequests()
+        .insert_header(("Retry-After", retry_after.to_string()))
+        .json(serde_json::json!({
+            "success": false,
+            "error": message,
+            "retry_after": retry_after
+        }))
+}
+
+/// Quota units charged to the signed-in caller for the request being handled
+#[derive(Clone)]
+struct QuotaCharge {
+    user_id: String,
+    day: String,
+    units: Rc<Cell<u32>>,
+}
+
+/// Charge the daily quota for every prompt of a multi-prompt request. The middleware
+/// has already counted one; returns the 429 to send when the rest do not fit.
+pub(crate) async fn charge_prompts(
+    req: &HttpRequest,
+    prompts: usize,
+    locale: Locale,
+) -> Option<HttpResponse> {
+    let charge = req.extensions().get::<QuotaCharge>().cloned()?;
+    let app_state = req.app_data::<web::Data<AppState>>()?;
+
+    let extra = (prompts as u32).saturating_sub(charge.units.get());
+    if extra == 0 {
+        return None;
+    }
+
+    match app_state
+        .quota_service
+        .try_consume(&charge.user_id, &charge.day, extra)
+        .await
+    {
+        Ok(true) => {
+            charge.units.set(charge.units.get() + extra);
+            None
+        }
+        Ok(false) => {
+            tracing::info!(user_id = %charge.user_id, prompts, "Daily AI quota too low for every prompt");
+            Some(too_many_requests(
+                QuotaService::seconds_until_reset(),
+                locale.text("daily-quota-reached"),
+            ))
+        }
+        Err(e) => {
+            tracing::warn!(user_id = %charge.user_id, error = %e, "Failed to check daily quota");
+            None
+        }
+    }
+}
+
+/// Middleware that applies the named route's token bucket and the per-user daily quota.
+/// Quota is refunded when the handler answers with a 4xx or 5xx.
+pub struct RateLimit {
+    route: &'static str,
+    per_ip: bool,
+}
+
+impl RateLimit {
+    pub fn new(route: &'static str) -> Self {
+        Self {
+            route,
+            per_ip: false,
+        }
+    }
+
+    /// Limit public routes by client address only, without touching the AI quota
+    pub fn per_ip(route: &'static str) -> Self {
+        Self {
+            route,
+            per_ip: true,
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+            route: self.route,
+            per_ip: self.per_ip,
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    route: &'static str,
+    per_ip: bool,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let route = self.route;
+        let per_ip = self.per_ip;
+
+        Box::pin(async move {
+            let Some(app_state) = req.app_data::<web::Data<AppState>>().cloned() else {
+                return service.call(req).await.map(|res| res.map_into_left_body());
+            };
+
+            // API key callers share the bucket and quota of the user who owns the key
+            let user_id = if per_ip {
+                None
+            } else {
+                caller_user_id(req.request()).unwrap_or(None)
+            };
+            let locale = Locale::from_request(req.request());
+
+            let key = match &user_id {
+                Some(id) => format!("user:{}", id),
+                None => format!("ip:{}", app_state.rate_limiter.client_ip(&req)),
+            };
+
+            if let Err(retry_after) = app_state.rate_limiter.check(route, &key) {
+                tracing::info!(route, key = %key, "Rate limit hit");
+                let response = too_many_requests(retry_after, locale.text("rate-limited"));
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+
+            let mut charge = None;
+            if let Some(user_id) = &user_id {
+                let day = QuotaService::today();
+                match app_state.quota_service.try_consume(user_id, &day, 1).await {
+                    Ok(true) => {
+                        let quota_charge = QuotaCharge {
+                            user_id: user_id.clone(),
+                            day,
+                            units: Rc::new(Cell::new(1)),
+                        };
+                        req.extensions_mut().insert(quota_charge.clone());
+                        charge = Some(quota_charge);
+                    }
+                    Ok(false) => {
+                        tracing::info!(user_id = %user_id, "Daily AI quota exhausted");
+                        let response = too_many_requests(
+                            QuotaService::seconds_until_reset(),
+                            locale.text("daily-quota-reached"),
+                        );
+                        return Ok(req.into_response(response).map_into_right_body());
+                    }
+                    Err(e) => {
+                        // Never block requests because the quota store is unavailable
+                        tracing::warn!(user_id = %user_id, error = %e, "Failed to check daily quota");
+                    }
+                }
+            }
+
+            let result = service.call(req).await;
+
+            // Rejected or failed requests produced nothing, so they are free
+            if let Some(charge) = charge {
+                let status = match &result {
+                    Ok(res) => res.status(),
+                    Err(e) => e.as_response_error().status_code(),
+                };
+                if status.is_client_error() || status.is_server_error() {
+                    if let Err(e) = app_state
+                        .quota_service
+                        .refund(&charge.user_id, &charge.day, charge.units.get())
+                        .await
+                    {
+                        tracing::warn!(user_id = %charge.user_id, error = %e, "Failed to refund daily quota");
+                    }
+                }
+            }
+
+            result.map(|res| res.map_into_left_body())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use std::time::Duration;
+
+    fn limiter(per_minute: u32) -> RateLimiter {
+        RateLimiter::new(
+            HashMap::from([("test", RateLimitPolicy::per_minute(per_minute))]),
+            false,
+        )
+    }
+
+    #[test]
+    fn bucket_allows_a_burst_then_asks_to_wait_for_one_token() {
+        let limiter = limiter(2);
+
+        assert_eq!(limiter.check("test", "ip:a"), Ok(()));
+        assert_eq!(limiter.check("test", "ip:a"), Ok(()));
+        // Two per minute refill one token every 30 seconds
+        assert_eq!(limiter.check("test", "ip:a"), Err(30));
+
+        // Other clients have their own bucket
+        assert_eq!(limiter.check("test", "ip:b"), Ok(()));
+    }
+
+    #[test]
+    fn routes_without_a_policy_are_not_limited() {
+        let limiter = limiter(1);
+
+        for _ in 0..5 {
+            assert_eq!(limiter.check("other", "ip:a"), Ok(()));
+        }
+    }
+
+    #[test]
+    fn zero_rate_policy_waits_a_minute() {
+        let limiter = limiter(0);
+
+        assert_eq!(limiter.check("test", "ip:a"), Err(60));
+    }
+
+    #[test]
+    fn refill_adds_tokens_for_elapsed_time_up_to_capacity() {
+        let policy = RateLimitPolicy::per_minute(60);
+        let mut bucket = TokenBucket {
+            tokens: 0.0,
+            last_refill: Instant::now() - Duration::from_secs(10),
+        };
+
+        bucket.refill(&policy);
+        assert!((bucket.tokens - 10.0).abs() < 0.1);
+
+        bucket.last_refill = Instant::now() - Duration::from_secs(600);
+        bucket.refill(&policy);
+        assert_eq!(bucket.tokens, policy.capacity);
+    }
+
+    #[test]
+    fn partially_refilled_bucket_reports_remaining_wait() {
+        let limiter = limiter(6);
+        limiter.buckets.lock().unwrap().insert(
+            ("test", "ip:a".to_string()),
+            TokenBucket {
+                tokens: 0.5,
+                last_refill: Instant::now(),
+            },
+        );
+
+        // Half a token is missing and one token takes 10 seconds
+        assert_eq!(limiter.check("test", "ip:a"), Err(5));
+    }
+
+    #[test]
+    fn full_buckets_are_pruned_once_the_table_is_too_large() {
+        let limiter = limiter(10);
+        {
+            let mut buckets = limiter.buckets.lock().unwrap();
+            for i in 0..=MAX_TRACKED_BUCKETS {
+                buckets.insert(
+                    ("test", format!("ip:{}", i)),
+                    TokenBucket::full(&RateLimitPolicy::per_minute(10)),
+                );
+            }
+            buckets.insert(
+                ("test", "ip:busy".to_string()),
+                TokenBucket {
+                    tokens: 0.0,
+                    last_refill: Instant::now(),
+                },
+            );
+        }
+
+        assert_eq!(limiter.check("test", "ip:new"), Ok(()));
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert!(buckets.contains_key(&("test", "ip:busy".to_string())));
+        assert!(buckets.contains_key(&("test", "ip:new".to_string())));
+        assert_eq!(buckets.len(), 2);
+    }
+
+    #[test]
+    fn forwarded_for_is_only_used_when_trusted() {
+        let request = || {
+            TestRequest::default()
+                .peer_addr("10.0.0.1:4000".parse().unwrap())
+                .insert_header(("X-Forwarded-For", "203.0.113.7"))
+                .to_srv_request()
+        };
+
+        let untrusted = RateLimiter::new(HashMap::new(), false);
+        assert_eq!(untrusted.client_ip(&request()), "10.0.0.1");
+
+        let trusted = RateLimiter::new(HashMap::new(), true);
+        assert_eq!(trusted.client_ip(&request()), "203.0.113.7");
+    }
+}
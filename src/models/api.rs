@@ -0,0 +1,34 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Body of every JSON error response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub success: bool,
+    pub error: String,
+}
+
+/// Multipart form for melody conditioning and continuation.
+/// Send either `audio` or `file_id`.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct AudioConditioningForm {
+    /// WAV upload
+    #[schema(value_type = Option<String>, format = Binary)]
+    audio: Option<Vec<u8>>,
+    /// A previously generated song to use instead of an upload
+    file_id: Option<String>,
+    prompt: Option<String>,
+    duration: Option<u32>,
+    /// `GenerationControls` as a JSON string
+    controls: Option<String>,
+}
+
+/// Multipart form for image analysis
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct ImageUploadForm {
+    /// JPEG, PNG, WebP or GIF, at most 10 MB
+    #[schema(value_type = String, format = Binary)]
+    image: Vec<u8>,
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// What an API key is allowed to do
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub enum ApiScope {
+    #[serde(rename = "playlist:generate")]
+    PlaylistGenerate,
+    /// Covers synchronous generation, jobs and streaming the resulting audio
+    #[serde(rename = "music:generate")]
+    MusicGenerate,
+}
+
+impl ApiScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiScope::PlaylistGenerate => "playlist:generate",
+            ApiScope::MusicGenerate => "music:generate",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "playlist:generate" => Some(ApiScope::PlaylistGenerate),
+            "music:generate" => Some(ApiScope::MusicGenerate),
+            _ => None,
+        }
+    }
+}
+
+/// An API key as shown to its owner. The secret itself is only returned once, on creation.
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    /// First characters of the key, to tell keys apart
+    pub prefix: String,
+    pub scopes: Vec<ApiScope>,
+    #[serde(skip_serializing)]
+    pub user_id: String,
+    pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    /// Days until the key stops working; it never expires when omitted
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiKeyCreatedResponse {
+    pub success: bool,
+    /// Send as `Authorization: Bearer <key>`. It cannot be shown again.
+    pub key: String,
+    pub api_key: ApiKey,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiKeyListResponse {
+    pub success: bool,
+    pub api_keys: Vec<ApiKey>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiKeyRevokedResponse {
+    pub success: bool,
+    pub id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_round_trip_through_their_names() {
+        for scope in [ApiScope::PlaylistGenerate, ApiScope::MusicGenerate] {
+            assert_eq!(ApiScope::parse(scope.as_str()), Some(scope));
+            assert_eq!(
+                serde_json::to_value(scope).unwrap(),
+                serde_json::json!(scope.as_str())
+            );
+        }
+
+        assert_eq!(ApiScope::parse("admin"), None);
+        assert_eq!(ApiScope::parse("Music:Generate"), None);
+    }
+}
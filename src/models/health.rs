@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+/// What a music backend reports about itself
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BackendHealth {
+    pub healthy: bool,
+    #[serde(default)]
+    pub device: Option<String>,
+    /// Models currently loaded in memory
+    #[serde(default)]
+    pub models: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Down,
+    /// The dependency is not configured, so it cannot be checked
+    Skipped,
+}
+
+/// Result of probing one dependency
+#[derive(Debug, Serialize, Clone)]
+pub struct DependencyCheck {
+    pub status: CheckStatus,
+    pub latency_ms: u64,
+    /// Why the check failed or was skipped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Extra details such as the MusicGen device and loaded models
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DependencyChecks {
+    pub database: DependencyCheck,
+    pub musicgen: DependencyCheck,
+    pub gemini: DependencyCheck,
+    pub spotify: DependencyCheck,
+}
+
+impl DependencyChecks {
+    /// Ready once nothing is down; skipped checks do not block readiness
+    pub fn is_ready(&self) -> bool {
+        [&self.database, &self.musicgen, &self.gemini, &self.spotify]
+            .iter()
+            .all(|check| check.status != CheckStatus::Down)
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub checked_at: String,
+    /// Age of the cached checks in seconds, 0 when they were just run
+    pub age_seconds: u64,
+    pub checks: DependencyChecks,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LivenessReport {
+    pub status: &'static str,
+    pub version: &'static str,
+    pub uptime_seconds: u64,
+}
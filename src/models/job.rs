@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Done => "done",
+            JobStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "running" => JobStatus::Running,
+            "done" => JobStatus::Done,
+            "failed" => JobStatus::Failed,
+            _ => JobStatus::Queued,
+        }
+    }
+
+    /// Whether the job has reached a final state
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Done | JobStatus::Failed)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Single,
+    Batch,
+    /// A Gemini album concept rendered as a MusicGen batch
+    Album,
+}
+
+impl JobKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::Single => "single",
+            JobKind::Batch => "batch",
+            JobKind::Album => "album",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "batch" => JobKind::Batch,
+            "album" => JobKind::Album,
+            _ => JobKind::Single,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct Job {
+    pub id: String,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    pub progress: f64,
+    pub file_ids: Vec<String>,
+    /// The generation response once the job is done
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing)]
+    pub user_id: Option<String>,
+    /// Random ID kept in the session that queued the job when nobody was signed in
+    #[serde(skip_serializing)]
+    pub session_owner: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Job {
+    /// Failed jobs can be rerun, and so can batches where some prompts failed
+    pub fn is_retryable(&self) -> bool {
+        match self.status {
+            JobStatus::Failed => true,
+            JobStatus::Done => {
+                self.kind == JobKind::Batch
+                    && self
+                        .result
+                        .as_ref()
+                        .and_then(|result| result.get("failures"))
+                        .and_then(|failures| failures.as_array())
+                        .is_some_and(|failures| !failures.is_empty())
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct JobCreatedResponse {
+    pub success: bool,
+    pub job_id: String,
+    pub status: JobStatus,
+    pub status_url: String,
+    pub events_url: String,
+}
@@ -0,0 +1,183 @@
+use crate::models::playlist::{
+    AiMix, AiMusicBatchResponse, AiMusicResponse, AiSong, AudioPostProcessing, GenerationControls,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Which endpoint produced a library track
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackSource {
+    Text,
+    Batch,
+    Album,
+    Image,
+    Melody,
+    Continuation,
+    Mix,
+}
+
+impl TrackSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrackSource::Text => "text",
+            TrackSource::Batch => "batch",
+            TrackSource::Album => "album",
+            TrackSource::Image => "image",
+            TrackSource::Melody => "melody",
+            TrackSource::Continuation => "continuation",
+            TrackSource::Mix => "mix",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "batch" => TrackSource::Batch,
+            "album" => TrackSource::Album,
+            "image" => TrackSource::Image,
+            "melody" => TrackSource::Melody,
+            "continuation" => TrackSource::Continuation,
+            "mix" => TrackSource::Mix,
+            _ => TrackSource::Text,
+        }
+    }
+}
+
+/// A generated song about to be saved to a user's library
+#[derive(Debug, Clone)]
+pub struct NewLibraryTrack {
+    pub file_id: String,
+    pub original_file_id: Option<String>,
+    pub title: Option<String>,
+    pub prompt: String,
+    pub source: TrackSource,
+    /// Generation controls and post-processing options the song was made with
+    pub parameters: serde_json::Value,
+    pub duration_seconds: f64,
+}
+
+impl NewLibraryTrack {
+    /// The settings worth keeping alongside a track so it can be reproduced
+    pub fn parameters(
+        controls: &GenerationControls,
+        post_processing: Option<&AudioPostProcessing>,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "controls": controls,
+            "post_processing": post_processing,
+        })
+    }
+
+    pub fn from_song(
+        song: &AiMusicResponse,
+        source: TrackSource,
+        parameters: serde_json::Value,
+    ) -> Self {
+        Self {
+            file_id: song.file_id.clone(),
+            original_file_id: song.original_file_id.clone(),
+            title: None,
+            prompt: song.prompt.clone(),
+            source,
+            parameters,
+            duration_seconds: song.duration as f64,
+        }
+    }
+
+    pub fn from_batch_song(
+        song: &AiSong,
+        duration: u32,
+        source: TrackSource,
+        parameters: serde_json::Value,
+    ) -> Self {
+        Self {
+            file_id: song.file_id.clone(),
+            original_file_id: song.original_file_id.clone(),
+            title: Some(song.title.clone()),
+            prompt: song.prompt.clone(),
+            source,
+            parameters,
+            duration_seconds: duration as f64,
+        }
+    }
+
+    /// One track per song in the batch, plus the mix if one was built
+    pub fn from_batch(
+        batch: &AiMusicBatchResponse,
+        duration: u32,
+        source: TrackSource,
+        parameters: serde_json::Value,
+    ) -> Vec<Self> {
+        batch
+            .songs
+            .iter()
+            .map(|song| Self::from_batch_song(song, duration, source, parameters.clone()))
+            .chain(
+                batch
+                    .mix
+                    .as_ref()
+                    .map(|mix| Self::from_mix(mix, &batch.songs, parameters.clone())),
+            )
+            .collect()
+    }
+
+    pub fn from_mix(mix: &AiMix, songs: &[AiSong], parameters: serde_json::Value) -> Self {
+        let titles: Vec<&str> = songs.iter().map(|song| song.title.as_str()).collect();
+
+        Self {
+            file_id: mix.file_id.clone(),
+            original_file_id: None,
+            title: Some("Mix".to_string()),
+            prompt: format!("Mix of {}", titles.join(", ")),
+            source: TrackSource::Mix,
+            parameters,
+            duration_seconds: mix.duration_seconds,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct LibraryTrack {
+    pub file_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_file_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub prompt: String,
+    pub source: TrackSource,
+    /// The generation settings the track was made with
+    #[schema(value_type = Object)]
+    pub parameters: serde_json::Value,
+    pub duration_seconds: f64,
+    pub favorite: bool,
+    pub audio_url: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct LibraryQuery {
+    /// Matches prompt or title, case-insensitively
+    pub q: Option<String>,
+    pub favorite: Option<bool>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LibraryUpdate {
+    pub favorite: Option<bool>,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LibraryListResponse {
+    pub success: bool,
+    pub total: i64,
+    pub tracks: Vec<LibraryTrack>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LibraryDeleteResponse {
+    pub success: bool,
+    pub file_id: String,
+}
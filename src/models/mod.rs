@@ -1 +1,9 @@
+pub mod api;
+pub mod api_key;
+pub mod health;
+pub mod job;
+pub mod library;
 pub mod playlist;
+pub mod playlist_history;
+pub mod share;
+pub mod user;
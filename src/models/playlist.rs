@@ -1,6 +1,8 @@
+use crate::i18n::Message;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Track {
     pub name: String,
     pub artist: String,
@@ -9,11 +11,58 @@ pub struct Track {
     pub spotify_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// How matched tracks are written to an existing playlist
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaylistWriteMode {
+    /// Add the tracks after the current ones
+    #[default]
+    Append,
+    /// Swap out every current track for the new ones
+    Replace,
+    /// Add only the tracks the playlist doesn't contain yet
+    Merge,
+}
+
+/// Cover image uploaded for a playlist
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverStyle {
+    /// Colour gradient with the playlist name on top
+    Gradient,
+    /// Album art of the matched tracks, in a 2x2 grid when there is enough of it
+    Collage,
+    /// Keep Spotify's own cover
+    None,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct CreatePlaylistRequest {
     pub tracks: Vec<Track>,
     pub playlist_name: String,
     pub playlist_description: Option<String>,
+    /// Existing playlist to write to instead of creating one: an ID, URI or open.spotify.com link
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_playlist: Option<String>,
+    /// Only used with `target_playlist`
+    #[serde(default)]
+    pub mode: PlaylistWriteMode,
+    /// New playlists follow the user's `public_playlists` preference when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public: Option<bool>,
+    /// Collaborative playlists must be private
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collaborative: Option<bool>,
+    /// Defaults to a gradient for new playlists and to keeping the cover of existing ones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cover: Option<CoverStyle>,
+}
+
+/// Returned when a playlist is queued; the client sends the user to `auth_url`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlaylistAuthResponse {
+    pub auth_url: String,
+    pub session_id: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -24,17 +73,20 @@ pub struct RecentTrack {
     pub played_at: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct GeminiTrack {
     pub title: String,
     pub artist: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct GeminiPromptResponse {
     pub tracks: Vec<GeminiTrack>,
     pub playlist_name: String,
     pub playlist_description: String,
+    /// History entry the suggestion was saved as, for signed-in users
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generation_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -44,11 +96,79 @@ pub struct CallbackQuery {
     pub for_history: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct GeminiPromptRequest {
     pub prompt: String,
 }
 
+/// What to make from an imported Spotify playlist
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SeedMode {
+    /// New songs that carry the playlist on
+    #[default]
+    Extend,
+    /// A fresh take on the playlist, steered by the prompt
+    Variant,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PlaylistSeedRequest {
+    /// Public Spotify playlist ID, URI or open.spotify.com link
+    pub playlist: String,
+    #[serde(default)]
+    pub mode: SeedMode,
+    /// Optional direction such as "calmer" or "more acoustic"
+    pub prompt: Option<String>,
+    pub track_count: Option<usize>,
+}
+
+/// Audio traits Spotify reports for a track, all 0.0-1.0 except tempo in BPM
+#[derive(Debug, Serialize, Clone, Copy, ToSchema)]
+pub struct TrackAudioFeatures {
+    pub tempo: f32,
+    pub energy: f32,
+    pub valence: f32,
+    pub danceability: f32,
+    pub acousticness: f32,
+    pub instrumentalness: f32,
+}
+
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct SeedTrack {
+    pub name: String,
+    pub artist: String,
+    pub url: String,
+    pub spotify_id: String,
+    /// Missing when Spotify no longer serves audio features to this app
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub features: Option<TrackAudioFeatures>,
+}
+
+/// A Spotify playlist loaded as context for Gemini
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct PlaylistSeed {
+    pub id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub url: String,
+    pub tracks: Vec<SeedTrack>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SeededPlaylistResponse {
+    pub success: bool,
+    pub seed: PlaylistSeed,
+    pub playlist_name: String,
+    pub playlist_description: String,
+    /// Suggested songs, ready to send to the playlist creation endpoint
+    pub tracks: Vec<Track>,
+    /// History entry the suggestion was saved as, for signed-in users
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_id: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct LastFmResponse {
     #[serde(default)]
@@ -115,27 +235,176 @@ pub struct CallbackForm {
 }
 
 // AI Music Generation Models
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AiMusicRequest {
     pub prompt: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub duration: Option<u32>,
+    #[serde(flatten)]
+    pub controls: GenerationControls,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_processing: Option<AudioPostProcessing>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AiMusicBatchRequest {
     pub prompts: Vec<AiMusicPrompt>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub duration: Option<u32>,
+    /// Applied to every prompt in the batch
+    #[serde(flatten)]
+    pub controls: GenerationControls,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_processing: Option<AudioPostProcessing>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Clip length used when a request does not set one, in seconds
+pub const DEFAULT_AI_MUSIC_DURATION: u32 = 10;
+
+/// Longest clip MusicGen is asked to produce, in seconds
+pub const MAX_AI_MUSIC_DURATION: u32 = 30;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MusicGenModelSize {
+    Small,
+    Medium,
+    Large,
+}
+
+/// Optional musical and sampling controls forwarded to MusicGen
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct GenerationControls {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bpm: Option<u32>,
+    /// Musical key such as "C", "F# minor" or "Bb major"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instrumentation: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub with_vocals: Option<bool>,
+    /// Fixes the random seed so the same request produces the same audio
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guidance_scale: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_size: Option<MusicGenModelSize>,
+}
+
+impl GenerationControls {
+    pub fn validate(&self) -> Result<(), Message> {
+        let out_of_range = |field: &'static str, min: f64, max: f64| {
+            Message::new("field-out-of-range")
+                .arg("field", field)
+                .arg("min", min)
+                .arg("max", max)
+        };
+
+        if let Some(bpm) = self.bpm {
+            if !(40..=240).contains(&bpm) {
+                return Err(out_of_range("bpm", 40.0, 240.0));
+            }
+        }
+
+        if let Some(key) = &self.key {
+            if !is_valid_key(key) {
+                return Err(Message::new("invalid-key"));
+            }
+        }
+
+        if let Some(genre) = &self.genre {
+            if genre.trim().is_empty() || genre.len() > 50 {
+                return Err(Message::new("field-length")
+                    .arg("field", "genre")
+                    .arg("max", 50));
+            }
+        }
+
+        if let Some(instruments) = &self.instrumentation {
+            if instruments.len() > 10 {
+                return Err(Message::new("too-many-instruments").arg("max", 10));
+            }
+            if instruments
+                .iter()
+                .any(|instrument| instrument.trim().is_empty() || instrument.len() > 50)
+            {
+                return Err(Message::new("instrument-length").arg("max", 50));
+            }
+        }
+
+        if let Some(temperature) = self.temperature {
+            if !(0.1..=2.0).contains(&temperature) {
+                return Err(out_of_range("temperature", 0.1, 2.0));
+            }
+        }
+
+        if let Some(top_k) = self.top_k {
+            if !(1..=1000).contains(&top_k) {
+                return Err(out_of_range("top_k", 1.0, 1000.0));
+            }
+        }
+
+        if let Some(top_p) = self.top_p {
+            if !(0.0..=1.0).contains(&top_p) {
+                return Err(out_of_range("top_p", 0.0, 1.0));
+            }
+        }
+
+        if let Some(guidance_scale) = self.guidance_scale {
+            if !(1.0..=10.0).contains(&guidance_scale) {
+                return Err(out_of_range("guidance_scale", 1.0, 10.0));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn is_valid_key(key: &str) -> bool {
+    let mut parts = key.split_whitespace();
+    let Some(note) = parts.next() else {
+        return false;
+    };
+
+    let mut chars = note.chars();
+    let valid_note = matches!(chars.next(), Some('A'..='G'))
+        && matches!(chars.as_str(), "" | "#" | "b" | "m" | "#m" | "bm");
+
+    let valid_mode = match parts.next() {
+        None => true,
+        Some(mode) => matches!(mode.to_lowercase().as_str(), "major" | "minor"),
+    };
+
+    valid_note && valid_mode && parts.next().is_none()
+}
+
+/// Check a requested clip length against what MusicGen can produce
+pub fn validate_duration(duration: Option<u32>) -> Result<(), Message> {
+    match duration {
+        Some(seconds) if seconds == 0 || seconds > MAX_AI_MUSIC_DURATION => {
+            Err(Message::new("invalid-duration").arg("max", MAX_AI_MUSIC_DURATION))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct AiMusicPrompt {
     pub title: String,
     pub prompt: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AiMusicResponse {
     pub success: bool,
     pub file_id: String,
@@ -144,19 +413,216 @@ pub struct AiMusicResponse {
     pub sample_rate: u32,
     pub prompt: String,
     pub timestamp: String,
+    /// Raw MusicGen output when `file_id` points at a post-processed version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_file_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AiMusicBatchResponse {
+    /// True when at least one prompt produced a song
     pub success: bool,
     pub songs: Vec<AiSong>,
     pub count: usize,
+    /// Prompts that failed; retrying a batch job only regenerates these
+    #[serde(default)]
+    pub failures: Vec<AiBatchFailure>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mix: Option<AiMix>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl AiMusicBatchResponse {
+    /// Build a response from per-prompt outcomes, ordered by prompt index
+    pub fn from_outcomes(outcomes: Vec<Result<AiSong, AiBatchFailure>>) -> Self {
+        let mut songs = Vec::new();
+        let mut failures = Vec::new();
+
+        for outcome in outcomes {
+            match outcome {
+                Ok(song) => songs.push(song),
+                Err(failure) => failures.push(failure),
+            }
+        }
+
+        songs.sort_by_key(|song| song.index);
+        failures.sort_by_key(|failure| failure.index);
+
+        Self {
+            success: !songs.is_empty(),
+            count: songs.len(),
+            songs,
+            failures,
+            mix: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct AiBatchFailure {
+    /// Position of the prompt in the request
+    pub index: usize,
+    pub title: String,
+    pub prompt: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AiSong {
+    /// Position of the prompt in the request
+    #[serde(default)]
+    pub index: usize,
     pub title: String,
     pub file_id: String,
     pub file_path: String,
     pub prompt: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_file_id: Option<String>,
+}
+
+/// A batch joined into one continuous track
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AiMix {
+    pub file_id: String,
+    pub file_path: String,
+    pub duration_seconds: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    #[default]
+    Wav,
+    Mp3,
+    Ogg,
+    Flac,
+}
+
+impl AudioFormat {
+    pub const ALL: [AudioFormat; 4] = [
+        AudioFormat::Wav,
+        AudioFormat::Mp3,
+        AudioFormat::Ogg,
+        AudioFormat::Flac,
+    ];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Ogg => "ogg",
+            AudioFormat::Flac => "flac",
+        }
+    }
+}
+
+/// Optional effects applied to generated audio before it is returned
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct AudioPostProcessing {
+    /// Target integrated loudness in LUFS (EBU R128), e.g. -14 for streaming
+    pub normalize_lufs: Option<f64>,
+    pub fade_in_ms: Option<u32>,
+    pub fade_out_ms: Option<u32>,
+    #[serde(default)]
+    pub trim_silence: bool,
+    /// Output format, WAV when omitted
+    pub format: Option<AudioFormat>,
+    /// Batch only: join all songs into one mix, overlapping them by this many milliseconds
+    pub crossfade_ms: Option<u32>,
+}
+
+impl AudioPostProcessing {
+    /// Whether anything needs to be done to individual tracks
+    pub fn has_track_effects(&self) -> bool {
+        self.normalize_lufs.is_some()
+            || self.fade_in_ms.is_some()
+            || self.fade_out_ms.is_some()
+            || self.trim_silence
+            || self.format.is_some_and(|format| format != AudioFormat::Wav)
+    }
+
+    pub fn validate(&self) -> Result<(), Message> {
+        if let Some(lufs) = self.normalize_lufs {
+            if !(-70.0..=0.0).contains(&lufs) {
+                return Err(Message::new("field-out-of-range")
+                    .arg("field", "normalize_lufs")
+                    .arg("min", -70)
+                    .arg("max", 0));
+            }
+        }
+
+        for (name, value) in [
+            ("fade_in_ms", self.fade_in_ms),
+            ("fade_out_ms", self.fade_out_ms),
+            ("crossfade_ms", self.crossfade_ms),
+        ] {
+            if value.is_some_and(|ms| ms > 10_000) {
+                return Err(Message::new("field-too-large")
+                    .arg("field", name)
+                    .arg("max", 10_000));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImageAnalysisResponse {
+    pub success: bool,
+    pub caption: String,
+    pub suggested_prompt: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AnalyzeImageQuery {
+    /// Optionally feed the caption into "playlist" or "music" generation
+    pub chain: Option<String>,
+    pub duration: Option<u32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AnalyzeImageResponse {
+    pub success: bool,
+    pub caption: String,
+    pub suggested_prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub playlist: Option<GeminiPromptResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub song: Option<AiMusicResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AiAlbumRequest {
+    pub prompt: String,
+    pub track_count: Option<usize>,
+    pub duration: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AlbumConcept {
+    pub album_title: String,
+    pub description: String,
+    pub cover_art_prompt: String,
+    pub tracks: Vec<AiMusicPrompt>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AiAlbumTrack {
+    pub track_number: usize,
+    pub title: String,
+    pub prompt: String,
+    pub file_id: String,
+    pub audio_url: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AiAlbumResponse {
+    pub success: bool,
+    pub title: String,
+    pub description: String,
+    pub cover_art_prompt: String,
+    pub tracks: Vec<AiAlbumTrack>,
+    /// Tracks that could not be generated; the album is returned without them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<AiBatchFailure>,
 }
@@ -0,0 +1,78 @@
+use crate::i18n::Locale;
+use crate::models::playlist::GeminiTrack;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// A playlist suggestion kept for a signed-in user, with what it took to produce it
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct PlaylistGeneration {
+    pub id: String,
+    pub prompt: String,
+    /// Request options other than the prompt, replayed when the entry is re-run
+    #[schema(value_type = Object)]
+    pub parameters: serde_json::Value,
+    /// Gemini model that produced the suggestion
+    pub model: String,
+    pub tracks: Vec<GeminiTrack>,
+    pub playlist_name: String,
+    pub playlist_description: String,
+    pub latency_ms: i64,
+    /// The entry this one was re-run or duplicated from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_generation_id: Option<String>,
+    pub created_at: String,
+}
+
+impl PlaylistGeneration {
+    /// Request options worth storing next to the prompt, plus the language Gemini wrote in
+    pub fn parameters(request: &impl Serialize, language: Locale) -> serde_json::Value {
+        let mut parameters = serde_json::to_value(request).unwrap_or_default();
+        if let Some(fields) = parameters.as_object_mut() {
+            fields.remove("prompt");
+            fields.insert("language".to_string(), language.tag().into());
+        }
+        parameters
+    }
+
+    /// The language the suggestion was written in, if it was stored
+    pub fn language(&self) -> Option<Locale> {
+        self.parameters
+            .get("language")
+            .and_then(|tag| tag.as_str())
+            .map(|tag| Locale::negotiate(Some(tag), None))
+    }
+
+    /// Whether this entry was built from a Spotify playlist rather than a prompt alone
+    pub fn is_seeded(&self) -> bool {
+        self.parameters.get("playlist").is_some()
+    }
+
+    /// Rebuild the request that produced this entry so it can be sent again
+    pub fn request<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        let mut fields = self.parameters.as_object().cloned().unwrap_or_default();
+        fields.insert("prompt".to_string(), self.prompt.clone().into());
+        serde_json::from_value(serde_json::Value::Object(fields))
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PlaylistHistoryQuery {
+    /// Matches prompt or playlist name, case-insensitively
+    pub q: Option<String>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlaylistHistoryResponse {
+    pub success: bool,
+    pub total: i64,
+    pub generations: Vec<PlaylistGeneration>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlaylistGenerationDeletedResponse {
+    pub success: bool,
+    pub id: String,
+}
@@ -0,0 +1,140 @@
+use crate::i18n::Message;
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+/// Spotify green on white, the look of the original QR codes
+pub const DEFAULT_QR_DARK: [u8; 3] = [0x1D, 0xB9, 0x54];
+pub const DEFAULT_QR_LIGHT: [u8; 3] = [0xFF, 0xFF, 0xFF];
+
+pub const DEFAULT_QR_SIZE: u32 = 200;
+pub const MIN_QR_SIZE: u32 = 64;
+pub const MAX_QR_SIZE: u32 = 2048;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QrFormat {
+    #[default]
+    Svg,
+    Png,
+}
+
+/// How much of the code can be damaged or covered and still scan: about 7%, 15%, 25% or 30%
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QrEcLevel {
+    L,
+    M,
+    Q,
+    H,
+}
+
+/// Fully resolved QR rendering settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QrOptions {
+    /// Minimum width and height in pixels; the code is scaled up to whole modules
+    pub size: u32,
+    pub dark: [u8; 3],
+    pub light: [u8; 3],
+    pub ec_level: QrEcLevel,
+    /// Draw the app logo in the middle of the code
+    pub logo: bool,
+}
+
+impl Default for QrOptions {
+    fn default() -> Self {
+        Self {
+            size: DEFAULT_QR_SIZE,
+            dark: DEFAULT_QR_DARK,
+            light: DEFAULT_QR_LIGHT,
+            ec_level: QrEcLevel::M,
+            logo: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default, IntoParams)]
+pub struct QrQuery {
+    /// `svg` (default) or `png`
+    pub format: Option<QrFormat>,
+    /// Minimum width and height in pixels, 64 to 2048
+    pub size: Option<u32>,
+    /// Module colour as hex, e.g. `1DB954` or `#1DB954`
+    pub dark: Option<String>,
+    /// Background colour as hex
+    pub light: Option<String>,
+    /// Error correction level; defaults to `m`, or `h` when a logo is drawn
+    pub ec: Option<QrEcLevel>,
+    /// Put the logo in the middle of the code. Needs error correction `q` or `h`.
+    pub logo: Option<bool>,
+}
+
+impl QrQuery {
+    /// Check the query and fill in defaults
+    pub fn options(&self) -> Result<QrOptions, Message> {
+        let size = self.size.unwrap_or(DEFAULT_QR_SIZE);
+        if !(MIN_QR_SIZE..=MAX_QR_SIZE).contains(&size) {
+            return Err(Message::new("field-out-of-range")
+                .arg("field", "size")
+                .arg("min", MIN_QR_SIZE)
+                .arg("max", MAX_QR_SIZE));
+        }
+
+        let color = |field: &'static str, hex: Option<&str>, default: [u8; 3]| match hex {
+            Some(hex) => parse_hex_color(hex).ok_or_else(|| {
+                Message::new("invalid-hex-color").arg("field", field).arg(
+                    "example",
+                    format!("{:02X}{:02X}{:02X}", default[0], default[1], default[2]),
+                )
+            }),
+            None => Ok(default),
+        };
+        let dark = color("dark", self.dark.as_deref(), DEFAULT_QR_DARK)?;
+        let light = color("light", self.light.as_deref(), DEFAULT_QR_LIGHT)?;
+        // Scanners look for dark modules on a light background and give up on weak contrast
+        if luminance(light) - luminance(dark) < 0.4 {
+            return Err(Message::new("qr-low-contrast"));
+        }
+
+        let logo = self.logo.unwrap_or(false);
+        let ec_level = match self.ec {
+            Some(level) if logo && level < QrEcLevel::Q => {
+                return Err(Message::new("qr-logo-ec-level"));
+            }
+            Some(level) => level,
+            None if logo => QrEcLevel::H,
+            None => QrEcLevel::M,
+        };
+
+        Ok(QrOptions {
+            size,
+            dark,
+            light,
+            ec_level,
+            logo,
+        })
+    }
+}
+
+/// Parse `RRGGBB` or `#RRGGBB`
+pub fn parse_hex_color(hex: &str) -> Option<[u8; 3]> {
+    let hex = hex.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Relative luminance from 0 (black) to 1 (white)
+fn luminance([r, g, b]: [u8; 3]) -> f32 {
+    let linear = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b)
+}
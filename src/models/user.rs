@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Settings a user can change; unset fields fall back to the app defaults
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct UserPreferences {
+    /// Interface and playlist-description language, e.g. "en" or "tr"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Whether new Spotify playlists are created public
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_playlists: Option<bool>,
+}
+
+impl UserPreferences {
+    /// Overwrite the fields set in `update`, leaving the rest alone
+    pub fn merge(&mut self, update: UserPreferences) {
+        if update.language.is_some() {
+            self.language = update.language;
+        }
+        if update.public_playlists.is_some() {
+            self.public_playlists = update.public_playlists;
+        }
+    }
+}
+
+/// A person who has signed in with Spotify
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct User {
+    pub spotify_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// ISO 3166-1 alpha-2 code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// Spotify subscription tier: "premium" or "free"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub product: Option<String>,
+    pub preferences: UserPreferences,
+    pub created_at: String,
+    pub last_login_at: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateMeRequest {
+    pub preferences: UserPreferences,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MeResponse {
+    pub success: bool,
+    pub user: User,
+}
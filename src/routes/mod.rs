@@ -1,4 +1,6 @@
+pub mod openapi;
 pub mod statistics;
+pub mod v1;
 
 use actix_web::web;
 
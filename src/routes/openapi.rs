@@ -0,0 +1,151 @@
+use crate::handlers;
+use crate::models::api::{AudioConditioningForm, ErrorResponse, ImageUploadForm};
+use crate::models::api_key::{
+    ApiKey, ApiKeyCreatedResponse, ApiKeyListResponse, ApiKeyRevokedResponse, ApiScope,
+    CreateApiKeyRequest,
+};
+use crate::models::job::{Job, JobCreatedResponse, JobKind, JobStatus};
+use crate::models::library::{
+    LibraryDeleteResponse, LibraryListResponse, LibraryTrack, LibraryUpdate, TrackSource,
+};
+use crate::models::playlist::*;
+use crate::models::playlist_history::{
+    PlaylistGeneration, PlaylistGenerationDeletedResponse, PlaylistHistoryResponse,
+};
+use crate::models::share::{QrEcLevel, QrFormat};
+use crate::models::user::{MeResponse, UpdateMeRequest, User, UserPreferences};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+/// OpenAPI 3 description of the `/api/v1` routes
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Melanify API",
+        description = "AI playlist suggestions, Spotify playlist creation and MusicGen songs.\n\n\
+            Endpoints that touch a user's data rely on the Spotify session cookie. \
+            Scripts can instead send `Authorization: Bearer <api key>` to endpoints that \
+            list the `api_key` scheme; the key needs the `playlist:generate` or \
+            `music:generate` scope for the endpoint's tag."
+    ),
+    paths(
+        handlers::process_gemini_prompt,
+        handlers::playlist_history::list_generations,
+        handlers::playlist_history::get_generation,
+        handlers::playlist_history::rerun_generation,
+        handlers::playlist_history::duplicate_generation,
+        handlers::playlist_history::delete_generation,
+        handlers::playlist_seed::suggest_from_playlist,
+        handlers::create_spotify_playlist_handler,
+        handlers::share::playlist_qr,
+        handlers::generate_ai_music,
+        handlers::generate_ai_music_batch,
+        handlers::conditioning::generate_from_melody,
+        handlers::conditioning::continue_ai_music,
+        handlers::audio::stream_ai_music,
+        handlers::album::generate_ai_album,
+        handlers::image::analyze_image,
+        handlers::jobs::submit_ai_music_job,
+        handlers::jobs::submit_ai_music_batch_job,
+        handlers::jobs::get_job,
+        handlers::jobs::retry_job,
+        handlers::jobs::job_events,
+        handlers::users::get_me,
+        handlers::users::update_me,
+        handlers::library::list_library,
+        handlers::library::get_library_track,
+        handlers::library::update_library_track,
+        handlers::library::delete_library_track,
+        handlers::api_keys::create_api_key,
+        handlers::api_keys::list_api_keys,
+        handlers::api_keys::revoke_api_key,
+    ),
+    components(schemas(
+        ErrorResponse,
+        Track,
+        CreatePlaylistRequest,
+        PlaylistWriteMode,
+        CoverStyle,
+        PlaylistAuthResponse,
+        GeminiPromptRequest,
+        GeminiPromptResponse,
+        GeminiTrack,
+        PlaylistGeneration,
+        PlaylistHistoryResponse,
+        PlaylistGenerationDeletedResponse,
+        SeedMode,
+        PlaylistSeedRequest,
+        PlaylistSeed,
+        SeedTrack,
+        TrackAudioFeatures,
+        SeededPlaylistResponse,
+        QrFormat,
+        QrEcLevel,
+        AiMusicRequest,
+        AiMusicBatchRequest,
+        AiMusicPrompt,
+        GenerationControls,
+        MusicGenModelSize,
+        AudioPostProcessing,
+        AudioFormat,
+        AiMusicResponse,
+        AiMusicBatchResponse,
+        AiBatchFailure,
+        AiSong,
+        AiMix,
+        AudioConditioningForm,
+        ImageUploadForm,
+        AnalyzeImageResponse,
+        AiAlbumRequest,
+        AiAlbumResponse,
+        AiAlbumTrack,
+        Job,
+        JobKind,
+        JobStatus,
+        JobCreatedResponse,
+        User,
+        UserPreferences,
+        UpdateMeRequest,
+        MeResponse,
+        LibraryTrack,
+        LibraryUpdate,
+        LibraryListResponse,
+        LibraryDeleteResponse,
+        TrackSource,
+        ApiScope,
+        ApiKey,
+        CreateApiKeyRequest,
+        ApiKeyCreatedResponse,
+        ApiKeyListResponse,
+        ApiKeyRevokedResponse,
+    )),
+    modifiers(&ApiKeySecurity),
+    tags(
+        (name = "playlists", description = "Gemini playlist suggestions and Spotify playlists"),
+        (name = "ai-music", description = "Synchronous MusicGen generation and audio streaming"),
+        (name = "jobs", description = "Queued generation with polling and Server-Sent Events"),
+        (name = "users", description = "The account of the signed-in Spotify user"),
+        (name = "library", description = "Songs saved for the signed-in Spotify user"),
+        (name = "api-keys", description = "Keys for scripts, managed from a browser session"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Registers the `api_key` bearer scheme referenced by the generation endpoints
+struct ApiKeySecurity;
+
+impl Modify for ApiKeySecurity {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "api_key",
+                SecurityScheme::Http(
+                    HttpBuilder::new()
+                        .scheme(HttpAuthScheme::Bearer)
+                        .description(Some("API key created under /api/v1/api-keys"))
+                        .build(),
+                ),
+            );
+        }
+    }
+}
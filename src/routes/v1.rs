@@ -0,0 +1,155 @@
+use crate::handlers;
+use crate::middleware::api_key::ApiKeyAuth;
+use crate::middleware::rate_limit::RateLimit;
+use crate::models::api_key::ApiScope;
+use crate::routes::openapi::ApiDoc;
+use actix_web::web;
+use utoipa::OpenApi;
+use utoipa_redoc::{Redoc, Servable};
+
+pub const API_V1_PREFIX: &str = "/api/v1";
+
+/// The versioned public API. Handlers are shared with the legacy flat routes,
+/// so both stay in step until the old paths are retired.
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope(API_V1_PREFIX)
+            // Only the routes that call Gemini are rate limited, not history reads
+            .service(
+                web::resource("/playlists/suggestions")
+                    .wrap(ApiKeyAuth::new(ApiScope::PlaylistGenerate))
+                    .route(web::get().to(handlers::playlist_history::list_generations))
+                    .route(
+                        web::post()
+                            .to(handlers::process_gemini_prompt)
+                            .wrap(RateLimit::new("process_prompt")),
+                    ),
+            )
+            .service(
+                web::resource("/playlists/seeded-suggestions")
+                    .wrap(RateLimit::new("process_prompt"))
+                    .wrap(ApiKeyAuth::new(ApiScope::PlaylistGenerate))
+                    .route(web::post().to(handlers::playlist_seed::suggest_from_playlist)),
+            )
+            .service(
+                web::resource("/playlists/suggestions/{id}")
+                    .wrap(ApiKeyAuth::new(ApiScope::PlaylistGenerate))
+                    .route(web::get().to(handlers::playlist_history::get_generation))
+                    .route(web::delete().to(handlers::playlist_history::delete_generation)),
+            )
+            .service(
+                web::resource("/playlists/suggestions/{id}/rerun")
+                    .wrap(RateLimit::new("process_prompt"))
+                    .wrap(ApiKeyAuth::new(ApiScope::PlaylistGenerate))
+                    .route(web::post().to(handlers::playlist_history::rerun_generation)),
+            )
+            .service(
+                web::resource("/playlists/suggestions/{id}/duplicate")
+                    .wrap(ApiKeyAuth::new(ApiScope::PlaylistGenerate))
+                    .route(web::post().to(handlers::playlist_history::duplicate_generation)),
+            )
+            .route(
+                "/playlists",
+                web::post().to(handlers::create_spotify_playlist_handler),
+            )
+            .service(
+                web::resource("/playlists/{playlist_id}/qr")
+                    .wrap(RateLimit::per_ip("share"))
+                    .route(web::get().to(handlers::share::playlist_qr)),
+            )
+            .service(
+                web::resource("/ai-music")
+                    .wrap(RateLimit::new("generate_ai_music"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::generate_ai_music)),
+            )
+            .service(
+                web::resource("/ai-music/melody")
+                    .wrap(RateLimit::new("generate_ai_music"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::conditioning::generate_from_melody)),
+            )
+            .service(
+                web::resource("/ai-music/continuations")
+                    .wrap(RateLimit::new("generate_ai_music"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::conditioning::continue_ai_music)),
+            )
+            .service(
+                web::resource("/ai-music/batches")
+                    .wrap(RateLimit::new("generate_ai_music_batch"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::generate_ai_music_batch)),
+            )
+            .service(
+                web::resource("/ai-music/{file_id}")
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::get().to(handlers::audio::stream_ai_music)),
+            )
+            .service(
+                web::resource("/albums")
+                    .wrap(RateLimit::new("generate_ai_album"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::album::generate_ai_album)),
+            )
+            .service(
+                web::resource("/image-analyses")
+                    .wrap(RateLimit::new("analyze_image"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::image::analyze_image)),
+            )
+            .service(
+                web::resource("/jobs/ai-music")
+                    .wrap(RateLimit::new("generate_ai_music"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::jobs::submit_ai_music_job)),
+            )
+            .service(
+                web::resource("/jobs/ai-music-batches")
+                    .wrap(RateLimit::new("generate_ai_music_batch"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::jobs::submit_ai_music_batch_job)),
+            )
+            .service(
+                web::resource("/jobs/{id}")
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::get().to(handlers::jobs::get_job)),
+            )
+            .service(
+                web::resource("/jobs/{id}/retry")
+                    .wrap(RateLimit::new("generate_ai_music_batch"))
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::post().to(handlers::jobs::retry_job)),
+            )
+            .service(
+                web::resource("/jobs/{id}/events")
+                    .wrap(ApiKeyAuth::new(ApiScope::MusicGenerate))
+                    .route(web::get().to(handlers::jobs::job_events)),
+            )
+            .service(
+                web::resource("/me")
+                    .route(web::get().to(handlers::users::get_me))
+                    .route(web::patch().to(handlers::users::update_me)),
+            )
+            .route("/library", web::get().to(handlers::library::list_library))
+            .service(
+                web::resource("/library/{file_id}")
+                    .route(web::get().to(handlers::library::get_library_track))
+                    .route(web::patch().to(handlers::library::update_library_track))
+                    .route(web::delete().to(handlers::library::delete_library_track)),
+            )
+            // Key management needs a browser session; keys cannot manage keys
+            .service(
+                web::resource("/api-keys")
+                    .route(web::get().to(handlers::api_keys::list_api_keys))
+                    .route(web::post().to(handlers::api_keys::create_api_key)),
+            )
+            .route(
+                "/api-keys/{id}",
+                web::delete().to(handlers::api_keys::revoke_api_key),
+            )
+            // Machine-readable spec for client codegen, and a browsable version of it
+            .route("/openapi.json", web::get().to(handlers::docs::openapi_json))
+            .service(Redoc::with_url("/docs", ApiDoc::openapi())),
+    );
+}
@@ -0,0 +1,274 @@
+use crate::models::api_key::{ApiKey, ApiScope};
+use chrono::{Duration, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::error::Error;
+
+/// Every key starts with this so leaked keys are easy to recognise and scan for
+const KEY_PREFIX: &str = "mel_";
+
+/// Random characters after the prefix, about 238 bits of entropy
+const KEY_RANDOM_LENGTH: usize = 40;
+
+/// Characters of the key kept in clear text so owners can tell keys apart
+const DISPLAY_PREFIX_LENGTH: usize = 12;
+
+#[derive(Debug, sqlx::FromRow)]
+struct ApiKeyRow {
+    id: String,
+    user_id: String,
+    name: String,
+    prefix: String,
+    scopes: String,
+    created_at: String,
+    expires_at: Option<String>,
+    last_used_at: Option<String>,
+    revoked_at: Option<String>,
+}
+
+impl From<ApiKeyRow> for ApiKey {
+    fn from(row: ApiKeyRow) -> Self {
+        ApiKey {
+            id: row.id,
+            user_id: row.user_id,
+            name: row.name,
+            prefix: row.prefix,
+            scopes: row.scopes.split(' ').filter_map(ApiScope::parse).collect(),
+            created_at: row.created_at,
+            expires_at: row.expires_at,
+            last_used_at: row.last_used_at,
+            revoked_at: row.revoked_at,
+        }
+    }
+}
+
+/// Keys are random, so a plain SHA-256 is enough to make the stored hash useless to a thief
+fn hash_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// API keys for scripts and internal tools, owned by the Spotify user who created them
+#[derive(Debug, Clone)]
+pub struct ApiKeyService {
+    pool: SqlitePool,
+}
+
+impl ApiKeyService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a key and return it with its plain-text secret, which is not stored anywhere
+    pub async fn create(
+        &self,
+        user_id: &str,
+        name: &str,
+        scopes: &[ApiScope],
+        expires_in_days: Option<u32>,
+    ) -> Result<(ApiKey, String), Box<dyn Error>> {
+        let random: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(KEY_RANDOM_LENGTH)
+            .map(char::from)
+            .collect();
+        let secret = format!("{}{}", KEY_PREFIX, random);
+
+        let now = Utc::now();
+        let mut unique_scopes = Vec::new();
+        for scope in scopes {
+            if !unique_scopes.contains(scope) {
+                unique_scopes.push(*scope);
+            }
+        }
+
+        let api_key = ApiKey {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            name: name.to_string(),
+            prefix: secret[..DISPLAY_PREFIX_LENGTH].to_string(),
+            scopes: unique_scopes,
+            created_at: now.to_rfc3339(),
+            expires_at: expires_in_days
+                .map(|days| (now + Duration::days(i64::from(days))).to_rfc3339()),
+            last_used_at: None,
+            revoked_at: None,
+        };
+
+        let scopes = api_key
+            .scopes
+            .iter()
+            .map(ApiScope::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        sqlx::query(
+            r#"
+            INSERT INTO api_keys (id, user_id, name, prefix, key_hash, scopes, created_at, expires_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&api_key.id)
+        .bind(&api_key.user_id)
+        .bind(&api_key.name)
+        .bind(&api_key.prefix)
+        .bind(hash_key(&secret))
+        .bind(scopes)
+        .bind(&api_key.created_at)
+        .bind(&api_key.expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok((api_key, secret))
+    }
+
+    /// All of a user's keys, including revoked and expired ones, newest first
+    pub async fn list(&self, user_id: &str) -> Result<Vec<ApiKey>, sqlx::Error> {
+        let rows: Vec<ApiKeyRow> = sqlx::query_as(
+            r#"
+            SELECT id, user_id, name, prefix, scopes, created_at, expires_at, last_used_at, revoked_at
+            FROM api_keys WHERE user_id = ?
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(ApiKey::from).collect())
+    }
+
+    /// Stop a key from working. Returns `false` if the user has no such active key.
+    pub async fn revoke(&self, user_id: &str, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE api_keys SET revoked_at = ? WHERE id = ? AND user_id = ? AND revoked_at IS NULL",
+        )
+        .bind(Utc::now().to_rfc3339())
+        .bind(id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Look up the key behind a bearer token. Unknown, revoked and expired keys all give `None`.
+    pub async fn authenticate(&self, secret: &str) -> Result<Option<ApiKey>, sqlx::Error> {
+        if !secret.starts_with(KEY_PREFIX) {
+            return Ok(None);
+        }
+
+        let now = Utc::now().to_rfc3339();
+
+        let row: Option<ApiKeyRow> = sqlx::query_as(
+            r#"
+            UPDATE api_keys SET last_used_at = ?
+            WHERE key_hash = ? AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > ?)
+            RETURNING id, user_id, name, prefix, scopes, created_at, expires_at, last_used_at, revoked_at
+            "#,
+        )
+        .bind(&now)
+        .bind(hash_key(secret))
+        .bind(&now)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(ApiKey::from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    async fn service() -> ApiKeyService {
+        let path = std::env::temp_dir().join(format!("melanify-keys-{}.db", uuid::Uuid::new_v4()));
+        let pool = db::init_pool(&format!("sqlite://{}", path.display()))
+            .await
+            .unwrap();
+        ApiKeyService::new(pool)
+    }
+
+    #[tokio::test]
+    async fn created_key_authenticates_as_its_owner() {
+        let service = service().await;
+
+        let (created, secret) = service
+            .create(
+                "listener",
+                "script",
+                &[ApiScope::MusicGenerate, ApiScope::MusicGenerate],
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert!(secret.starts_with(KEY_PREFIX));
+        assert_eq!(secret.len(), KEY_PREFIX.len() + KEY_RANDOM_LENGTH);
+        assert_eq!(created.prefix, secret[..DISPLAY_PREFIX_LENGTH]);
+        assert_eq!(created.scopes, vec![ApiScope::MusicGenerate]);
+
+        let found = service.authenticate(&secret).await.unwrap().unwrap();
+        assert_eq!(found.id, created.id);
+        assert_eq!(found.user_id, "listener");
+        assert_eq!(found.scopes, vec![ApiScope::MusicGenerate]);
+        assert!(found.last_used_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn unknown_and_foreign_tokens_are_rejected() {
+        let service = service().await;
+        let (_, secret) = service
+            .create("listener", "script", &[ApiScope::PlaylistGenerate], None)
+            .await
+            .unwrap();
+
+        assert!(service
+            .authenticate("mel_not-a-real-key")
+            .await
+            .unwrap()
+            .is_none());
+        // Only the prefix differs, so the key never reaches the database lookup
+        let foreign = secret.replacen(KEY_PREFIX, "sk_", 1);
+        assert!(service.authenticate(&foreign).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn expired_keys_stop_working() {
+        let service = service().await;
+
+        let (created, secret) = service
+            .create("listener", "script", &[ApiScope::MusicGenerate], Some(0))
+            .await
+            .unwrap();
+
+        assert!(created.expires_at.is_some());
+        assert!(service.authenticate(&secret).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn only_the_owner_can_revoke_a_key() {
+        let service = service().await;
+        let (created, secret) = service
+            .create("listener", "script", &[ApiScope::MusicGenerate], Some(30))
+            .await
+            .unwrap();
+
+        assert!(!service.revoke("someone-else", &created.id).await.unwrap());
+        assert!(service.authenticate(&secret).await.unwrap().is_some());
+
+        assert!(service.revoke("listener", &created.id).await.unwrap());
+        assert!(service.authenticate(&secret).await.unwrap().is_none());
+        // Revoking twice reports that there was nothing left to revoke
+        assert!(!service.revoke("listener", &created.id).await.unwrap());
+
+        let listed = service.list("listener").await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].revoked_at.is_some());
+    }
+}
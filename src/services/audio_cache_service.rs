@@ -0,0 +1,349 @@
+use crate::audio;
+use crate::models::playlist::{
+    AiMix, AiMusicBatchResponse, AiMusicResponse, AudioFormat, AudioPostProcessing,
+};
+use crate::services::music_generator::MusicGenerator;
+use chrono::Utc;
+use sqlx::SqlitePool;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Default cache budget of 1 GiB
+const DEFAULT_MAX_CACHE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Who a generated file belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileOwner {
+    /// The file was never generated through this server
+    Unknown,
+    /// Generated without a signed-in user; anyone holding the file ID may play it
+    Anonymous,
+    User(String),
+}
+
+/// Keeps a local disk copy of generated audio so the browser never has to
+/// reach the internal Python service, and tracks which user owns each file.
+///
+/// Post-processed files only exist on this server, so they are kept in a
+/// separate directory that is never evicted.
+#[derive(Debug, Clone)]
+pub struct AudioCacheService {
+    pool: SqlitePool,
+    cache_dir: PathBuf,
+    processed_dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl AudioCacheService {
+    pub fn new(
+        pool: SqlitePool,
+        cache_dir: PathBuf,
+        processed_dir: PathBuf,
+        max_bytes: u64,
+    ) -> Self {
+        Self {
+            pool,
+            cache_dir,
+            processed_dir,
+            max_bytes,
+        }
+    }
+
+    /// Create an AudioCacheService from AUDIO_CACHE_DIR, PROCESSED_AUDIO_DIR and AUDIO_CACHE_MAX_BYTES
+    pub fn from_env(pool: SqlitePool) -> Self {
+        let cache_dir = std::env::var("AUDIO_CACHE_DIR")
+            .unwrap_or_else(|_| "audio_cache".to_string())
+            .into();
+        let processed_dir = std::env::var("PROCESSED_AUDIO_DIR")
+            .unwrap_or_else(|_| "processed_audio".to_string())
+            .into();
+        let max_bytes = std::env::var("AUDIO_CACHE_MAX_BYTES")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_MAX_CACHE_BYTES);
+
+        Self::new(pool, cache_dir, processed_dir, max_bytes)
+    }
+
+    /// Public path the browser should use to play a generated song
+    pub fn public_path(file_id: &str) -> String {
+        format!("/ai-music/{}", file_id)
+    }
+
+    /// Remember who generated a file so only they can stream it later
+    pub async fn record_owner(
+        &self,
+        file_id: &str,
+        user_id: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT OR IGNORE INTO ai_music_files (file_id, user_id, created_at) VALUES (?, ?, ?)",
+        )
+        .bind(file_id)
+        .bind(user_id)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn owner_of(&self, file_id: &str) -> Result<FileOwner, sqlx::Error> {
+        let row: Option<(Option<String>,)> =
+            sqlx::query_as("SELECT user_id FROM ai_music_files WHERE file_id = ?")
+                .bind(file_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(match row {
+            None => FileOwner::Unknown,
+            Some((None,)) => FileOwner::Anonymous,
+            Some((Some(user_id),)) => FileOwner::User(user_id),
+        })
+    }
+
+    /// The raw WAV a post-processed file was made from, if it has a single one
+    pub async fn original_of(&self, file_id: &str) -> Result<Option<String>, sqlx::Error> {
+        let row: Option<(String,)> =
+            sqlx::query_as("SELECT original_file_id FROM ai_music_file_sources WHERE file_id = ?")
+                .bind(file_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(row.map(|(original_file_id,)| original_file_id))
+    }
+
+    /// Return the local path of a song, downloading it from MusicGen on a cache miss
+    pub async fn cached_path(
+        &self,
+        file_id: &str,
+        musicgen_service: &dyn MusicGenerator,
+    ) -> Result<PathBuf, Box<dyn Error>> {
+        if let Some(path) = self.processed_path(file_id) {
+            return Ok(path);
+        }
+
+        let path = self.cache_dir.join(format!("{}.wav", file_id));
+
+        if path.exists() {
+            // Bump the modification time so eviction treats it as recently used
+            if let Ok(file) = fs::File::options().append(true).open(&path) {
+                let _ = file.set_modified(SystemTime::now());
+            }
+            return Ok(path);
+        }
+
+        tracing::info!(file_id, "Audio cache miss, fetching from MusicGen");
+        let bytes = musicgen_service.download_song(file_id).await?;
+
+        fs::create_dir_all(&self.cache_dir)?;
+        let tmp_path = self.cache_dir.join(format!("{}.wav.part", file_id));
+        fs::write(&tmp_path, &bytes)?;
+        fs::rename(&tmp_path, &path)?;
+
+        if let Err(e) = self.evict() {
+            tracing::warn!(error = %e, "Failed to evict audio cache entries");
+        }
+
+        Ok(path)
+    }
+
+    /// Delete every local copy of a file, forget its owner and ask MusicGen to drop its original
+    pub async fn remove_file(
+        &self,
+        file_id: &str,
+        musicgen_service: &dyn MusicGenerator,
+    ) -> Result<(), Box<dyn Error>> {
+        let processed = self.processed_path(file_id);
+        let generated_remotely = processed.is_none();
+
+        for path in processed.into_iter().chain(std::iter::once(
+            self.cache_dir.join(format!("{}.wav", file_id)),
+        )) {
+            match fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        sqlx::query("DELETE FROM ai_music_files WHERE file_id = ?")
+            .bind(file_id)
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("DELETE FROM ai_music_file_sources WHERE file_id = ?")
+            .bind(file_id)
+            .execute(&self.pool)
+            .await?;
+
+        if generated_remotely {
+            if let Err(e) = musicgen_service.delete_song(file_id).await {
+                tracing::warn!(file_id, error = %e, "Failed to delete song from MusicGen");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn processed_path(&self, file_id: &str) -> Option<PathBuf> {
+        AudioFormat::ALL
+            .iter()
+            .map(|format| {
+                self.processed_dir
+                    .join(format!("{}.{}", file_id, format.extension()))
+            })
+            .find(|path| path.exists())
+    }
+
+    /// Save post-processed audio under a fresh file ID owned by `user_id`,
+    /// remembering the raw file it came from when there is exactly one
+    async fn store_processed(
+        &self,
+        bytes: &[u8],
+        format: AudioFormat,
+        user_id: Option<&str>,
+        original_file_id: Option<&str>,
+    ) -> Result<String, Box<dyn Error>> {
+        let file_id = uuid::Uuid::new_v4().to_string();
+
+        fs::create_dir_all(&self.processed_dir)?;
+        fs::write(
+            self.processed_dir
+                .join(format!("{}.{}", file_id, format.extension())),
+            bytes,
+        )?;
+        self.record_owner(&file_id, user_id).await?;
+
+        if let Some(original_file_id) = original_file_id {
+            sqlx::query(
+                "INSERT OR IGNORE INTO ai_music_file_sources (file_id, original_file_id) VALUES (?, ?)",
+            )
+            .bind(&file_id)
+            .bind(original_file_id)
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(file_id)
+    }
+
+    /// Apply post-processing to a generated song, pointing the response at the processed file
+    pub async fn post_process_song(
+        &self,
+        musicgen_service: &dyn MusicGenerator,
+        song: &mut AiMusicResponse,
+        options: &AudioPostProcessing,
+        user_id: Option<&str>,
+    ) -> Result<(), Box<dyn Error>> {
+        if !options.has_track_effects() {
+            return Ok(());
+        }
+
+        let file_id = self
+            .process_file(musicgen_service, &song.file_id, options, user_id)
+            .await?;
+        song.original_file_id = Some(std::mem::replace(&mut song.file_id, file_id));
+        song.file_path = Self::public_path(&song.file_id);
+
+        Ok(())
+    }
+
+    /// Apply per-track effects to every song in a batch and build the crossfaded mix if requested.
+    /// Safe to call again after more songs are added to the batch.
+    pub async fn post_process_batch(
+        &self,
+        musicgen_service: &dyn MusicGenerator,
+        batch: &mut AiMusicBatchResponse,
+        options: &AudioPostProcessing,
+        user_id: Option<&str>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut raw_tracks = Vec::with_capacity(batch.songs.len());
+
+        for song in batch.songs.iter_mut() {
+            // The mix is always built from the unprocessed tracks
+            let raw_id = song.original_file_id.as_deref().unwrap_or(&song.file_id);
+            let path = self.cached_path(raw_id, musicgen_service).await?;
+            raw_tracks.push(fs::read(path)?);
+
+            // Songs processed on an earlier attempt of the batch keep their processed file
+            if options.has_track_effects() && song.original_file_id.is_none() {
+                let file_id = self
+                    .process_file(musicgen_service, &song.file_id, options, user_id)
+                    .await?;
+                song.original_file_id = Some(std::mem::replace(&mut song.file_id, file_id));
+                song.file_path = Self::public_path(&song.file_id);
+            }
+        }
+
+        if options.crossfade_ms.is_some() && !raw_tracks.is_empty() {
+            let mix_options = options.clone();
+            let (bytes, format, duration_seconds) =
+                tokio::task::spawn_blocking(move || audio::mix(&raw_tracks, &mix_options))
+                    .await?
+                    .map_err(|e| format!("Failed to mix tracks: {}", e))?;
+
+            let file_id = self.store_processed(&bytes, format, user_id, None).await?;
+            batch.mix = Some(AiMix {
+                file_path: Self::public_path(&file_id),
+                file_id,
+                duration_seconds,
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn process_file(
+        &self,
+        musicgen_service: &dyn MusicGenerator,
+        file_id: &str,
+        options: &AudioPostProcessing,
+        user_id: Option<&str>,
+    ) -> Result<String, Box<dyn Error>> {
+        let raw = fs::read(self.cached_path(file_id, musicgen_service).await?)?;
+
+        let track_options = options.clone();
+        let (bytes, format) =
+            tokio::task::spawn_blocking(move || audio::process(&raw, &track_options))
+                .await?
+                .map_err(|e| format!("Failed to process audio: {}", e))?;
+
+        self.store_processed(&bytes, format, user_id, Some(file_id))
+            .await
+    }
+
+    /// Delete least recently used files until the cache fits its budget
+    fn evict(&self) -> std::io::Result<()> {
+        let mut entries = Vec::new();
+        let mut total: u64 = 0;
+
+        for entry in fs::read_dir(&self.cache_dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            total += metadata.len();
+            entries.push((metadata.modified()?, metadata.len(), entry.path()));
+        }
+
+        if total <= self.max_bytes {
+            return Ok(());
+        }
+
+        entries.sort_by_key(|(modified, _, _)| *modified);
+
+        for (_, size, path) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            fs::remove_file(&path)?;
+            total = total.saturating_sub(size);
+            tracing::info!(path = %path.display(), "Evicted from audio cache");
+        }
+
+        Ok(())
+    }
+}
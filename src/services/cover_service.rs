@@ -0,0 +1,566 @@
+use crate::i18n::Locale;
+use crate::models::playlist::CoverStyle;
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageOutputFormat, Rgb, RgbImage, RgbaImage};
+use reqwest::Client;
+use rspotify::model::PlaylistId;
+use rspotify::prelude::*;
+use rspotify::AuthCodeSpotify;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::error::Error;
+use std::hash::{Hash, Hasher};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Bold sans-serif shipped by the Debian `fonts-dejavu-core` package
+const DEFAULT_FONT_PATH: &str = "/usr/share/fonts/truetype/dejavu/DejaVuSans-Bold.ttf";
+
+const COVER_SIZE: u32 = 640;
+const TEXT_MARGIN: f32 = 56.0;
+const MAX_TITLE_LINES: usize = 4;
+
+/// Open Graph previews are shown at roughly 1.91:1
+const CARD_WIDTH: u32 = 1200;
+const CARD_HEIGHT: u32 = 630;
+const CARD_MARGIN: u32 = 80;
+const CARD_COVER_SIZE: u32 = CARD_HEIGHT - 2 * CARD_MARGIN;
+
+/// Spotify rejects cover uploads whose base64 payload is larger than this
+const MAX_UPLOAD_BYTES: usize = 256 * 1024;
+
+/// Album art larger than this is not worth downloading for a collage tile
+const MAX_ART_BYTES: usize = 2 * 1024 * 1024;
+
+/// Rendered share cards are reused for as long as browsers may cache them
+const SHARE_CARD_TTL: Duration = Duration::from_secs(3600);
+
+/// Around 100 KB each, so the card cache stays within a few tens of MB
+const MAX_CACHED_SHARE_CARDS: usize = 256;
+
+/// Share cards by playlist ID and language, with the time they were rendered
+type ShareCardCache = HashMap<(String, Locale), (Instant, Vec<u8>)>;
+
+/// Renders playlist covers and uploads them to Spotify
+#[derive(Clone)]
+pub struct CoverService {
+    /// `None` when the title font could not be loaded; gradients are drawn without text
+    font: Option<Arc<FontVec>>,
+    client: Client,
+    share_cards: Arc<Mutex<ShareCardCache>>,
+}
+
+impl CoverService {
+    pub fn new(font: Option<FontVec>) -> Self {
+        Self {
+            font: font.map(Arc::new),
+            client: Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            share_cards: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Load the title font from COVER_FONT_PATH, falling back to DejaVu Sans Bold
+    pub fn from_env() -> Self {
+        let path =
+            std::env::var("COVER_FONT_PATH").unwrap_or_else(|_| DEFAULT_FONT_PATH.to_string());
+
+        let font = std::fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| FontVec::try_from_vec(bytes).map_err(|e| e.to_string()));
+
+        match font {
+            Ok(font) => Self::new(Some(font)),
+            Err(e) => {
+                tracing::warn!(
+                    path = %path,
+                    error = %e,
+                    "Cover font unavailable, covers will have no title"
+                );
+                Self::new(None)
+            }
+        }
+    }
+
+    /// Render a cover as base64 JPEG small enough for Spotify.
+    /// Collages fall back to a gradient when no album art can be fetched.
+    pub async fn render(
+        &self,
+        style: CoverStyle,
+        title: &str,
+        album_art: &[String],
+    ) -> Result<String, Box<dyn Error>> {
+        let art = match style {
+            CoverStyle::Collage => self.fetch_album_art(album_art).await,
+            CoverStyle::Gradient | CoverStyle::None => Vec::new(),
+        };
+
+        let font = self.font.clone();
+        let title = title.to_string();
+
+        let encoded = tokio::task::spawn_blocking(move || {
+            let cover = if art.is_empty() {
+                gradient_cover(&title, font.as_deref())
+            } else {
+                collage_cover(&art)
+            };
+            encode_for_upload(&cover)
+        })
+        .await??;
+
+        Ok(encoded)
+    }
+
+    /// Render the image shown when a playlist's share link is posted: its cover,
+    /// title and a QR code pointing at it with `caption` beside it, as JPEG bytes
+    pub async fn render_share_card(
+        &self,
+        title: &str,
+        subtitle: &str,
+        caption: &str,
+        cover_url: Option<&str>,
+        qr: RgbaImage,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let art = match cover_url {
+            Some(url) => self
+                .fetch_image(url)
+                .await
+                .map_err(|e| tracing::debug!(url = %url, error = %e, "Skipping playlist cover"))
+                .ok(),
+            None => None,
+        };
+
+        let font = self.font.clone();
+        let title = title.to_string();
+        let subtitle = subtitle.to_string();
+        let caption = caption.to_string();
+
+        let encoded = tokio::task::spawn_blocking(move || {
+            let card = share_card(&title, &subtitle, &caption, art, qr, font.as_deref());
+            let mut encoded = Cursor::new(Vec::new());
+            card.write_to(&mut encoded, ImageOutputFormat::Jpeg(90))
+                .map(|_| encoded.into_inner())
+                .map_err(|e| format!("Failed to encode share card: {}", e))
+        })
+        .await??;
+
+        Ok(encoded)
+    }
+
+    /// A share card rendered for `playlist_id` in `locale` within the last hour
+    pub fn cached_share_card(&self, playlist_id: &str, locale: Locale) -> Option<Vec<u8>> {
+        let cards = self.share_cards.lock().unwrap();
+        cards
+            .get(&(playlist_id.to_string(), locale))
+            .filter(|(rendered_at, _)| rendered_at.elapsed() < SHARE_CARD_TTL)
+            .map(|(_, card)| card.clone())
+    }
+
+    /// Keep a rendered share card so repeated crawls skip Spotify and the renderer
+    pub fn cache_share_card(&self, playlist_id: &str, locale: Locale, card: Vec<u8>) {
+        let mut cards = self.share_cards.lock().unwrap();
+
+        if cards.len() >= MAX_CACHED_SHARE_CARDS {
+            cards.retain(|_, (rendered_at, _)| rendered_at.elapsed() < SHARE_CARD_TTL);
+        }
+        if cards.len() >= MAX_CACHED_SHARE_CARDS {
+            let oldest = cards
+                .iter()
+                .min_by_key(|(_, (rendered_at, _))| *rendered_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                cards.remove(&oldest);
+            }
+        }
+
+        cards.insert((playlist_id.to_string(), locale), (Instant::now(), card));
+    }
+
+    /// Replace the playlist's cover. Needs a token with the `ugc-image-upload` scope.
+    pub async fn upload(
+        &self,
+        spotify: &AuthCodeSpotify,
+        playlist_id: PlaylistId<'_>,
+        image_base64: String,
+    ) -> Result<(), Box<dyn Error>> {
+        // rspotify 0.12 has no call for this endpoint, so send it with the user's token
+        let access_token = spotify
+            .get_token()
+            .lock()
+            .await
+            .map_err(|_| "Spotify token lock poisoned")?
+            .as_ref()
+            .map(|token| token.access_token.clone())
+            .ok_or("Not authenticated with Spotify")?;
+
+        let response = self
+            .client
+            .put(format!(
+                "https://api.spotify.com/v1/playlists/{}/images",
+                playlist_id.id()
+            ))
+            .bearer_auth(access_token)
+            .header("Content-Type", "image/jpeg")
+            .body(image_base64)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Spotify cover upload failed ({}): {}", status, body).into());
+        }
+
+        Ok(())
+    }
+
+    /// Download up to four distinct album covers, skipping any that fail
+    async fn fetch_album_art(&self, urls: &[String]) -> Vec<DynamicImage> {
+        let mut seen = Vec::new();
+        let mut images = Vec::new();
+
+        for url in urls {
+            if images.len() == 4 {
+                break;
+            }
+            if seen.contains(url) {
+                continue;
+            }
+            seen.push(url.clone());
+
+            match self.fetch_image(url).await {
+                Ok(image) => images.push(image),
+                Err(e) => tracing::debug!(url = %url, error = %e, "Skipping album art"),
+            }
+        }
+
+        images
+    }
+
+    /// Download and decode an image, giving up as soon as it passes `MAX_ART_BYTES`
+    async fn fetch_image(&self, url: &str) -> Result<DynamicImage, Box<dyn Error>> {
+        let mut response = self.client.get(url).send().await?.error_for_status()?;
+
+        if response
+            .content_length()
+            .is_some_and(|length| length > MAX_ART_BYTES as u64)
+        {
+            return Err("album art is too large".into());
+        }
+
+        // Servers may omit or understate Content-Length, so count while reading too
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if bytes.len() + chunk.len() > MAX_ART_BYTES {
+                return Err("album art is too large".into());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        Ok(image::load_from_memory(&bytes)?)
+    }
+}
+
+/// Two colours picked from the title so the same playlist always gets the same cover
+fn gradient_colors(title: &str) -> (Rgb<u8>, Rgb<u8>) {
+    let mut hasher = DefaultHasher::new();
+    title.hash(&mut hasher);
+    let hue = (hasher.finish() % 360) as f32;
+
+    (hsl(hue, 0.65, 0.5), hsl((hue + 50.0) % 360.0, 0.7, 0.22))
+}
+
+fn hsl(hue: f32, saturation: f32, lightness: f32) -> Rgb<u8> {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let channel = |value: f32| ((value + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+
+    Rgb([channel(r), channel(g), channel(b)])
+}
+
+fn gradient_cover(title: &str, font: Option<&FontVec>) -> RgbImage {
+    let (from, to) = gradient_colors(title);
+    let span = (2 * (COVER_SIZE - 1)) as f32;
+
+    let mut cover = RgbImage::from_fn(COVER_SIZE, COVER_SIZE, |x, y| {
+        let t = (x + y) as f32 / span;
+        Rgb([0, 1, 2].map(|i| (from[i] as f32 * (1.0 - t) + to[i] as f32 * t) as u8))
+    });
+
+    if let Some(font) = font {
+        draw_title(&mut cover, font, title);
+    }
+
+    cover
+}
+
+/// Break text into lines no wider than `max_width`, shrinking it from `size` until it fits
+/// in `max_lines`. Text that still does not fit at the smallest size is cut off with "…".
+fn layout_text(
+    font: &FontVec,
+    text: &str,
+    max_width: f32,
+    mut size: f32,
+    max_lines: usize,
+) -> (PxScale, Vec<String>) {
+    loop {
+        let scale = PxScale::from(size);
+        let mut lines: Vec<String> = Vec::new();
+
+        for word in text.split_whitespace() {
+            if let Some(line) = lines.last_mut() {
+                let joined = format!("{} {}", line, word);
+                if text_width(font, scale, &joined) <= max_width {
+                    *line = joined;
+                    continue;
+                }
+            }
+            lines.push(word.to_string());
+        }
+
+        let fits = lines.len() <= max_lines
+            && lines
+                .iter()
+                .all(|line| text_width(font, scale, line) <= max_width);
+        if fits {
+            return (scale, lines);
+        }
+        if size <= 28.0 {
+            if lines.len() > max_lines {
+                lines.truncate(max_lines);
+                if let Some(last) = lines.last_mut() {
+                    last.push('…');
+                }
+            }
+            return (scale, lines);
+        }
+        size -= 8.0;
+    }
+}
+
+fn text_width(font: &FontVec, scale: PxScale, text: &str) -> f32 {
+    let scaled = font.as_scaled(scale);
+    let mut width = 0.0;
+    let mut previous = None;
+
+    for c in text.chars() {
+        let glyph = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            width += scaled.kern(previous, glyph);
+        }
+        width += scaled.h_advance(glyph);
+        previous = Some(glyph);
+    }
+
+    width
+}
+
+fn draw_title(cover: &mut RgbImage, font: &FontVec, title: &str) {
+    let max_width = COVER_SIZE as f32 - 2.0 * TEXT_MARGIN;
+    let (scale, lines) = layout_text(font, title, max_width, 96.0, MAX_TITLE_LINES);
+    let scaled = font.as_scaled(scale);
+    let line_height = scaled.height() + scaled.line_gap();
+    let block_height = line_height * lines.len() as f32;
+    let mut baseline = (COVER_SIZE as f32 - block_height) / 2.0 + scaled.ascent();
+
+    for line in &lines {
+        let x = (COVER_SIZE as f32 - text_width(font, scale, line)) / 2.0;
+        // A soft shadow keeps white text readable on the lighter end of the gradient
+        draw_line(
+            cover,
+            font,
+            scale,
+            line,
+            (x + 3.0, baseline + 3.0),
+            [0, 0, 0],
+            0.35,
+        );
+        draw_line(
+            cover,
+            font,
+            scale,
+            line,
+            (x, baseline),
+            [255, 255, 255],
+            1.0,
+        );
+        baseline += line_height;
+    }
+}
+
+/// Blend one line of text onto the cover, starting at `(x, baseline)`
+fn draw_line(
+    cover: &mut RgbImage,
+    font: &FontVec,
+    scale: PxScale,
+    text: &str,
+    (x, baseline): (f32, f32),
+    color: [u8; 3],
+    opacity: f32,
+) {
+    let scaled = font.as_scaled(scale);
+    let mut caret = x;
+    let mut previous = None;
+
+    for c in text.chars() {
+        let glyph_id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, glyph_id);
+        }
+        previous = Some(glyph_id);
+
+        let glyph = glyph_id.with_scale_and_position(scale, point(caret, baseline));
+        caret += scaled.h_advance(glyph_id);
+
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+
+        outline.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+            if px < 0 || py < 0 || px >= cover.width() as i32 || py >= cover.height() as i32 {
+                return;
+            }
+
+            let alpha = coverage * opacity;
+            let pixel = cover.get_pixel_mut(px as u32, py as u32);
+            for i in 0..3 {
+                pixel[i] = (pixel[i] as f32 * (1.0 - alpha) + color[i] as f32 * alpha) as u8;
+            }
+        });
+    }
+}
+
+/// Cover on the left; title, subtitle and QR code on the right of a dark backdrop
+fn share_card(
+    title: &str,
+    subtitle: &str,
+    caption: &str,
+    art: Option<DynamicImage>,
+    qr: RgbaImage,
+    font: Option<&FontVec>,
+) -> RgbImage {
+    let (accent, _) = gradient_colors(title);
+    let background = Rgb([0x19, 0x14, 0x14]);
+    let mut card = RgbImage::from_fn(CARD_WIDTH, CARD_HEIGHT, |x, _| {
+        let t = 0.35 * (1.0 - x as f32 / CARD_WIDTH as f32);
+        Rgb([0, 1, 2].map(|i| (background[i] as f32 * (1.0 - t) + accent[i] as f32 * t) as u8))
+    });
+
+    let cover = match art {
+        Some(art) => art
+            .resize_to_fill(CARD_COVER_SIZE, CARD_COVER_SIZE, FilterType::Triangle)
+            .to_rgb8(),
+        None => image::imageops::resize(
+            &gradient_cover(title, font),
+            CARD_COVER_SIZE,
+            CARD_COVER_SIZE,
+            FilterType::Triangle,
+        ),
+    };
+    image::imageops::replace(&mut card, &cover, CARD_MARGIN as i64, CARD_MARGIN as i64);
+
+    let right = CARD_WIDTH - CARD_MARGIN;
+    let bottom = CARD_HEIGHT - CARD_MARGIN;
+    let qr = DynamicImage::ImageRgba8(qr).to_rgb8();
+    let (qr_x, qr_y) = (right - qr.width(), bottom - qr.height());
+    image::imageops::replace(&mut card, &qr, qr_x as i64, qr_y as i64);
+
+    let Some(font) = font else {
+        return card;
+    };
+
+    let text_x = (2 * CARD_MARGIN + CARD_COVER_SIZE) as f32;
+    let max_width = right as f32 - text_x;
+    let white = [255, 255, 255];
+    let grey = [0xB3, 0xB3, 0xB3];
+
+    let (scale, lines) = layout_text(font, title, max_width, 64.0, 3);
+    let scaled = font.as_scaled(scale);
+    let mut baseline = CARD_MARGIN as f32 + scaled.ascent();
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            baseline += scaled.height() + scaled.line_gap();
+        }
+        draw_line(&mut card, font, scale, line, (text_x, baseline), white, 1.0);
+    }
+    // Descent is negative, so this is the bottom of the title block
+    let title_bottom = baseline - scaled.descent();
+
+    let (scale, lines) = layout_text(font, subtitle, max_width, 30.0, 1);
+    let scaled = font.as_scaled(scale);
+    if let Some(line) = lines.first() {
+        let baseline = title_bottom + 20.0 + scaled.ascent();
+        draw_line(&mut card, font, scale, line, (text_x, baseline), grey, 1.0);
+    }
+
+    let caption_width = (qr_x as f32 - text_x - 24.0).max(0.0);
+    let (scale, lines) = layout_text(font, caption, caption_width, 28.0, 2);
+    let scaled = font.as_scaled(scale);
+    let line_height = scaled.height() + scaled.line_gap();
+    let mut baseline = bottom as f32 - line_height * (lines.len() as f32 - 1.0) + scaled.descent();
+    for line in &lines {
+        draw_line(&mut card, font, scale, line, (text_x, baseline), grey, 1.0);
+        baseline += line_height;
+    }
+
+    card
+}
+
+/// A 2x2 grid when four covers are available, otherwise the first one full size
+fn collage_cover(art: &[DynamicImage]) -> RgbImage {
+    if art.len() < 4 {
+        return art[0]
+            .resize_to_fill(COVER_SIZE, COVER_SIZE, FilterType::Triangle)
+            .to_rgb8();
+    }
+
+    let tile = COVER_SIZE / 2;
+    let mut cover = RgbImage::new(COVER_SIZE, COVER_SIZE);
+
+    for (i, image) in art.iter().take(4).enumerate() {
+        let tile_image = image
+            .resize_to_fill(tile, tile, FilterType::Triangle)
+            .to_rgb8();
+        let x = (i as u32 % 2) * tile;
+        let y = (i as u32 / 2) * tile;
+        image::imageops::replace(&mut cover, &tile_image, x as i64, y as i64);
+    }
+
+    cover
+}
+
+/// JPEG-encode at the highest quality whose base64 form fits Spotify's upload limit
+fn encode_for_upload(cover: &RgbImage) -> Result<String, String> {
+    for quality in (40..=90).rev().step_by(10) {
+        let mut encoded = Cursor::new(Vec::new());
+        cover
+            .write_to(&mut encoded, ImageOutputFormat::Jpeg(quality))
+            .map_err(|e| format!("Failed to encode cover: {}", e))?;
+
+        let encoded = STANDARD.encode(encoded.into_inner());
+        if encoded.len() <= MAX_UPLOAD_BYTES {
+            return Ok(encoded);
+        }
+    }
+
+    Err("Cover is too large to upload even at low quality".to_string())
+}
@@ -1,12 +1,25 @@
-use crate::models::playlist::{GeminiPromptResponse, GeminiTrack};
+use crate::config::Secret;
+use crate::i18n::Locale;
+use crate::metrics::{GEMINI_REQUEST_DURATION, GEMINI_TOKENS_TOTAL};
+use crate::models::playlist::{AlbumConcept, GeminiPromptResponse, PlaylistSeed, SeedMode};
 use reqwest::Client;
 use serde_json::json;
+use std::collections::HashSet;
 use std::error::Error;
+use std::time::Instant;
+
+/// The Gemini model every request goes to, recorded alongside stored generations
+pub const GEMINI_MODEL: &str = "gemini-2.0-flash";
+
+const GEMINI_MODELS_URL: &str = "https://generativelanguage.googleapis.com/v1beta/models";
+
+fn model_url() -> String {
+    format!("{}/{}", GEMINI_MODELS_URL, GEMINI_MODEL)
+}
 
 #[derive(Debug)]
 pub struct GeminiService {
-    api_key: String,
-    #[doc(hidden)]
+    api_key: Secret,
     client: Client,
 }
 
@@ -20,28 +33,37 @@ impl Clone for GeminiService {
 }
 
 impl GeminiService {
-    pub fn new(api_key: String) -> Self {
+    pub fn new(api_key: Secret) -> Self {
         Self {
             api_key,
             client: Client::new(),
         }
     }
 
+    /// Confirm the API key is accepted by fetching the model's metadata, which costs no tokens
+    pub async fn check_reachable(&self) -> Result<(), Box<dyn Error>> {
+        let response = self
+            .client
+            .get(model_url())
+            .header("x-goog-api-key", self.api_key.expose())
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("Gemini API returned {}", status).into());
+        }
+
+        Ok(())
+    }
+
+    /// Suggest songs for a prompt, naming and describing the playlist in `language`
     pub async fn generate_playlist(
         &self,
         prompt: &str,
+        language: Locale,
     ) -> Result<GeminiPromptResponse, Box<dyn Error>> {
-        println!(
-            "Starting playlist generation with API key length: {}",
-            self.api_key.len()
-        );
-        println!("Generating playlist with prompt: {}", prompt);
-
-        let url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.0-flash:generateContent?key={}",
-            self.api_key
-        );
-        println!("Using Gemini API URL: {}", url);
+        tracing::debug!(prompt, language = language.tag(), "Generating playlist");
 
         // Format the request prompt to ask for specific song suggestions
         let instruction = format!(
@@ -60,14 +82,202 @@ impl GeminiService {
                 \"playlist_description\": \"A description explaining the playlist concept and how these songs fit together\"
             }}
             
+            {}
+            
+            Be thoughtful in your song selections, ensuring they're real songs by real artists that can be found on music streaming platforms.",
+            prompt,
+            language_instruction(language)
+        );
+
+        let text = self
+            .generate_structured(&instruction, playlist_schema())
+            .await?;
+
+        parse_playlist(&text)
+    }
+
+    /// Suggest `track_count` songs that extend the seed playlist or reimagine it
+    pub async fn generate_seeded_playlist(
+        &self,
+        seed: &PlaylistSeed,
+        mode: SeedMode,
+        direction: Option<&str>,
+        track_count: usize,
+        language: Locale,
+    ) -> Result<GeminiPromptResponse, Box<dyn Error>> {
+        tracing::debug!(
+            playlist = %seed.name,
+            ?mode,
+            direction,
+            track_count,
+            language = language.tag(),
+            "Generating seeded playlist"
+        );
+
+        let tracks: Vec<String> = seed
+            .tracks
+            .iter()
+            .map(|track| match &track.features {
+                Some(features) => format!(
+                    "- {} by {} (tempo {:.0} BPM, energy {:.2}, valence {:.2}, danceability {:.2}, acousticness {:.2}, instrumentalness {:.2})",
+                    track.name,
+                    track.artist,
+                    features.tempo,
+                    features.energy,
+                    features.valence,
+                    features.danceability,
+                    features.acousticness,
+                    features.instrumentalness
+                ),
+                None => format!("- {} by {}", track.name, track.artist),
+            })
+            .collect();
+
+        let task = match mode {
+            SeedMode::Extend => format!(
+                "Suggest {} more songs that would fit naturally at the end of this playlist, keeping its genre, mood and energy. Do not repeat any song that is already in it.",
+                track_count
+            ),
+            SeedMode::Variant => format!(
+                "Create a new playlist of {} songs that is a variation of this one: keep what defines it but change it as directed. You may reuse a few of its songs if they suit the variation.",
+                track_count
+            ),
+        };
+
+        let instruction = format!(
+            "Here is an existing Spotify playlist called '{}'{}.
+            
+            Its songs are:
+            {}
+            
+            {}
+            {}
+            
+            Give the new playlist a fitting name and a description of how it relates to the original. {}
+            
             Be thoughtful in your song selections, ensuring they're real songs by real artists that can be found on music streaming platforms.",
-            prompt
+            seed.name,
+            seed.description
+                .as_deref()
+                .filter(|description| !description.trim().is_empty())
+                .map(|description| format!(" described as '{}'", description))
+                .unwrap_or_default(),
+            tracks.join("\n"),
+            task,
+            direction
+                .map(|direction| format!("Direction from the listener: '{}'", direction))
+                .unwrap_or_default(),
+            language_instruction(language)
+        );
+
+        let text = self
+            .generate_structured(&instruction, playlist_schema())
+            .await?;
+
+        let mut playlist = parse_playlist(&text)?;
+
+        if mode == SeedMode::Extend {
+            let existing: HashSet<(String, String)> = seed
+                .tracks
+                .iter()
+                .map(|track| (track.name.to_lowercase(), track.artist.to_lowercase()))
+                .collect();
+            playlist.tracks.retain(|track| {
+                !existing.contains(&(track.title.to_lowercase(), track.artist.to_lowercase()))
+            });
+
+            if playlist.tracks.is_empty() {
+                return Err("Gemini only suggested songs already in the playlist".into());
+            }
+        }
+
+        Ok(playlist)
+    }
+
+    /// Ask Gemini for `track_count` original songs (title plus a detailed MusicGen prompt each)
+    pub async fn generate_album_concept(
+        &self,
+        prompt: &str,
+        track_count: usize,
+    ) -> Result<AlbumConcept, Box<dyn Error>> {
+        tracing::debug!(prompt, track_count, "Generating album concept");
+
+        let instruction = format!(
+            "Based on this prompt: '{}', design a cohesive instrumental album of exactly {} original tracks.
+            
+            For every track write a title and a detailed text-to-music prompt for an AI music model (MusicGen). Each prompt should describe genre, mood, tempo, key instruments and production style in one or two sentences, and the tracks should flow together as an album with some variety between them.
+            
+            Also write an album title, a short album description and a prompt for an image generator describing the album cover art.
+            
+            Your response should be in JSON format with the following structure:
+            {{
+                \"album_title\": \"Album Title\",
+                \"description\": \"A description of the album concept\",
+                \"cover_art_prompt\": \"A description of the cover artwork\",
+                \"tracks\": [
+                    {{ \"title\": \"Track Title 1\", \"prompt\": \"Detailed music prompt 1\" }},
+                    ...
+                ]
+            }}",
+            prompt, track_count
         );
 
-        println!(
-            "Making request to Gemini API with instruction: {}",
-            instruction
+        let schema = json!({
+            "type": "OBJECT",
+            "properties": {
+                "album_title": {"type": "STRING"},
+                "description": {"type": "STRING"},
+                "cover_art_prompt": {"type": "STRING"},
+                "tracks": {
+                    "type": "ARRAY",
+                    "items": {
+                        "type": "OBJECT",
+                        "properties": {
+                            "title": {"type": "STRING"},
+                            "prompt": {"type": "STRING"}
+                        },
+                        "required": ["title", "prompt"]
+                    }
+                }
+            },
+            "required": ["album_title", "description", "cover_art_prompt", "tracks"]
+        });
+
+        let text = self.generate_structured(&instruction, schema).await?;
+
+        let mut concept: AlbumConcept = serde_json::from_str(&text).map_err(|e| {
+            tracing::warn!(error = %e, "Error parsing Gemini album concept as JSON");
+            "Failed to parse AI response. Please try a different prompt."
+        })?;
+
+        concept
+            .tracks
+            .retain(|track| !track.prompt.trim().is_empty());
+        concept.tracks.truncate(track_count);
+
+        if concept.tracks.is_empty() {
+            return Err("Generated album has no tracks".into());
+        }
+
+        tracing::info!(
+            album = %concept.album_title,
+            tracks = concept.tracks.len(),
+            "Parsed generated album concept"
         );
+
+        Ok(concept)
+    }
+
+    /// Send an instruction to Gemini constrained by a JSON response schema and
+    /// return the generated JSON text
+    #[tracing::instrument(name = "gemini.generate", skip_all)]
+    async fn generate_structured(
+        &self,
+        instruction: &str,
+        schema: serde_json::Value,
+    ) -> Result<String, Box<dyn Error>> {
+        let url = format!("{}:generateContent", model_url());
+        tracing::debug!(instruction, "Sending instruction to Gemini");
         let request_body = json!({
             "contents": [{
                 "parts": [{
@@ -76,97 +286,128 @@ impl GeminiService {
             }],
             "generationConfig": {
                 "responseMimeType": "application/json",
-                "responseSchema": {
-                    "type": "OBJECT",
-                    "properties": {
-                        "tracks": {
-                            "type": "ARRAY",
-                            "items": {
-                                "type": "OBJECT",
-                                "properties": {
-                                    "title": {"type": "STRING"},
-                                    "artist": {"type": "STRING"}
-                                },
-                                "required": ["title", "artist"]
-                            }
-                        },
-                        "playlist_name": {
-                            "type": "STRING"
-                        },
-                        "playlist_description": {
-                            "type": "STRING"
-                        }
-                    },
-                    "required": ["tracks", "playlist_name", "playlist_description"]
-                }
+                "responseSchema": schema
             }
         });
-        println!(
-            "Request body: {}",
-            serde_json::to_string_pretty(&request_body).unwrap()
-        );
-
+        let started = Instant::now();
         let response = self
             .client
             .post(&url)
             .header("Content-Type", "application/json")
+            .header("x-goog-api-key", self.api_key.expose())
             .json(&request_body)
             .send()
-            .await?;
+            .await;
 
+        let outcome = match &response {
+            Ok(response) if response.status().is_success() => "ok",
+            _ => "error",
+        };
+        GEMINI_REQUEST_DURATION
+            .with_label_values(&[outcome])
+            .observe(started.elapsed().as_secs_f64());
+
+        let response = response?;
         let status = response.status();
-        println!("Gemini API response status: {}", status);
+        tracing::info!(
+            status = status.as_u16(),
+            latency_ms = started.elapsed().as_millis() as u64,
+            "Gemini responded"
+        );
 
         if !status.is_success() {
             let error_text = response.text().await?;
-            eprintln!("Gemini API error response: {}", error_text);
+            tracing::warn!(status = status.as_u16(), body = %error_text, "Gemini API error");
             return Err(format!("Gemini API error ({}): {}", status, error_text).into());
         }
 
         let response_text = response.text().await?;
-        println!("Raw Gemini Response: {}", response_text);
 
         let response_json: serde_json::Value =
             serde_json::from_str(&response_text).map_err(|e| {
-                eprintln!("Failed to parse response as JSON: {}", e);
+                tracing::warn!(error = %e, "Failed to parse Gemini response as JSON");
                 format!("Failed to parse Gemini response as JSON: {}", e)
             })?;
 
-        // Extract the text output from the response
-        if let Some(candidates) = response_json.get("candidates").and_then(|c| c.as_array()) {
-            if let Some(candidate) = candidates.first() {
-                if let Some(content) = candidate.get("content") {
-                    if let Some(parts) = content.get("parts").and_then(|p| p.as_array()) {
-                        if let Some(part) = parts.first() {
-                            if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
-                                println!("Generated text from Gemini: {}", text);
-
-                                // Parse the JSON response
-                                match serde_json::from_str::<GeminiPromptResponse>(text) {
-                                    Ok(result) => {
-                                        if result.tracks.is_empty() {
-                                            eprintln!("Generated playlist has no tracks");
-                                            return Err("Generated playlist has no tracks".into());
-                                        }
-                                        println!(
-                                            "Successfully parsed playlist with {} tracks",
-                                            result.tracks.len()
-                                        );
-                                        return Ok(result);
-                                    }
-                                    Err(e) => {
-                                        eprintln!("Error parsing Gemini response as JSON: {}", e);
-                                        eprintln!("Raw response: {}", text);
-                                        return Err("Failed to parse AI response. Please try a different prompt.".into());
-                                    }
-                                }
-                            }
-                        }
-                    }
+        if let Some(usage) = response_json.get("usageMetadata") {
+            for (kind, field) in [
+                ("prompt", "promptTokenCount"),
+                ("completion", "candidatesTokenCount"),
+            ] {
+                if let Some(count) = usage.get(field).and_then(|count| count.as_u64()) {
+                    GEMINI_TOKENS_TOTAL.with_label_values(&[kind]).inc_by(count);
                 }
             }
         }
 
-        Err("Invalid response structure from AI".into())
+        // Extract the text output from the response
+        let text = response_json
+            .get("candidates")
+            .and_then(|c| c.as_array())
+            .and_then(|candidates| candidates.first())
+            .and_then(|candidate| candidate.get("content"))
+            .and_then(|content| content.get("parts"))
+            .and_then(|p| p.as_array())
+            .and_then(|parts| parts.first())
+            .and_then(|part| part.get("text"))
+            .and_then(|t| t.as_str())
+            .ok_or("Invalid response structure from AI")?;
+
+        tracing::debug!(text, "Generated text from Gemini");
+
+        Ok(text.to_string())
+    }
+}
+
+/// Song titles and artists stay as released; only the text Gemini writes itself is translated
+fn language_instruction(language: Locale) -> String {
+    format!(
+        "Write the playlist name and description in {}, but keep song titles and artist names exactly as they are released.",
+        language.language_name()
+    )
+}
+
+/// Response schema shared by every playlist suggestion
+fn playlist_schema() -> serde_json::Value {
+    json!({
+        "type": "OBJECT",
+        "properties": {
+            "tracks": {
+                "type": "ARRAY",
+                "items": {
+                    "type": "OBJECT",
+                    "properties": {
+                        "title": {"type": "STRING"},
+                        "artist": {"type": "STRING"}
+                    },
+                    "required": ["title", "artist"]
+                }
+            },
+            "playlist_name": {
+                "type": "STRING"
+            },
+            "playlist_description": {
+                "type": "STRING"
+            }
+        },
+        "required": ["tracks", "playlist_name", "playlist_description"]
+    })
+}
+
+fn parse_playlist(text: &str) -> Result<GeminiPromptResponse, Box<dyn Error>> {
+    match serde_json::from_str::<GeminiPromptResponse>(text) {
+        Ok(result) => {
+            if result.tracks.is_empty() {
+                tracing::warn!("Generated playlist has no tracks");
+                return Err("Generated playlist has no tracks".into());
+            }
+            tracing::info!(tracks = result.tracks.len(), "Parsed generated playlist");
+            Ok(result)
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Error parsing Gemini playlist as JSON");
+            tracing::debug!(response = %text, "Unparseable Gemini playlist");
+            Err("Failed to parse AI response. Please try a different prompt.".into())
+        }
     }
 }
@@ -0,0 +1,230 @@
+use crate::config::SpotifyConfig;
+use crate::models::health::{
+    CheckStatus, DependencyCheck, DependencyChecks, LivenessReport, ReadinessReport,
+};
+use crate::services::gemini_service::GeminiService;
+use crate::services::music_generator::MusicGenerator;
+use chrono::Utc;
+use rspotify::ClientCredsSpotify;
+use sqlx::SqlitePool;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// How long dependency checks are reused before probing again
+const DEFAULT_CACHE_SECONDS: u64 = 30;
+/// How long a single dependency may take to answer
+const DEFAULT_CHECK_TIMEOUT_SECONDS: u64 = 5;
+
+#[derive(Clone)]
+pub struct HealthService {
+    pool: SqlitePool,
+    gemini_service: GeminiService,
+    musicgen_service: Arc<dyn MusicGenerator>,
+    spotify_config: Option<SpotifyConfig>,
+    started_at: Instant,
+    cache_ttl: Duration,
+    check_timeout: Duration,
+    cached: Arc<Mutex<Option<(Instant, ReadinessReport)>>>,
+}
+
+impl HealthService {
+    pub fn new(
+        pool: SqlitePool,
+        gemini_service: GeminiService,
+        musicgen_service: Arc<dyn MusicGenerator>,
+        spotify_config: Option<SpotifyConfig>,
+        cache_ttl: Duration,
+        check_timeout: Duration,
+    ) -> Self {
+        Self {
+            pool,
+            gemini_service,
+            musicgen_service,
+            spotify_config,
+            started_at: Instant::now(),
+            cache_ttl,
+            check_timeout,
+            cached: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Create a HealthService reading HEALTH_CACHE_SECONDS and HEALTH_CHECK_TIMEOUT_SECONDS
+    pub fn from_env(
+        pool: SqlitePool,
+        gemini_service: GeminiService,
+        musicgen_service: Arc<dyn MusicGenerator>,
+        spotify_config: Option<SpotifyConfig>,
+    ) -> Self {
+        let seconds = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self::new(
+            pool,
+            gemini_service,
+            musicgen_service,
+            spotify_config,
+            Duration::from_secs(seconds("HEALTH_CACHE_SECONDS", DEFAULT_CACHE_SECONDS)),
+            Duration::from_secs(seconds(
+                "HEALTH_CHECK_TIMEOUT_SECONDS",
+                DEFAULT_CHECK_TIMEOUT_SECONDS,
+            ))
+            .max(Duration::from_secs(1)),
+        )
+    }
+
+    /// The process is up; no dependency is contacted
+    pub fn liveness(&self) -> LivenessReport {
+        LivenessReport {
+            status: "ok",
+            version: env!("CARGO_PKG_VERSION"),
+            uptime_seconds: self.started_at.elapsed().as_secs(),
+        }
+    }
+
+    /// Check every dependency, reusing the last results while they are fresh.
+    /// Concurrent callers wait for a single refresh instead of each probing.
+    pub async fn readiness(&self) -> ReadinessReport {
+        let mut cached = self.cached.lock().await;
+
+        if let Some((checked_at, report)) = cached.as_ref() {
+            let age = checked_at.elapsed();
+            if age < self.cache_ttl {
+                let mut report = report.clone();
+                report.age_seconds = age.as_secs();
+                return report;
+            }
+        }
+
+        let checks = self.run_checks().await;
+        let report = ReadinessReport {
+            ready: checks.is_ready(),
+            checked_at: Utc::now().to_rfc3339(),
+            age_seconds: 0,
+            checks,
+        };
+
+        *cached = Some((Instant::now(), report.clone()));
+        report
+    }
+
+    async fn run_checks(&self) -> DependencyChecks {
+        let (database, musicgen, gemini, spotify) = tokio::join!(
+            self.check_database(),
+            self.check_musicgen(),
+            self.check_gemini(),
+            self.check_spotify()
+        );
+
+        DependencyChecks {
+            database,
+            musicgen,
+            gemini,
+            spotify,
+        }
+    }
+
+    async fn check_database(&self) -> DependencyCheck {
+        self.probe(async {
+            sqlx::query("SELECT 1")
+                .execute(&self.pool)
+                .await
+                .map(|_| None)
+                .map_err(|e| e.to_string())
+        })
+        .await
+    }
+
+    async fn check_musicgen(&self) -> DependencyCheck {
+        self.probe(async {
+            let health = self
+                .musicgen_service
+                .health_check()
+                .await
+                .map_err(|e| e.to_string())?;
+
+            if !health.healthy {
+                return Err("MusicGen model is not loaded".to_string());
+            }
+
+            Ok(Some(serde_json::json!({
+                "device": health.device,
+                "models": health.models,
+            })))
+        })
+        .await
+    }
+
+    async fn check_gemini(&self) -> DependencyCheck {
+        self.probe(async {
+            self.gemini_service
+                .check_reachable()
+                .await
+                .map(|_| None)
+                .map_err(|e| e.to_string())
+        })
+        .await
+    }
+
+    /// Request a client-credentials token to prove the app's Spotify credentials are valid
+    async fn check_spotify(&self) -> DependencyCheck {
+        let Some(spotify_config) = &self.spotify_config else {
+            return DependencyCheck {
+                status: CheckStatus::Skipped,
+                latency_ms: 0,
+                message: Some(
+                    "SPOTIFY_CLIENT_ID, SPOTIFY_CLIENT_SECRET or SPOTIFY_REDIRECT_URI is not set"
+                        .to_string(),
+                ),
+                details: None,
+            };
+        };
+
+        self.probe(async {
+            let spotify = ClientCredsSpotify::new(spotify_config.credentials());
+            spotify
+                .request_token()
+                .await
+                .map(|_| None)
+                .map_err(|e| e.to_string())
+        })
+        .await
+    }
+
+    /// Time a check and turn its outcome into a report, failing it if it takes too long
+    async fn probe<F>(&self, check: F) -> DependencyCheck
+    where
+        F: Future<Output = Result<Option<serde_json::Value>, String>>,
+    {
+        let started = Instant::now();
+        let outcome = tokio::time::timeout(self.check_timeout, check)
+            .await
+            .unwrap_or_else(|_| {
+                Err(format!(
+                    "No answer within {} seconds",
+                    self.check_timeout.as_secs()
+                ))
+            });
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        match outcome {
+            Ok(details) => DependencyCheck {
+                status: CheckStatus::Ok,
+                latency_ms,
+                message: None,
+                details,
+            },
+            Err(message) => DependencyCheck {
+                status: CheckStatus::Down,
+                latency_ms,
+                message: Some(message),
+                details: None,
+            },
+        }
+    }
+}
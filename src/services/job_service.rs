@@ -0,0 +1,625 @@
+use crate::metrics::MUSICGEN_JOB_DURATION;
+use crate::models::job::{Job, JobKind, JobStatus};
+use crate::models::library::{NewLibraryTrack, TrackSource};
+use crate::models::playlist::{
+    AiAlbumRequest, AiAlbumResponse, AiAlbumTrack, AiBatchFailure, AiMusicBatchRequest,
+    AiMusicBatchResponse, AiMusicRequest, AiSong, GenerationControls, DEFAULT_AI_MUSIC_DURATION,
+};
+use crate::services::audio_cache_service::AudioCacheService;
+use crate::services::gemini_service::GeminiService;
+use crate::services::library_service::LibraryService;
+use crate::services::music_generator::{batch_outcomes, MusicGenerator};
+use chrono::Utc;
+use futures::StreamExt;
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{broadcast, mpsc, Mutex};
+use tracing::Instrument;
+
+/// Default number of concurrent MusicGen workers
+const DEFAULT_WORKERS: usize = 2;
+
+/// Tracks on an album when the request does not say
+pub const DEFAULT_ALBUM_TRACKS: usize = 5;
+
+#[derive(Debug, sqlx::FromRow)]
+struct JobRow {
+    id: String,
+    kind: String,
+    status: String,
+    progress: f64,
+    file_ids: String,
+    result: Option<String>,
+    error: Option<String>,
+    user_id: Option<String>,
+    session_owner: Option<String>,
+    created_at: String,
+    updated_at: String,
+}
+
+impl From<JobRow> for Job {
+    fn from(row: JobRow) -> Self {
+        Job {
+            id: row.id,
+            kind: JobKind::parse(&row.kind),
+            status: JobStatus::parse(&row.status),
+            progress: row.progress,
+            file_ids: serde_json::from_str(&row.file_ids).unwrap_or_default(),
+            result: row.result.and_then(|r| serde_json::from_str(&r).ok()),
+            error: row.error,
+            user_id: row.user_id,
+            session_owner: row.session_owner,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// Queues AI music generation so HTTP requests can return immediately.
+/// Job state lives in SQLite and every change is broadcast to SSE subscribers.
+#[derive(Debug, Clone)]
+pub struct JobService {
+    pool: SqlitePool,
+    audio_cache_service: AudioCacheService,
+    library_service: LibraryService,
+    gemini_service: GeminiService,
+    queue: mpsc::UnboundedSender<String>,
+    events: broadcast::Sender<Job>,
+}
+
+impl JobService {
+    /// Create the service and the receiving end of its queue, to be handed to `start_workers`
+    pub fn new(
+        pool: SqlitePool,
+        audio_cache_service: AudioCacheService,
+        library_service: LibraryService,
+        gemini_service: GeminiService,
+    ) -> (Self, mpsc::UnboundedReceiver<String>) {
+        let (queue, receiver) = mpsc::unbounded_channel();
+        let (events, _) = broadcast::channel(256);
+
+        (
+            Self {
+                pool,
+                audio_cache_service,
+                library_service,
+                gemini_service,
+                queue,
+                events,
+            },
+            receiver,
+        )
+    }
+
+    /// Spawn the worker pool (size from MUSIC_JOB_WORKERS) and requeue unfinished jobs
+    pub async fn start_workers(
+        &self,
+        receiver: mpsc::UnboundedReceiver<String>,
+        musicgen_service: Arc<dyn MusicGenerator>,
+    ) -> Result<(), Box<dyn Error>> {
+        let workers = std::env::var("MUSIC_JOB_WORKERS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .filter(|count| *count > 0)
+            .unwrap_or(DEFAULT_WORKERS);
+
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for worker_id in 0..workers {
+            let service = self.clone();
+            let receiver = Arc::clone(&receiver);
+            let musicgen_service = Arc::clone(&musicgen_service);
+
+            tokio::spawn(async move {
+                loop {
+                    let job_id = match receiver.lock().await.recv().await {
+                        Some(job_id) => job_id,
+                        None => break,
+                    };
+
+                    let span = tracing::info_span!("job", job_id = %job_id, worker = worker_id);
+                    async {
+                        tracing::info!("Worker picked up job");
+                        if let Err(e) = service.run_job(&job_id, musicgen_service.as_ref()).await {
+                            tracing::error!(error = %e, "Worker failed to run job");
+                        }
+                    }
+                    .instrument(span)
+                    .await;
+                }
+            });
+        }
+
+        // Jobs interrupted by a restart are picked up again from the start
+        let unfinished: Vec<(String,)> =
+            sqlx::query_as("SELECT id FROM ai_music_jobs WHERE status IN ('queued', 'running') ORDER BY created_at")
+                .fetch_all(&self.pool)
+                .await?;
+
+        for (job_id,) in unfinished {
+            tracing::info!(job_id = %job_id, "Requeueing unfinished job");
+            self.set_status(&job_id, JobStatus::Queued, 0.0).await?;
+            self.queue.send(job_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Persist a new job and push it onto the queue. Anonymous jobs pass the
+    /// `session_owner` of the browser session that may read them.
+    pub async fn enqueue(
+        &self,
+        kind: JobKind,
+        request: serde_json::Value,
+        user_id: Option<String>,
+        session_owner: Option<String>,
+    ) -> Result<Job, Box<dyn Error>> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = Utc::now().to_rfc3339();
+
+        sqlx::query(
+            r#"
+            INSERT INTO ai_music_jobs (id, kind, status, progress, request, user_id, created_at, updated_at)
+            VALUES (?, ?, ?, 0, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(kind.as_str())
+        .bind(JobStatus::Queued.as_str())
+        .bind(request.to_string())
+        .bind(&user_id)
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+
+        if let Some(session_owner) = &session_owner {
+            sqlx::query("INSERT INTO ai_music_job_sessions (job_id, session_owner) VALUES (?, ?)")
+                .bind(&id)
+                .bind(session_owner)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        self.queue.send(id.clone())?;
+
+        self.get(&id)
+            .await?
+            .ok_or_else(|| "Job disappeared right after being created".into())
+    }
+
+    /// Put a finished job back on the queue. A batch keeps its songs and only
+    /// regenerates the prompts that failed. Returns `None` if the job is not finished.
+    pub async fn retry(&self, job_id: &str) -> Result<Option<Job>, Box<dyn Error>> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE ai_music_jobs SET status = ?, progress = 0, error = NULL, updated_at = ?
+            WHERE id = ? AND status IN ('done', 'failed')
+            "#,
+        )
+        .bind(JobStatus::Queued.as_str())
+        .bind(Utc::now().to_rfc3339())
+        .bind(job_id)
+        .execute(&self.pool)
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        self.queue.send(job_id.to_string())?;
+        self.publish(job_id).await;
+        self.get(job_id).await
+    }
+
+    /// How many prompts a retry of `job` will generate: the failed ones of a finished
+    /// batch, otherwise every prompt of the original request
+    pub async fn retry_prompts(&self, job: &Job) -> Result<usize, Box<dyn Error>> {
+        let failures = job
+            .result
+            .as_ref()
+            .and_then(|result| result.get("failures"))
+            .and_then(|failures| failures.as_array())
+            .map_or(0, Vec::len);
+        if failures > 0 {
+            return Ok(failures);
+        }
+
+        let (request,): (String,) =
+            sqlx::query_as("SELECT request FROM ai_music_jobs WHERE id = ?")
+                .bind(&job.id)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(match job.kind {
+            JobKind::Single => 1,
+            JobKind::Batch => serde_json::from_str::<AiMusicBatchRequest>(&request)?
+                .prompts
+                .len(),
+            JobKind::Album => serde_json::from_str::<AiAlbumRequest>(&request)?
+                .track_count
+                .unwrap_or(DEFAULT_ALBUM_TRACKS),
+        })
+    }
+
+    /// Number of jobs waiting in the queue and currently being generated
+    pub async fn count_active(&self) -> Result<(i64, i64), sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                COALESCE(SUM(status = 'queued'), 0),
+                COALESCE(SUM(status = 'running'), 0)
+            FROM ai_music_jobs
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn get(&self, job_id: &str) -> Result<Option<Job>, Box<dyn Error>> {
+        let row: Option<JobRow> = sqlx::query_as(
+            r#"
+            SELECT id, kind, status, progress, file_ids, result, error, user_id,
+                   session_owner, created_at, updated_at
+            FROM ai_music_jobs
+            LEFT JOIN ai_music_job_sessions ON job_id = id
+            WHERE id = ?
+            "#,
+        )
+        .bind(job_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(Job::from))
+    }
+
+    /// Receive every job update; callers filter by job id
+    pub fn subscribe(&self) -> broadcast::Receiver<Job> {
+        self.events.subscribe()
+    }
+
+    async fn run_job(
+        &self,
+        job_id: &str,
+        musicgen_service: &dyn MusicGenerator,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let (kind, request, user_id, previous): (String, String, Option<String>, Option<String>) =
+            sqlx::query_as("SELECT kind, request, user_id, result FROM ai_music_jobs WHERE id = ?")
+                .bind(job_id)
+                .fetch_one(&self.pool)
+                .await?;
+
+        self.set_status(job_id, JobStatus::Running, 0.0).await?;
+        let started = Instant::now();
+
+        let outcome = match JobKind::parse(&kind) {
+            JobKind::Single => {
+                self.run_single(&request, musicgen_service, user_id.as_deref())
+                    .await
+            }
+            JobKind::Batch => {
+                self.run_batch(
+                    job_id,
+                    &request,
+                    previous.as_deref(),
+                    musicgen_service,
+                    user_id.as_deref(),
+                )
+                .await
+            }
+            JobKind::Album => {
+                self.run_album(job_id, &request, musicgen_service, user_id.as_deref())
+                    .await
+            }
+        };
+
+        let status = if outcome.is_ok() {
+            JobStatus::Done
+        } else {
+            JobStatus::Failed
+        };
+        MUSICGEN_JOB_DURATION
+            .with_label_values(&[&kind, status.as_str()])
+            .observe(started.elapsed().as_secs_f64());
+
+        match outcome {
+            Ok((file_ids, result)) => {
+                for file_id in &file_ids {
+                    self.audio_cache_service
+                        .record_owner(file_id, user_id.as_deref())
+                        .await?;
+                }
+
+                sqlx::query(
+                    "UPDATE ai_music_jobs SET status = ?, progress = 1, file_ids = ?, result = ?, error = NULL, updated_at = ? WHERE id = ?",
+                )
+                .bind(JobStatus::Done.as_str())
+                .bind(serde_json::to_string(&file_ids)?)
+                .bind(result.to_string())
+                .bind(Utc::now().to_rfc3339())
+                .bind(job_id)
+                .execute(&self.pool)
+                .await?;
+                tracing::info!(files = file_ids.len(), "Job finished");
+            }
+            Err(error) => {
+                sqlx::query(
+                    "UPDATE ai_music_jobs SET status = ?, error = ?, updated_at = ? WHERE id = ?",
+                )
+                .bind(JobStatus::Failed.as_str())
+                .bind(&error)
+                .bind(Utc::now().to_rfc3339())
+                .bind(job_id)
+                .execute(&self.pool)
+                .await?;
+                tracing::warn!(error = %error, "Job failed");
+            }
+        }
+
+        self.publish(job_id).await;
+        Ok(())
+    }
+
+    async fn run_single(
+        &self,
+        request: &str,
+        musicgen_service: &dyn MusicGenerator,
+        user_id: Option<&str>,
+    ) -> Result<(Vec<String>, serde_json::Value), String> {
+        let request: AiMusicRequest = serde_json::from_str(request).map_err(|e| e.to_string())?;
+
+        let mut response = musicgen_service
+            .generate_song(&request.prompt, request.duration, &request.controls)
+            .await
+            .map_err(|e| e.to_string())?;
+        response.file_path = AudioCacheService::public_path(&response.file_id);
+
+        if let Some(options) = &request.post_processing {
+            self.audio_cache_service
+                .post_process_song(musicgen_service, &mut response, options, user_id)
+                .await
+                .map_err(|e| format!("Post-processing failed: {}", e))?;
+        }
+
+        let parameters =
+            NewLibraryTrack::parameters(&request.controls, request.post_processing.as_ref());
+        self.library_service
+            .save(
+                user_id,
+                vec![NewLibraryTrack::from_song(
+                    &response,
+                    TrackSource::Text,
+                    parameters,
+                )],
+            )
+            .await;
+
+        let file_ids = std::iter::once(response.file_id.clone())
+            .chain(response.original_file_id.clone())
+            .collect();
+        let result = serde_json::to_value(&response).map_err(|e| e.to_string())?;
+        Ok((file_ids, result))
+    }
+
+    /// Generate batch items with bounded concurrency, reporting progress as each finishes.
+    /// When retrying, only the prompts that failed in `previous` are generated again.
+    async fn run_batch(
+        &self,
+        job_id: &str,
+        request: &str,
+        previous: Option<&str>,
+        musicgen_service: &dyn MusicGenerator,
+        user_id: Option<&str>,
+    ) -> Result<(Vec<String>, serde_json::Value), String> {
+        let request: AiMusicBatchRequest =
+            serde_json::from_str(request).map_err(|e| e.to_string())?;
+        let previous: Option<AiMusicBatchResponse> =
+            previous.and_then(|result| serde_json::from_str(result).ok());
+
+        let pending: Option<HashSet<usize>> = previous.as_ref().map(|previous| {
+            previous
+                .failures
+                .iter()
+                .map(|failure| failure.index)
+                .collect()
+        });
+        let total = pending
+            .as_ref()
+            .map_or(request.prompts.len(), HashSet::len)
+            .max(1) as f64;
+
+        let mut outcomes: Vec<Result<AiSong, AiBatchFailure>> = previous
+            .map(|previous| previous.songs.into_iter().map(Ok).collect())
+            .unwrap_or_default();
+        outcomes.extend(
+            self.generate_with_progress(
+                job_id,
+                &request,
+                pending.as_ref(),
+                total,
+                musicgen_service,
+            )
+            .await,
+        );
+
+        let mut response = AiMusicBatchResponse::from_outcomes(outcomes);
+
+        if !response.success {
+            let errors: Vec<String> = response
+                .failures
+                .iter()
+                .map(|failure| format!("'{}' failed: {}", failure.title, failure.error))
+                .collect();
+            return Err(errors.join("; "));
+        }
+
+        if let Some(options) = &request.post_processing {
+            self.audio_cache_service
+                .post_process_batch(musicgen_service, &mut response, options, user_id)
+                .await
+                .map_err(|e| format!("Post-processing failed: {}", e))?;
+        }
+
+        let parameters =
+            NewLibraryTrack::parameters(&request.controls, request.post_processing.as_ref());
+        self.library_service
+            .save(
+                user_id,
+                NewLibraryTrack::from_batch(
+                    &response,
+                    request.duration.unwrap_or(DEFAULT_AI_MUSIC_DURATION),
+                    TrackSource::Batch,
+                    parameters,
+                ),
+            )
+            .await;
+
+        let file_ids = response
+            .songs
+            .iter()
+            .flat_map(|song| {
+                std::iter::once(song.file_id.clone()).chain(song.original_file_id.clone())
+            })
+            .chain(response.mix.as_ref().map(|mix| mix.file_id.clone()))
+            .collect();
+        let result = serde_json::to_value(&response).map_err(|e| e.to_string())?;
+        Ok((file_ids, result))
+    }
+
+    /// Write the album concept with Gemini, then render its tracks as a batch.
+    /// Tracks that fail are reported in the result; the job only fails if none succeed.
+    async fn run_album(
+        &self,
+        job_id: &str,
+        request: &str,
+        musicgen_service: &dyn MusicGenerator,
+        user_id: Option<&str>,
+    ) -> Result<(Vec<String>, serde_json::Value), String> {
+        let request: AiAlbumRequest = serde_json::from_str(request).map_err(|e| e.to_string())?;
+        let track_count = request.track_count.unwrap_or(DEFAULT_ALBUM_TRACKS);
+
+        let concept = self
+            .gemini_service
+            .generate_album_concept(&request.prompt, track_count)
+            .await
+            .map_err(|e| format!("Album concept failed: {}", e))?;
+
+        let batch = AiMusicBatchRequest {
+            prompts: concept.tracks.clone(),
+            duration: request.duration,
+            controls: GenerationControls::default(),
+            post_processing: None,
+        };
+        let total = batch.prompts.len().max(1) as f64;
+        let outcomes = self
+            .generate_with_progress(job_id, &batch, None, total, musicgen_service)
+            .await;
+        let generated = AiMusicBatchResponse::from_outcomes(outcomes);
+
+        if !generated.success {
+            return Err("Every album track failed to generate".to_string());
+        }
+
+        let tracks: Vec<AiAlbumTrack> = generated
+            .songs
+            .into_iter()
+            .map(|song| AiAlbumTrack {
+                track_number: song.index + 1,
+                // Report the prompt Gemini wrote rather than MusicGen's enhanced version
+                prompt: concept.tracks[song.index].prompt.clone(),
+                title: song.title,
+                audio_url: song.file_path,
+                file_id: song.file_id,
+            })
+            .collect();
+
+        let duration = request.duration.unwrap_or(DEFAULT_AI_MUSIC_DURATION);
+        let library_tracks = tracks
+            .iter()
+            .map(|track| NewLibraryTrack {
+                file_id: track.file_id.clone(),
+                original_file_id: None,
+                title: Some(track.title.clone()),
+                prompt: track.prompt.clone(),
+                source: TrackSource::Album,
+                parameters: serde_json::json!({
+                    "album_title": concept.album_title,
+                    "track_number": track.track_number,
+                }),
+                duration_seconds: duration as f64,
+            })
+            .collect();
+        self.library_service.save(user_id, library_tracks).await;
+
+        tracing::info!(album = %concept.album_title, tracks = tracks.len(), "Generated AI album");
+
+        let file_ids = tracks.iter().map(|track| track.file_id.clone()).collect();
+        let response = AiAlbumResponse {
+            success: true,
+            title: concept.album_title,
+            description: concept.description,
+            cover_art_prompt: concept.cover_art_prompt,
+            tracks,
+            failures: generated.failures,
+        };
+        let result = serde_json::to_value(&response).map_err(|e| e.to_string())?;
+        Ok((file_ids, result))
+    }
+
+    /// Stream batch outcomes, recording progress against `total` as each item finishes
+    async fn generate_with_progress(
+        &self,
+        job_id: &str,
+        request: &AiMusicBatchRequest,
+        pending: Option<&HashSet<usize>>,
+        total: f64,
+        musicgen_service: &dyn MusicGenerator,
+    ) -> Vec<Result<AiSong, AiBatchFailure>> {
+        let mut outcomes = Vec::new();
+        let mut generated = Box::pin(batch_outcomes(musicgen_service, request, pending));
+
+        while let Some(outcome) = generated.next().await {
+            outcomes.push(outcome.map(|mut song| {
+                song.file_path = AudioCacheService::public_path(&song.file_id);
+                song
+            }));
+
+            let progress = outcomes.len() as f64 / total;
+            if let Err(e) = self.set_status(job_id, JobStatus::Running, progress).await {
+                tracing::warn!(error = %e, "Failed to record job progress");
+            }
+        }
+
+        outcomes
+    }
+
+    async fn set_status(
+        &self,
+        job_id: &str,
+        status: JobStatus,
+        progress: f64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE ai_music_jobs SET status = ?, progress = ?, updated_at = ? WHERE id = ?",
+        )
+        .bind(status.as_str())
+        .bind(progress)
+        .bind(Utc::now().to_rfc3339())
+        .bind(job_id)
+        .execute(&self.pool)
+        .await?;
+
+        self.publish(job_id).await;
+        Ok(())
+    }
+
+    async fn publish(&self, job_id: &str) {
+        if let Ok(Some(job)) = self.get(job_id).await {
+            // Nobody listening is fine
+            let _ = self.events.send(job);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use crate::db;
+use crate::models::library::{
+    LibraryQuery, LibraryTrack, LibraryUpdate, NewLibraryTrack, TrackSource,
+};
+use crate::services::audio_cache_service::AudioCacheService;
+use crate::services::music_generator::MusicGenerator;
+use chrono::Utc;
+use sqlx::SqlitePool;
+use std::error::Error;
+
+#[derive(Debug, sqlx::FromRow)]
+struct LibraryRow {
+    file_id: String,
+    original_file_id: Option<String>,
+    title: Option<String>,
+    prompt: String,
+    source: String,
+    parameters: String,
+    duration_seconds: f64,
+    favorite: bool,
+    created_at: String,
+}
+
+impl From<LibraryRow> for LibraryTrack {
+    fn from(row: LibraryRow) -> Self {
+        LibraryTrack {
+            audio_url: AudioCacheService::public_path(&row.file_id),
+            file_id: row.file_id,
+            original_file_id: row.original_file_id,
+            title: row.title,
+            prompt: row.prompt,
+            source: TrackSource::parse(&row.source),
+            parameters: serde_json::from_str(&row.parameters).unwrap_or_default(),
+            duration_seconds: row.duration_seconds,
+            favorite: row.favorite,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// Every song a signed-in user generates, so they can find it again later
+#[derive(Debug, Clone)]
+pub struct LibraryService {
+    pool: SqlitePool,
+    audio_cache_service: AudioCacheService,
+}
+
+impl LibraryService {
+    pub fn new(pool: SqlitePool, audio_cache_service: AudioCacheService) -> Self {
+        Self {
+            pool,
+            audio_cache_service,
+        }
+    }
+
+    /// Save generated tracks for a signed-in user. Anonymous generations are not kept,
+    /// and failures are only logged because the songs themselves were already created.
+    pub async fn save(&self, user_id: Option<&str>, tracks: Vec<NewLibraryTrack>) {
+        let Some(user_id) = user_id else {
+            return;
+        };
+
+        for track in tracks {
+            if let Err(e) = self.insert(user_id, &track).await {
+                tracing::warn!(file_id = %track.file_id, error = %e, "Failed to save track to library");
+            }
+        }
+    }
+
+    async fn insert(&self, user_id: &str, track: &NewLibraryTrack) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO ai_music_library
+                (file_id, user_id, original_file_id, title, prompt, source, parameters, duration_seconds, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&track.file_id)
+        .bind(user_id)
+        .bind(&track.original_file_id)
+        .bind(&track.title)
+        .bind(&track.prompt)
+        .bind(track.source.as_str())
+        .bind(track.parameters.to_string())
+        .bind(track.duration_seconds)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Newest first, filtered by search term and favorite flag. Returns the total match count too.
+    pub async fn list(
+        &self,
+        user_id: &str,
+        query: &LibraryQuery,
+    ) -> Result<(i64, Vec<LibraryTrack>), sqlx::Error> {
+        let pattern = query
+            .q
+            .as_deref()
+            .map(str::trim)
+            .filter(|term| !term.is_empty())
+            .map(db::like_pattern);
+        let limit = db::page_limit(query.limit);
+        let offset = query.offset.unwrap_or(0);
+
+        let filter = r#"
+            WHERE user_id = ?
+              AND (? IS NULL OR prompt LIKE ? ESCAPE '\' OR title LIKE ? ESCAPE '\')
+              AND (? IS NULL OR favorite = ?)
+        "#;
+
+        let (total,): (i64,) =
+            sqlx::query_as(&format!("SELECT COUNT(*) FROM ai_music_library {}", filter))
+                .bind(user_id)
+                .bind(&pattern)
+                .bind(&pattern)
+                .bind(&pattern)
+                .bind(query.favorite)
+                .bind(query.favorite)
+                .fetch_one(&self.pool)
+                .await?;
+
+        let rows: Vec<LibraryRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT file_id, original_file_id, title, prompt, source, parameters, duration_seconds, favorite, created_at
+            FROM ai_music_library {}
+            ORDER BY created_at DESC
+            LIMIT ? OFFSET ?
+            "#,
+            filter
+        ))
+        .bind(user_id)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(query.favorite)
+        .bind(query.favorite)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok((total, rows.into_iter().map(LibraryTrack::from).collect()))
+    }
+
+    pub async fn get(
+        &self,
+        user_id: &str,
+        file_id: &str,
+    ) -> Result<Option<LibraryTrack>, sqlx::Error> {
+        let row: Option<LibraryRow> = sqlx::query_as(
+            r#"
+            SELECT file_id, original_file_id, title, prompt, source, parameters, duration_seconds, favorite, created_at
+            FROM ai_music_library WHERE user_id = ? AND file_id = ?
+            "#,
+        )
+        .bind(user_id)
+        .bind(file_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(LibraryTrack::from))
+    }
+
+    /// Change the favorite flag and/or title; `None` if the track isn't in the user's library
+    pub async fn update(
+        &self,
+        user_id: &str,
+        file_id: &str,
+        update: &LibraryUpdate,
+    ) -> Result<Option<LibraryTrack>, sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE ai_music_library
+            SET favorite = COALESCE(?, favorite), title = COALESCE(?, title)
+            WHERE user_id = ? AND file_id = ?
+            "#,
+        )
+        .bind(update.favorite)
+        .bind(&update.title)
+        .bind(user_id)
+        .bind(file_id)
+        .execute(&self.pool)
+        .await?;
+
+        self.get(user_id, file_id).await
+    }
+
+    /// Remove a track from the library and delete its audio, including the
+    /// unprocessed original. Returns false if the user has no such track.
+    pub async fn delete(
+        &self,
+        user_id: &str,
+        file_id: &str,
+        musicgen_service: &dyn MusicGenerator,
+    ) -> Result<bool, Box<dyn Error>> {
+        let Some(track) = self.get(user_id, file_id).await? else {
+            return Ok(false);
+        };
+
+        sqlx::query("DELETE FROM ai_music_library WHERE user_id = ? AND file_id = ?")
+            .bind(user_id)
+            .bind(file_id)
+            .execute(&self.pool)
+            .await?;
+
+        for id in std::iter::once(track.file_id).chain(track.original_file_id) {
+            self.audio_cache_service
+                .remove_file(&id, musicgen_service)
+                .await?;
+        }
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,292 @@
+use crate::audio::AudioClip;
+use crate::models::health::BackendHealth;
+use crate::models::playlist::{AiMusicResponse, GenerationControls, ImageAnalysisResponse};
+use crate::services::music_generator::MusicGenerator;
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::f32::consts::PI;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+/// Same output rate as MusicGen so downstream code sees realistic files
+const SAMPLE_RATE: u32 = 32_000;
+const DEFAULT_DURATION: u32 = 10;
+const MAX_DURATION: u32 = 30;
+
+/// Rendering is cheap, so batches can fan out freely
+const MAX_CONCURRENCY: usize = 4;
+
+/// Oldest clips are forgotten once this many are remembered
+const MAX_STORED_SONGS: usize = 1_000;
+
+/// Prompts containing this fail, so error handling can be exercised without a real backend
+pub const FAILURE_MARKER: &str = "[mock-fail]";
+
+/// Semitone offsets of a major pentatonic scale
+const PENTATONIC: [i32; 5] = [0, 2, 4, 7, 9];
+
+/// What is needed to re-render a generated clip on download
+#[derive(Debug, Clone)]
+struct MockSong {
+    prompt: String,
+    duration: u32,
+    controls: GenerationControls,
+    /// Input audio a continuation starts with, already mono at SAMPLE_RATE
+    prefix: Vec<i16>,
+}
+
+/// Generated clips by file ID, plus their insertion order for eviction
+#[derive(Debug, Default)]
+struct SongStore {
+    songs: HashMap<String, MockSong>,
+    order: VecDeque<String>,
+}
+
+impl SongStore {
+    fn insert(&mut self, file_id: String, song: MockSong) {
+        self.order.push_back(file_id.clone());
+        self.songs.insert(file_id, song);
+
+        while self.songs.len() > MAX_STORED_SONGS {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.songs.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn remove(&mut self, file_id: &str) {
+        if self.songs.remove(file_id).is_some() {
+            self.order.retain(|id| id != file_id);
+        }
+    }
+}
+
+/// In-process MusicGen stand-in that renders simple tone melodies.
+/// The same prompt, duration and controls always produce the same audio.
+#[derive(Debug, Clone, Default)]
+pub struct MockMusicGenService {
+    songs: Arc<Mutex<SongStore>>,
+}
+
+impl MockMusicGenService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Synthesize a prompt's melody as mono 16-bit samples.
+    /// An explicit seed replaces the prompt hash and an explicit bpm sets the tempo.
+    fn render_samples(
+        prompt: &str,
+        total_samples: usize,
+        controls: &GenerationControls,
+    ) -> Vec<i16> {
+        let seed = match controls.seed {
+            Some(seed) => fnv1a(&seed.to_le_bytes()),
+            None => fnv1a(prompt.as_bytes()),
+        };
+
+        // Derive the key, tempo and melody pattern from the seed
+        let root_midi = 48 + (seed % 12) as i32;
+        let beats_per_minute = controls.bpm.map(u64::from).unwrap_or(80 + (seed >> 8) % 60);
+        let samples_per_note = (SAMPLE_RATE as u64 * 60 / beats_per_minute) as usize;
+        let pattern: Vec<i32> = (0..8)
+            .map(|step| {
+                let degree = ((seed >> (16 + step * 3)) % PENTATONIC.len() as u64) as usize;
+                let octave = ((seed >> (40 + step)) & 1) as i32 * 12;
+                root_midi + PENTATONIC[degree] + octave
+            })
+            .collect();
+
+        let attack = (SAMPLE_RATE / 100) as usize;
+        let mut samples = Vec::with_capacity(total_samples);
+
+        for n in 0..total_samples {
+            let note = pattern[(n / samples_per_note) % pattern.len()];
+            let position = n % samples_per_note;
+            let frequency = 440.0 * 2f32.powf((note - 69) as f32 / 12.0);
+            let t = n as f32 / SAMPLE_RATE as f32;
+
+            let envelope = if position < attack {
+                position as f32 / attack as f32
+            } else {
+                (-3.0 * (position - attack) as f32 / samples_per_note as f32).exp()
+            };
+
+            let value = 0.3 * envelope * (2.0 * PI * frequency * t).sin();
+            samples.push((value * i16::MAX as f32) as i16);
+        }
+
+        samples
+    }
+
+    fn remember(&self, prompt: &str, duration: u32, controls: &GenerationControls) -> String {
+        self.store(MockSong {
+            prompt: prompt.to_string(),
+            duration,
+            controls: controls.clone(),
+            prefix: Vec::new(),
+        })
+    }
+
+    fn store(&self, song: MockSong) -> String {
+        let file_id = uuid::Uuid::new_v4().to_string();
+        self.songs.lock().unwrap().insert(file_id.clone(), song);
+        file_id
+    }
+
+    fn response(file_id: String, prompt: &str, duration: u32) -> AiMusicResponse {
+        AiMusicResponse {
+            success: true,
+            file_path: format!("/download/{}", file_id),
+            file_id,
+            duration,
+            sample_rate: SAMPLE_RATE,
+            prompt: prompt.to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+            original_file_id: None,
+        }
+    }
+}
+
+#[async_trait]
+impl MusicGenerator for MockMusicGenService {
+    async fn health_check(&self) -> Result<BackendHealth, Box<dyn Error>> {
+        Ok(BackendHealth {
+            healthy: true,
+            device: Some("cpu".to_string()),
+            models: vec!["mock-tone-synth".to_string()],
+        })
+    }
+
+    async fn generate_song(
+        &self,
+        prompt: &str,
+        duration: Option<u32>,
+        controls: &GenerationControls,
+    ) -> Result<AiMusicResponse, Box<dyn Error>> {
+        if prompt.contains(FAILURE_MARKER) {
+            return Err("Mock generation failed on request".into());
+        }
+
+        let duration = duration.unwrap_or(DEFAULT_DURATION).min(MAX_DURATION);
+        let file_id = self.remember(prompt, duration, controls);
+
+        Ok(Self::response(file_id, prompt, duration))
+    }
+
+    /// The melody only perturbs the seed; the mock has no pitch tracking
+    async fn generate_with_melody(
+        &self,
+        prompt: &str,
+        melody_wav: &[u8],
+        duration: Option<u32>,
+        controls: &GenerationControls,
+    ) -> Result<AiMusicResponse, Box<dyn Error>> {
+        let duration = duration.unwrap_or(DEFAULT_DURATION).min(MAX_DURATION);
+        let seeded_prompt = format!("{} {:x}", prompt, fnv1a(melody_wav));
+        let file_id = self.remember(&seeded_prompt, duration, controls);
+
+        Ok(Self::response(file_id, prompt, duration))
+    }
+
+    async fn continue_song(
+        &self,
+        prompt: Option<&str>,
+        audio_wav: &[u8],
+        duration: u32,
+        controls: &GenerationControls,
+    ) -> Result<AiMusicResponse, Box<dyn Error>> {
+        let clip = AudioClip::from_wav(audio_wav).map_err(|e| e.to_string())?;
+        if clip.channels != 1 || clip.sample_rate != SAMPLE_RATE {
+            return Err("Continuation audio must be mono at 32 kHz".into());
+        }
+
+        let prompt = prompt.unwrap_or_default();
+        let duration = duration.min(MAX_DURATION);
+        let file_id = self.store(MockSong {
+            prompt: prompt.to_string(),
+            duration,
+            controls: controls.clone(),
+            prefix: clip
+                .samples
+                .iter()
+                .map(|sample| (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+                .collect(),
+        });
+
+        Ok(Self::response(file_id, prompt, duration))
+    }
+
+    fn max_concurrency(&self) -> usize {
+        MAX_CONCURRENCY
+    }
+
+    async fn analyze_image(
+        &self,
+        image_base64: &str,
+    ) -> Result<ImageAnalysisResponse, Box<dyn Error>> {
+        let caption = format!("an image ({} bytes of base64 data)", image_base64.len());
+
+        Ok(ImageAnalysisResponse {
+            success: true,
+            suggested_prompt: format!("Music that captures the mood of: {}", caption),
+            caption,
+        })
+    }
+
+    async fn delete_song(&self, file_id: &str) -> Result<(), Box<dyn Error>> {
+        self.songs.lock().unwrap().remove(file_id);
+        Ok(())
+    }
+
+    async fn download_song(&self, file_id: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let song = self.songs.lock().unwrap().songs.get(file_id).cloned();
+
+        match song {
+            Some(song) => {
+                let total_samples = (SAMPLE_RATE * song.duration) as usize;
+                let remaining = total_samples.saturating_sub(song.prefix.len());
+
+                let mut samples = song.prefix;
+                samples.extend(Self::render_samples(
+                    &song.prompt,
+                    remaining,
+                    &song.controls,
+                ));
+                Ok(encode_wav(&samples, SAMPLE_RATE)?)
+            }
+            None => Err(format!("Mock song {} not found", file_id).into()),
+        }
+    }
+}
+
+/// 64-bit FNV-1a, stable across platforms and Rust versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Wrap 16-bit mono samples in a RIFF/WAVE container
+fn encode_wav(samples: &[i16], sample_rate: u32) -> Result<Vec<u8>, hound::Error> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut bytes = Vec::with_capacity(44 + samples.len() * 2);
+    let mut writer = hound::WavWriter::new(Cursor::new(&mut bytes), spec)?;
+    for sample in samples {
+        writer.write_sample(*sample)?;
+    }
+    writer.finalize()?;
+
+    Ok(bytes)
+}
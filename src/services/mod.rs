@@ -1,4 +1,16 @@
+pub mod api_key_service;
+pub mod audio_cache_service;
+pub mod cover_service;
 pub mod gemini_service;
+pub mod health_service;
+pub mod job_service;
+pub mod library_service;
+pub mod mock_musicgen_service;
+pub mod music_generator;
 pub mod musicgen_service;
+pub mod playlist_history_service;
 pub mod qr_service;
+pub mod quota_service;
+pub mod spotify_playlist_service;
 pub mod statistics_service;
+pub mod user_service;
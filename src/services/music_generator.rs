@@ -0,0 +1,138 @@
+use crate::models::health::BackendHealth;
+use crate::models::playlist::{
+    AiBatchFailure, AiMusicBatchRequest, AiMusicBatchResponse, AiMusicResponse, AiSong,
+    GenerationControls, ImageAnalysisResponse,
+};
+use crate::services::mock_musicgen_service::MockMusicGenService;
+use crate::services::musicgen_service::MusicGenService;
+use async_trait::async_trait;
+use futures::stream::{self, Stream, StreamExt};
+use std::collections::HashSet;
+use std::error::Error;
+use std::sync::Arc;
+
+/// A backend that can turn prompts into audio.
+///
+/// `MusicGenService` talks to the Python MusicGen service over HTTP, while
+/// `MockMusicGenService` synthesizes deterministic tones in-process so the
+/// server can run without a GPU model.
+#[async_trait]
+pub trait MusicGenerator: Send + Sync {
+    /// Report whether the backend is ready to generate and which models it has loaded
+    async fn health_check(&self) -> Result<BackendHealth, Box<dyn Error>>;
+
+    /// Generate a single song from a text prompt, honoring whichever controls the backend supports
+    async fn generate_song(
+        &self,
+        prompt: &str,
+        duration: Option<u32>,
+        controls: &GenerationControls,
+    ) -> Result<AiMusicResponse, Box<dyn Error>>;
+
+    /// Generate a song from a text prompt that follows the melody of a mono 32 kHz WAV clip
+    async fn generate_with_melody(
+        &self,
+        prompt: &str,
+        melody_wav: &[u8],
+        duration: Option<u32>,
+        controls: &GenerationControls,
+    ) -> Result<AiMusicResponse, Box<dyn Error>>;
+
+    /// Extend a mono 32 kHz WAV clip to `duration` seconds, optionally steered by a prompt
+    async fn continue_song(
+        &self,
+        prompt: Option<&str>,
+        audio_wav: &[u8],
+        duration: u32,
+        controls: &GenerationControls,
+    ) -> Result<AiMusicResponse, Box<dyn Error>>;
+
+    /// How many songs the backend can usefully generate at once
+    fn max_concurrency(&self) -> usize {
+        1
+    }
+
+    /// Caption an image and suggest a music prompt for it
+    async fn analyze_image(
+        &self,
+        image_base64: &str,
+    ) -> Result<ImageAnalysisResponse, Box<dyn Error>>;
+
+    /// Fetch the WAV bytes of a generated song
+    async fn download_song(&self, file_id: &str) -> Result<Vec<u8>, Box<dyn Error>>;
+
+    /// Delete a generated song from the backend's storage
+    async fn delete_song(&self, file_id: &str) -> Result<(), Box<dyn Error>>;
+}
+
+/// Pick the backend from MUSICGEN_BACKEND ("http" or "mock"), MUSICGEN_API_URL and MUSICGEN_MAX_CONCURRENCY
+pub fn from_env() -> Arc<dyn MusicGenerator> {
+    match std::env::var("MUSICGEN_BACKEND").as_deref() {
+        Ok("mock") => {
+            tracing::info!("Using in-process mock MusicGen backend");
+            Arc::new(MockMusicGenService::new())
+        }
+        _ => {
+            let service = match std::env::var("MUSICGEN_API_URL") {
+                Ok(api_url) => MusicGenService::with_url(api_url),
+                Err(_) => MusicGenService::new(),
+            };
+            let max_concurrency = std::env::var("MUSICGEN_MAX_CONCURRENCY")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(1);
+            Arc::new(service.with_max_concurrency(max_concurrency))
+        }
+    }
+}
+
+/// Generate each prompt of a batch, running up to `max_concurrency()` songs at once.
+/// Outcomes come out in prompt order; `only` restricts generation to those prompt indices.
+pub fn batch_outcomes<'a>(
+    generator: &'a dyn MusicGenerator,
+    request: &'a AiMusicBatchRequest,
+    only: Option<&'a HashSet<usize>>,
+) -> impl Stream<Item = Result<AiSong, AiBatchFailure>> + Send + 'a {
+    let items = request
+        .prompts
+        .iter()
+        .enumerate()
+        .filter(move |(index, _)| only.is_none_or(|indices| indices.contains(index)));
+
+    stream::iter(items)
+        .map(move |(index, item)| async move {
+            // Offset a fixed seed per prompt so the songs differ but stay reproducible
+            let mut controls = request.controls.clone();
+            controls.seed = controls.seed.map(|seed| seed.wrapping_add(index as u64));
+
+            match generator
+                .generate_song(&item.prompt, request.duration, &controls)
+                .await
+            {
+                Ok(song) => Ok(AiSong {
+                    index,
+                    title: item.title.clone(),
+                    file_id: song.file_id,
+                    file_path: song.file_path,
+                    prompt: song.prompt,
+                    original_file_id: None,
+                }),
+                Err(e) => Err(AiBatchFailure {
+                    index,
+                    title: item.title.clone(),
+                    prompt: item.prompt.clone(),
+                    error: e.to_string(),
+                }),
+            }
+        })
+        .buffered(generator.max_concurrency().max(1))
+}
+
+/// Generate a whole batch; one failing prompt is reported without losing the others
+pub async fn generate_batch(
+    generator: &dyn MusicGenerator,
+    request: &AiMusicBatchRequest,
+) -> AiMusicBatchResponse {
+    let outcomes = batch_outcomes(generator, request, None).collect().await;
+    AiMusicBatchResponse::from_outcomes(outcomes)
+}
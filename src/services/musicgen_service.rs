@@ -1,14 +1,21 @@
+use crate::models::health::BackendHealth;
 use crate::models::playlist::{
-    AiMusicBatchRequest, AiMusicBatchResponse, AiMusicRequest, AiMusicResponse,
+    AiMusicResponse, GenerationControls, ImageAnalysisResponse, DEFAULT_AI_MUSIC_DURATION,
 };
+use crate::services::music_generator::MusicGenerator;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
 use reqwest::Client;
 use serde_json::json;
 use std::error::Error;
+use std::time::Instant;
 
 #[derive(Debug)]
 pub struct MusicGenService {
     api_url: String,
     client: Client,
+    max_concurrency: usize,
 }
 
 impl Clone for MusicGenService {
@@ -16,10 +23,17 @@ impl Clone for MusicGenService {
         Self {
             api_url: self.api_url.clone(),
             client: Client::new(),
+            max_concurrency: self.max_concurrency,
         }
     }
 }
 
+impl Default for MusicGenService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MusicGenService {
     /// Create a new MusicGenService instance
     /// Default API URL is http://localhost:5000
@@ -27,6 +41,7 @@ impl MusicGenService {
         Self {
             api_url: "http://localhost:5000".to_string(),
             client: Client::new(),
+            max_concurrency: 1,
         }
     }
 
@@ -35,63 +50,44 @@ impl MusicGenService {
         Self {
             api_url,
             client: Client::new(),
+            max_concurrency: 1,
         }
     }
 
-    /// Check if the AI music service is healthy and running
-    pub async fn health_check(&self) -> Result<bool, Box<dyn Error>> {
-        let url = format!("{}/health", self.api_url);
+    /// Allow batch items to be sent in parallel, for services that run several models or workers
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
 
-        match self.client.get(&url).send().await {
-            Ok(response) => Ok(response.status().is_success()),
-            Err(e) => {
-                eprintln!("Health check failed: {}", e);
-                Err(format!(
-                    "AI Music service is not running. Please start the Python service at {}",
-                    self.api_url
-                )
-                .into())
-            }
-        }
+    /// Get the download URL for a generated song
+    pub fn get_download_url(&self, file_id: &str) -> String {
+        format!("{}/download/{}", self.api_url, file_id)
     }
 
-    /// Generate a single AI song from a text prompt
-    pub async fn generate_song(
+    /// POST a generation request that returns a single song
+    #[tracing::instrument(name = "musicgen.generate", skip(self, request_body))]
+    async fn post_generation(
         &self,
-        prompt: &str,
-        duration: Option<u32>,
+        endpoint: &str,
+        request_body: &serde_json::Value,
     ) -> Result<AiMusicResponse, Box<dyn Error>> {
-        println!("Generating AI music with prompt: {}", prompt);
-
-        // First check if service is running
-        if !self.health_check().await? {
-            return Err("AI Music service is not available".into());
-        }
-
-        let url = format!("{}/generate", self.api_url);
-
-        let request_body = json!({
-            "prompt": prompt,
-            "duration": duration.unwrap_or(10),
-            "return_url": true
-        });
-
-        println!("Making request to MusicGen API: {}", url);
+        let url = format!("{}/{}", self.api_url, endpoint);
+        let started = Instant::now();
 
         let response = self
             .client
             .post(&url)
             .header("Content-Type", "application/json")
-            .json(&request_body)
+            .json(request_body)
             .send()
             .await?;
 
         let status = response.status();
-        println!("MusicGen API response status: {}", status);
 
         if !status.is_success() {
             let error_text = response.text().await?;
-            eprintln!("MusicGen API error response: {}", error_text);
+            tracing::warn!(status = status.as_u16(), body = %error_text, "MusicGen API error");
             return Err(format!("MusicGen API error ({}): {}", status, error_text).into());
         }
 
@@ -101,68 +97,164 @@ impl MusicGenService {
             return Err("Failed to generate AI music".into());
         }
 
-        println!(
-            "Successfully generated AI music: {}",
-            response_json.file_id
+        tracing::info!(
+            file_id = %response_json.file_id,
+            latency_ms = started.elapsed().as_millis() as u64,
+            "Generated AI music"
         );
 
         Ok(response_json)
     }
+}
 
-    /// Generate multiple AI songs from a list of prompts
-    pub async fn generate_batch(
-        &self,
-        request: AiMusicBatchRequest,
-    ) -> Result<AiMusicBatchResponse, Box<dyn Error>> {
-        println!("Generating batch of {} AI songs", request.prompts.len());
+#[async_trait]
+impl MusicGenerator for MusicGenService {
+    /// Check if the AI music service is healthy and running
+    async fn health_check(&self) -> Result<BackendHealth, Box<dyn Error>> {
+        let url = format!("{}/health", self.api_url);
 
-        // First check if service is running
-        if !self.health_check().await? {
-            return Err("AI Music service is not available".into());
+        match self.client.get(&url).send().await {
+            Ok(response) if response.status().is_success() => {
+                let body: serde_json::Value = response.json().await?;
+                Ok(BackendHealth {
+                    healthy: body["model_loaded"].as_bool().unwrap_or(false),
+                    device: body["device"].as_str().map(str::to_string),
+                    models: serde_json::from_value(body["models"].clone()).unwrap_or_default(),
+                })
+            }
+            Ok(response) => Err(format!("AI Music service returned {}", response.status()).into()),
+            Err(e) => {
+                tracing::warn!(error = %e, "MusicGen health check failed");
+                Err(format!(
+                    "AI Music service is not running. Please start the Python service at {}",
+                    self.api_url
+                )
+                .into())
+            }
         }
+    }
 
-        let url = format!("{}/batch-generate", self.api_url);
+    /// Generate a single AI song from a text prompt
+    async fn generate_song(
+        &self,
+        prompt: &str,
+        duration: Option<u32>,
+        controls: &GenerationControls,
+    ) -> Result<AiMusicResponse, Box<dyn Error>> {
+        tracing::debug!(prompt, "Generating AI music");
 
-        let request_body = json!({
-            "prompts": request.prompts,
-            "duration": request.duration.unwrap_or(10)
-        });
+        let mut request_body = serde_json::to_value(controls)?;
+        request_body["prompt"] = json!(prompt);
+        request_body["duration"] = json!(duration.unwrap_or(DEFAULT_AI_MUSIC_DURATION));
 
-        println!("Making batch request to MusicGen API: {}", url);
+        self.post_generation("generate", &request_body).await
+    }
+
+    /// Generate an AI song that follows the melody of an input clip
+    async fn generate_with_melody(
+        &self,
+        prompt: &str,
+        melody_wav: &[u8],
+        duration: Option<u32>,
+        controls: &GenerationControls,
+    ) -> Result<AiMusicResponse, Box<dyn Error>> {
+        tracing::debug!(prompt, "Generating melody-conditioned AI music");
+
+        let mut request_body = serde_json::to_value(controls)?;
+        request_body["prompt"] = json!(prompt);
+        request_body["melody"] = json!(STANDARD.encode(melody_wav));
+        request_body["duration"] = json!(duration.unwrap_or(DEFAULT_AI_MUSIC_DURATION));
+
+        self.post_generation("generate-melody", &request_body).await
+    }
+
+    /// Continue an existing clip up to the requested total duration
+    async fn continue_song(
+        &self,
+        prompt: Option<&str>,
+        audio_wav: &[u8],
+        duration: u32,
+        controls: &GenerationControls,
+    ) -> Result<AiMusicResponse, Box<dyn Error>> {
+        tracing::debug!(prompt, duration, "Continuing AI music");
+
+        let mut request_body = serde_json::to_value(controls)?;
+        request_body["prompt"] = json!(prompt);
+        request_body["audio"] = json!(STANDARD.encode(audio_wav));
+        request_body["duration"] = json!(duration);
+
+        self.post_generation("continue", &request_body).await
+    }
+
+    fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
+
+    /// Caption an image with the BLIP model and get a music prompt for it
+    #[tracing::instrument(name = "musicgen.analyze_image", skip_all)]
+    async fn analyze_image(
+        &self,
+        image_base64: &str,
+    ) -> Result<ImageAnalysisResponse, Box<dyn Error>> {
+        let url = format!("{}/analyze-image", self.api_url);
 
         let response = self
             .client
             .post(&url)
             .header("Content-Type", "application/json")
-            .json(&request_body)
+            .json(&json!({ "image": image_base64 }))
             .send()
             .await?;
 
         let status = response.status();
-        println!("MusicGen API batch response status: {}", status);
 
         if !status.is_success() {
             let error_text = response.text().await?;
-            eprintln!("MusicGen API batch error response: {}", error_text);
+            tracing::warn!(status = status.as_u16(), body = %error_text, "MusicGen image analysis error");
             return Err(format!("MusicGen API error ({}): {}", status, error_text).into());
         }
 
-        let response_json: AiMusicBatchResponse = response.json().await?;
+        let response_json: ImageAnalysisResponse = response.json().await?;
 
         if !response_json.success {
-            return Err("Failed to generate AI music batch".into());
+            return Err("Failed to analyze image".into());
         }
 
-        println!(
-            "Successfully generated {} AI songs",
-            response_json.songs.len()
-        );
+        tracing::debug!(caption = %response_json.caption, "Captioned image");
 
         Ok(response_json)
     }
 
-    /// Get the download URL for a generated song
-    pub fn get_download_url(&self, file_id: &str) -> String {
-        format!("{}/download/{}", self.api_url, file_id)
+    /// Download the audio bytes of a generated song from the Python service
+    #[tracing::instrument(name = "musicgen.download", skip(self))]
+    async fn download_song(&self, file_id: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let url = self.get_download_url(file_id);
+
+        let response = self.client.get(&url).send().await?;
+        let status = response.status();
+
+        if !status.is_success() {
+            let error_text = response.text().await?;
+            return Err(format!("MusicGen download error ({}): {}", status, error_text).into());
+        }
+
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Delete a generated song from the Python service's output directory
+    #[tracing::instrument(name = "musicgen.delete", skip(self))]
+    async fn delete_song(&self, file_id: &str) -> Result<(), Box<dyn Error>> {
+        let url = format!("{}/files/{}", self.api_url, file_id);
+
+        let response = self.client.delete(&url).send().await?;
+        let status = response.status();
+
+        // Already gone is as good as deleted
+        if !status.is_success() && status != reqwest::StatusCode::NOT_FOUND {
+            let error_text = response.text().await?;
+            return Err(format!("MusicGen delete error ({}): {}", status, error_text).into());
+        }
+
+        Ok(())
     }
 }
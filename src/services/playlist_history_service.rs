@@ -0,0 +1,199 @@
+use crate::db;
+use crate::models::playlist::GeminiPromptResponse;
+use crate::models::playlist_history::{PlaylistGeneration, PlaylistHistoryQuery};
+use crate::services::gemini_service::GEMINI_MODEL;
+use chrono::Utc;
+use sqlx::SqlitePool;
+use std::error::Error;
+
+#[derive(Debug, sqlx::FromRow)]
+struct GenerationRow {
+    id: String,
+    prompt: String,
+    parameters: String,
+    model: String,
+    tracks: String,
+    playlist_name: String,
+    playlist_description: String,
+    latency_ms: i64,
+    source_generation_id: Option<String>,
+    created_at: String,
+}
+
+impl From<GenerationRow> for PlaylistGeneration {
+    fn from(row: GenerationRow) -> Self {
+        PlaylistGeneration {
+            id: row.id,
+            prompt: row.prompt,
+            parameters: serde_json::from_str(&row.parameters).unwrap_or_default(),
+            model: row.model,
+            tracks: serde_json::from_str(&row.tracks).unwrap_or_default(),
+            playlist_name: row.playlist_name,
+            playlist_description: row.playlist_description,
+            latency_ms: row.latency_ms,
+            source_generation_id: row.source_generation_id,
+            created_at: row.created_at,
+        }
+    }
+}
+
+const GENERATION_COLUMNS: &str = "id, prompt, parameters, model, tracks, playlist_name, \
+    playlist_description, latency_ms, source_generation_id, created_at";
+
+/// Every playlist a signed-in user had Gemini suggest, so it can be revisited or re-run
+#[derive(Debug, Clone)]
+pub struct PlaylistHistoryService {
+    pool: SqlitePool,
+}
+
+impl PlaylistHistoryService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Store a suggestion Gemini just made for `user_id`. `parameters` comes from
+    /// `PlaylistGeneration::parameters` and is what a re-run replays.
+    pub async fn record(
+        &self,
+        user_id: &str,
+        prompt: &str,
+        parameters: serde_json::Value,
+        playlist: &GeminiPromptResponse,
+        latency_ms: i64,
+        source_generation_id: Option<&str>,
+    ) -> Result<PlaylistGeneration, Box<dyn Error>> {
+        let row: GenerationRow = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO playlist_generations
+                (id, user_id, prompt, parameters, model, tracks, playlist_name, playlist_description, latency_ms, source_generation_id, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            RETURNING {}
+            "#,
+            GENERATION_COLUMNS
+        ))
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(user_id)
+        .bind(prompt)
+        .bind(parameters.to_string())
+        .bind(GEMINI_MODEL)
+        .bind(serde_json::to_string(&playlist.tracks)?)
+        .bind(&playlist.playlist_name)
+        .bind(&playlist.playlist_description)
+        .bind(latency_ms)
+        .bind(source_generation_id)
+        .bind(Utc::now().to_rfc3339())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.into())
+    }
+
+    /// Newest first, optionally filtered by a search term. Returns the total match count too.
+    pub async fn list(
+        &self,
+        user_id: &str,
+        query: &PlaylistHistoryQuery,
+    ) -> Result<(i64, Vec<PlaylistGeneration>), sqlx::Error> {
+        let pattern = query
+            .q
+            .as_deref()
+            .map(str::trim)
+            .filter(|term| !term.is_empty())
+            .map(db::like_pattern);
+        let limit = db::page_limit(query.limit);
+        let offset = query.offset.unwrap_or(0);
+
+        let filter = r#"
+            WHERE user_id = ?
+              AND (? IS NULL OR prompt LIKE ? ESCAPE '\' OR playlist_name LIKE ? ESCAPE '\')
+        "#;
+
+        let (total,): (i64,) = sqlx::query_as(&format!(
+            "SELECT COUNT(*) FROM playlist_generations {}",
+            filter
+        ))
+        .bind(user_id)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(&pattern)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let rows: Vec<GenerationRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT {} FROM playlist_generations {}
+            ORDER BY created_at DESC
+            LIMIT ? OFFSET ?
+            "#,
+            GENERATION_COLUMNS, filter
+        ))
+        .bind(user_id)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok((
+            total,
+            rows.into_iter().map(PlaylistGeneration::from).collect(),
+        ))
+    }
+
+    pub async fn get(
+        &self,
+        user_id: &str,
+        id: &str,
+    ) -> Result<Option<PlaylistGeneration>, sqlx::Error> {
+        let row: Option<GenerationRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM playlist_generations WHERE user_id = ? AND id = ?",
+            GENERATION_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(PlaylistGeneration::from))
+    }
+
+    /// Copy an entry under a new ID without asking Gemini again.
+    /// `None` if the user has no such entry.
+    pub async fn duplicate(
+        &self,
+        user_id: &str,
+        id: &str,
+    ) -> Result<Option<PlaylistGeneration>, sqlx::Error> {
+        let row: Option<GenerationRow> = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO playlist_generations
+                (id, user_id, prompt, parameters, model, tracks, playlist_name, playlist_description, latency_ms, source_generation_id, created_at)
+            SELECT ?, user_id, prompt, parameters, model, tracks, playlist_name, playlist_description, latency_ms, id, ?
+            FROM playlist_generations WHERE user_id = ? AND id = ?
+            RETURNING {}
+            "#,
+            GENERATION_COLUMNS
+        ))
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(Utc::now().to_rfc3339())
+        .bind(user_id)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(PlaylistGeneration::from))
+    }
+
+    /// Returns false if the user has no such entry
+    pub async fn delete(&self, user_id: &str, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM playlist_generations WHERE user_id = ? AND id = ?")
+            .bind(user_id)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -1,22 +1,209 @@
+use crate::models::share::{QrEcLevel, QrOptions};
+use image::{ImageOutputFormat, Rgba, RgbaImage};
 use qrcode::render::svg;
-use qrcode::QrCode;
+use qrcode::{Color, EcLevel, QrCode};
 use std::error::Error;
+use std::io::Cursor;
+
+/// Blank modules the renderers leave around the code, as the QR spec asks for
+const QUIET_ZONE: u32 = 4;
+
+/// Logo diameter as a share of the code's width; small enough for levels Q and H to recover
+const LOGO_FRACTION: f32 = 0.22;
+
+/// The logo's three sound-wave arcs in logo units (badge radius 1, origin at its centre,
+/// y pointing down): each arc is centred on (0, ARC_CENTER_Y) with a radius and half-angle
+const ARC_CENTER_Y: f32 = 1.6;
+const ARCS: [(f32, f32); 3] = [(2.05, 0.3), (1.65, 0.36), (1.25, 0.42)];
+const ARC_WIDTH: f32 = 0.17;
+
+/// Where and how large the logo is drawn, in output pixels
+struct LogoPlacement {
+    center: f32,
+    radius: f32,
+    /// Half the side of the light square that clears the modules behind the badge
+    plate: f32,
+}
 
 pub struct QrService;
 
 impl QrService {
     pub fn generate_playlist_qr(playlist_url: &str) -> Result<String, Box<dyn Error>> {
-        // Create QR code
-        let code = QrCode::new(playlist_url)?;
+        Self::render_svg(playlist_url, &QrOptions::default())
+    }
 
-        // Render as SVG with some styling
-        let svg_xml = code
+    pub fn render_svg(data: &str, options: &QrOptions) -> Result<String, Box<dyn Error>> {
+        let code = encode(data, options.ec_level)?;
+        let (module, total) = module_size(&code, options.size);
+
+        let mut svg_xml = code
             .render::<svg::Color>()
-            .min_dimensions(200, 200)
-            .dark_color(svg::Color("#1DB954")) // Spotify green
-            .light_color(svg::Color("#FFFFFF")) // White background
+            .quiet_zone(true)
+            .module_dimensions(module, module)
+            .dark_color(svg::Color(&hex(options.dark)))
+            .light_color(svg::Color(&hex(options.light)))
             .build();
 
+        if options.logo {
+            let logo = logo_svg(&logo_placement(&code, module, total), options);
+            if let Some(end) = svg_xml.rfind("</svg>") {
+                svg_xml.insert_str(end, &logo);
+            }
+        }
+
         Ok(svg_xml)
     }
+
+    pub fn render_image(data: &str, options: &QrOptions) -> Result<RgbaImage, Box<dyn Error>> {
+        let code = encode(data, options.ec_level)?;
+        let (module, total) = module_size(&code, options.size);
+        let width = code.width() as u32;
+        let colors = code.to_colors();
+
+        let dark = Rgba([options.dark[0], options.dark[1], options.dark[2], 255]);
+        let light = Rgba([options.light[0], options.light[1], options.light[2], 255]);
+
+        let mut image = RgbaImage::from_fn(total, total, |x, y| {
+            let (mx, my) = (x / module, y / module);
+            let inside = (QUIET_ZONE..QUIET_ZONE + width).contains(&mx)
+                && (QUIET_ZONE..QUIET_ZONE + width).contains(&my);
+            if !inside {
+                return light;
+            }
+
+            let index = ((my - QUIET_ZONE) * width + (mx - QUIET_ZONE)) as usize;
+            match colors[index] {
+                Color::Dark => dark,
+                Color::Light => light,
+            }
+        });
+
+        if options.logo {
+            draw_logo(&mut image, &logo_placement(&code, module, total), options);
+        }
+
+        Ok(image)
+    }
+
+    pub fn render_png(data: &str, options: &QrOptions) -> Result<Vec<u8>, Box<dyn Error>> {
+        let image = Self::render_image(data, options)?;
+
+        let mut encoded = Cursor::new(Vec::new());
+        image.write_to(&mut encoded, ImageOutputFormat::Png)?;
+        Ok(encoded.into_inner())
+    }
+}
+
+fn encode(data: &str, ec_level: QrEcLevel) -> Result<QrCode, Box<dyn Error>> {
+    let level = match ec_level {
+        QrEcLevel::L => EcLevel::L,
+        QrEcLevel::M => EcLevel::M,
+        QrEcLevel::Q => EcLevel::Q,
+        QrEcLevel::H => EcLevel::H,
+    };
+    Ok(QrCode::with_error_correction_level(data, level)?)
+}
+
+/// Pixels per module and the resulting side, the smallest whole-module size of at least `size`
+fn module_size(code: &QrCode, size: u32) -> (u32, u32) {
+    let modules = code.width() as u32 + 2 * QUIET_ZONE;
+    let module = size.div_ceil(modules).max(1);
+    (module, module * modules)
+}
+
+fn logo_placement(code: &QrCode, module: u32, total: u32) -> LogoPlacement {
+    let radius = code.width() as f32 * module as f32 * LOGO_FRACTION / 2.0;
+    LogoPlacement {
+        center: total as f32 / 2.0,
+        radius,
+        plate: radius + module as f32,
+    }
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02X}{:02X}{:02X}", r, g, b)
+}
+
+fn logo_svg(placement: &LogoPlacement, options: &QrOptions) -> String {
+    let LogoPlacement {
+        center,
+        radius,
+        plate,
+    } = *placement;
+    let light = hex(options.light);
+
+    // The code itself is drawn with crispEdges, which would leave the badge jagged
+    let mut svg = format!(
+        r#"<rect x="{x:.2}" y="{x:.2}" width="{side:.2}" height="{side:.2}" fill="{light}"/><g shape-rendering="geometricPrecision"><circle cx="{center:.2}" cy="{center:.2}" r="{radius:.2}" fill="{dark}"/>"#,
+        x = center - plate,
+        side = 2.0 * plate,
+        dark = hex(options.dark),
+    );
+
+    for (arc_radius, half_angle) in ARCS {
+        let (sin, cos) = half_angle.sin_cos();
+        let y = center + (ARC_CENTER_Y - arc_radius * cos) * radius;
+        svg.push_str(&format!(
+            r#"<path d="M{x1:.2} {y:.2} A{r:.2} {r:.2} 0 0 1 {x2:.2} {y:.2}" fill="none" stroke="{light}" stroke-width="{w:.2}" stroke-linecap="round"/>"#,
+            x1 = center - arc_radius * sin * radius,
+            x2 = center + arc_radius * sin * radius,
+            r = arc_radius * radius,
+            w = ARC_WIDTH * radius,
+        ));
+    }
+    svg.push_str("</g>");
+
+    svg
+}
+
+/// Whether a point in logo units falls on one of the light arcs, round caps included
+fn on_arc(x: f32, y: f32) -> bool {
+    let half_width = ARC_WIDTH / 2.0;
+    let (dx, dy) = (x, y - ARC_CENTER_Y);
+    let distance = (dx * dx + dy * dy).sqrt();
+    // Angle away from straight up, which is where every arc peaks
+    let angle = dx.atan2(-dy).abs();
+
+    ARCS.iter().any(|&(arc_radius, half_angle)| {
+        if angle <= half_angle {
+            return (distance - arc_radius).abs() <= half_width;
+        }
+        let (sin, cos) = half_angle.sin_cos();
+        let end_x = arc_radius * sin * x.signum();
+        let end_y = ARC_CENTER_Y - arc_radius * cos;
+        (x - end_x).hypot(y - end_y) <= half_width
+    })
+}
+
+/// Paint the plate and badge, 4x supersampled so the edges stay smooth at small sizes
+fn draw_logo(image: &mut RgbaImage, placement: &LogoPlacement, options: &QrOptions) {
+    let LogoPlacement {
+        center,
+        radius,
+        plate,
+    } = *placement;
+    let start = (center - plate).floor().max(0.0) as u32;
+    let end = ((center + plate).ceil() as u32).min(image.width());
+    const SAMPLES: u32 = 4;
+
+    for py in start..end {
+        for px in start..end {
+            let mut dark_samples = 0;
+            for sy in 0..SAMPLES {
+                for sx in 0..SAMPLES {
+                    let x = (px as f32 + (sx as f32 + 0.5) / SAMPLES as f32 - center) / radius;
+                    let y = (py as f32 + (sy as f32 + 0.5) / SAMPLES as f32 - center) / radius;
+                    if x * x + y * y <= 1.0 && !on_arc(x, y) {
+                        dark_samples += 1;
+                    }
+                }
+            }
+
+            let t = dark_samples as f32 / (SAMPLES * SAMPLES) as f32;
+            let blend = |i: usize| {
+                (options.light[i] as f32 * (1.0 - t) + options.dark[i] as f32 * t).round() as u8
+            };
+            image.put_pixel(px, py, Rgba([blend(0), blend(1), blend(2), 255]));
+        }
+    }
 }
@@ -0,0 +1,89 @@
+use chrono::{Duration, Utc};
+use sqlx::SqlitePool;
+use std::error::Error;
+
+/// Default number of AI requests a Spotify user may make per UTC day
+const DEFAULT_DAILY_LIMIT: u32 = 50;
+
+#[derive(Debug, Clone)]
+pub struct QuotaService {
+    pool: SqlitePool,
+    daily_limit: u32,
+}
+
+impl QuotaService {
+    pub fn new(pool: SqlitePool, daily_limit: u32) -> Self {
+        Self { pool, daily_limit }
+    }
+
+    /// Create a QuotaService reading the limit from DAILY_AI_QUOTA
+    pub fn from_env(pool: SqlitePool) -> Self {
+        let daily_limit = std::env::var("DAILY_AI_QUOTA")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_DAILY_LIMIT);
+
+        Self::new(pool, daily_limit)
+    }
+
+    /// The UTC day quotas are currently counted against, as `YYYY-MM-DD`
+    pub fn today() -> String {
+        Utc::now().format("%Y-%m-%d").to_string()
+    }
+
+    /// Count `units` AI requests against the user's quota for `day`, all or nothing.
+    /// Returns `false` without recording anything when they do not fit.
+    pub async fn try_consume(
+        &self,
+        user_id: &str,
+        day: &str,
+        units: u32,
+    ) -> Result<bool, Box<dyn Error>> {
+        if units > self.daily_limit {
+            return Ok(false);
+        }
+
+        let row = sqlx::query(
+            r#"
+            INSERT INTO ai_daily_quota (user_id, day, used) VALUES (?, ?, ?)
+            ON CONFLICT (user_id, day) DO UPDATE SET used = used + excluded.used
+            WHERE used + excluded.used <= ?
+            RETURNING used
+            "#,
+        )
+        .bind(user_id)
+        .bind(day)
+        .bind(units)
+        .bind(self.daily_limit)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.is_some())
+    }
+
+    /// Give back units counted by `try_consume` for a request that failed
+    pub async fn refund(&self, user_id: &str, day: &str, units: u32) -> Result<(), Box<dyn Error>> {
+        sqlx::query(
+            "UPDATE ai_daily_quota SET used = MAX(used - ?, 0) WHERE user_id = ? AND day = ?",
+        )
+        .bind(units)
+        .bind(user_id)
+        .bind(day)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Seconds left until the quota resets at the next UTC midnight
+    pub fn seconds_until_reset() -> u64 {
+        let now = Utc::now();
+        let tomorrow = (now + Duration::days(1))
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .expect("midnight is always a valid time")
+            .and_utc();
+
+        (tomorrow - now).num_seconds().max(1) as u64
+    }
+}
@@ -0,0 +1,271 @@
+use crate::config::SpotifyConfig;
+use crate::models::playlist::{
+    CreatePlaylistRequest, PlaylistSeed, PlaylistWriteMode, SeedTrack, TrackAudioFeatures,
+};
+use futures::TryStreamExt;
+use lazy_static::lazy_static;
+use rspotify::model::{FullPlaylist, PlayableId, PlayableItem, PlaylistId, TrackId, UserId};
+use rspotify::prelude::*;
+use rspotify::{AuthCodeSpotify, ClientCredsSpotify, ClientResult};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+/// Spotify accepts at most this many items per add or replace call
+const MAX_ITEMS_PER_REQUEST: usize = 100;
+
+/// Seed playlists are cut to this many tracks to keep the Gemini prompt small
+const MAX_SEED_TRACKS: usize = 50;
+
+lazy_static! {
+    /// App clients by Spotify client ID. Clones share the token, which rspotify
+    /// fetches again once it expires, so public reads don't request one every time.
+    static ref APP_CLIENTS: Mutex<HashMap<String, ClientCredsSpotify>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Reads public playlists and writes matched tracks to the signed-in user's playlists
+pub struct SpotifyPlaylistService;
+
+impl SpotifyPlaylistService {
+    /// Accept a bare playlist ID, a `spotify:playlist:` URI or an open.spotify.com URL
+    pub fn parse_playlist_id(input: &str) -> Option<PlaylistId<'static>> {
+        let input = input.trim();
+
+        let id = if let Some(id) = input.strip_prefix("spotify:playlist:") {
+            id
+        } else if let Some(rest) = input.split("open.spotify.com/").nth(1) {
+            // Links may carry a locale segment such as /intl-tr/ before /playlist/
+            let path = rest.split(['?', '#']).next()?;
+            let mut segments = path.split('/');
+            segments.find(|segment| *segment == "playlist")?;
+            segments.next()?
+        } else {
+            input
+        };
+
+        if id.is_empty() {
+            return None;
+        }
+
+        PlaylistId::from_id(id.to_string()).ok()
+    }
+
+    /// Create the requested playlist, or load the target one after applying any
+    /// visibility flags the request sets explicitly
+    pub async fn open_playlist(
+        spotify: &AuthCodeSpotify,
+        user_id: UserId<'_>,
+        request: &CreatePlaylistRequest,
+        public_by_default: bool,
+    ) -> ClientResult<FullPlaylist> {
+        let Some(playlist_id) = request
+            .target_playlist
+            .as_deref()
+            .and_then(Self::parse_playlist_id)
+        else {
+            let collaborative = request.collaborative.unwrap_or(false);
+            // Spotify only allows collaborative playlists that are private
+            let public = request
+                .public
+                .unwrap_or(public_by_default && !collaborative);
+
+            return spotify
+                .user_playlist_create(
+                    user_id,
+                    &request.playlist_name,
+                    Some(public),
+                    Some(collaborative),
+                    request.playlist_description.as_deref(),
+                )
+                .await;
+        };
+
+        if request.public.is_some() || request.collaborative.is_some() {
+            spotify
+                .playlist_change_detail(
+                    playlist_id.as_ref(),
+                    None,
+                    request.public,
+                    None,
+                    request.collaborative,
+                )
+                .await?;
+        }
+
+        spotify.playlist(playlist_id, None, None).await
+    }
+
+    /// Write tracks to the playlist according to `mode` and return how many were added
+    pub async fn write_tracks(
+        spotify: &AuthCodeSpotify,
+        playlist_id: PlaylistId<'_>,
+        track_ids: &[TrackId<'static>],
+        mode: PlaylistWriteMode,
+    ) -> ClientResult<usize> {
+        let track_ids = match mode {
+            PlaylistWriteMode::Merge => {
+                let mut seen = Self::existing_track_ids(spotify, playlist_id.as_ref()).await?;
+                track_ids
+                    .iter()
+                    .filter(|id| seen.insert(id.id().to_string()))
+                    .cloned()
+                    .collect()
+            }
+            PlaylistWriteMode::Append | PlaylistWriteMode::Replace => track_ids.to_vec(),
+        };
+
+        let mut chunks = track_ids.chunks(MAX_ITEMS_PER_REQUEST).map(|chunk| {
+            chunk
+                .iter()
+                .map(|id| PlayableId::Track(id.clone()))
+                .collect::<Vec<_>>()
+        });
+
+        if mode == PlaylistWriteMode::Replace {
+            // Replacing with the first chunk clears whatever else was there
+            let first = chunks.next().unwrap_or_default();
+            spotify
+                .playlist_replace_items(playlist_id.as_ref(), first)
+                .await?;
+        }
+
+        for chunk in chunks {
+            spotify
+                .playlist_add_items(playlist_id.as_ref(), chunk, None)
+                .await?;
+        }
+
+        Ok(track_ids.len())
+    }
+
+    /// Load a public playlist with the app's credentials, e.g. to preview a share link
+    pub async fn fetch_playlist(
+        spotify_config: &SpotifyConfig,
+        playlist_id: PlaylistId<'_>,
+    ) -> ClientResult<FullPlaylist> {
+        let spotify = Self::app_client(spotify_config).await?;
+        spotify.playlist(playlist_id, None, None).await
+    }
+
+    /// Load the first tracks of a playlist with their audio features where Spotify
+    /// still provides them. Uses the app's credentials, so only public playlists work.
+    pub async fn fetch_seed(
+        spotify_config: &SpotifyConfig,
+        playlist_id: PlaylistId<'_>,
+    ) -> ClientResult<PlaylistSeed> {
+        let spotify = Self::app_client(spotify_config).await?;
+        let playlist = spotify.playlist(playlist_id, None, None).await?;
+
+        let mut tracks: Vec<SeedTrack> = playlist
+            .tracks
+            .items
+            .into_iter()
+            .filter_map(|item| match item.track {
+                Some(PlayableItem::Track(track)) => {
+                    // Local files have no ID and cannot be looked up
+                    let id = track.id?;
+                    let artists: Vec<&str> = track
+                        .artists
+                        .iter()
+                        .map(|artist| artist.name.as_str())
+                        .collect();
+                    Some(SeedTrack {
+                        url: track
+                            .external_urls
+                            .get("spotify")
+                            .cloned()
+                            .unwrap_or_default(),
+                        spotify_id: id.id().to_string(),
+                        name: track.name,
+                        artist: artists.join(", "),
+                        features: None,
+                    })
+                }
+                _ => None,
+            })
+            .take(MAX_SEED_TRACKS)
+            .collect();
+
+        let track_ids: Vec<TrackId> = tracks
+            .iter()
+            .filter_map(|track| TrackId::from_id(track.spotify_id.as_str()).ok())
+            .collect();
+
+        if !track_ids.is_empty() {
+            match spotify.tracks_features(track_ids).await {
+                Ok(features) => {
+                    let features: HashMap<String, TrackAudioFeatures> = features
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|features| {
+                            (
+                                features.id.id().to_string(),
+                                TrackAudioFeatures {
+                                    tempo: features.tempo,
+                                    energy: features.energy,
+                                    valence: features.valence,
+                                    danceability: features.danceability,
+                                    acousticness: features.acousticness,
+                                    instrumentalness: features.instrumentalness,
+                                },
+                            )
+                        })
+                        .collect();
+
+                    for track in &mut tracks {
+                        track.features = features.get(&track.spotify_id).copied();
+                    }
+                }
+                // Spotify has closed audio features to newer apps; the track list is enough
+                Err(e) => {
+                    tracing::warn!(error = %e, "Audio features unavailable for seed playlist")
+                }
+            }
+        }
+
+        Ok(PlaylistSeed {
+            id: playlist.id.id().to_string(),
+            name: playlist.name,
+            description: playlist.description,
+            url: playlist
+                .external_urls
+                .get("spotify")
+                .cloned()
+                .unwrap_or_default(),
+            tracks,
+        })
+    }
+
+    /// A client authorised as the app rather than a user, which can read public playlists
+    async fn app_client(spotify_config: &SpotifyConfig) -> ClientResult<ClientCredsSpotify> {
+        if let Some(spotify) = APP_CLIENTS.lock().unwrap().get(&spotify_config.client_id) {
+            return Ok(spotify.clone());
+        }
+
+        let spotify = ClientCredsSpotify::new(spotify_config.credentials());
+        spotify.request_token().await?;
+        APP_CLIENTS
+            .lock()
+            .unwrap()
+            .insert(spotify_config.client_id.clone(), spotify.clone());
+        Ok(spotify)
+    }
+
+    async fn existing_track_ids(
+        spotify: &AuthCodeSpotify,
+        playlist_id: PlaylistId<'_>,
+    ) -> ClientResult<HashSet<String>> {
+        let items: Vec<_> = spotify
+            .playlist_items(playlist_id, None, None)
+            .try_collect()
+            .await?;
+
+        Ok(items
+            .into_iter()
+            .filter_map(|item| match item.track {
+                Some(PlayableItem::Track(track)) => track.id.map(|id| id.id().to_string()),
+                _ => None,
+            })
+            .collect())
+    }
+}
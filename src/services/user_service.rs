@@ -0,0 +1,112 @@
+use crate::models::user::{User, UserPreferences};
+use chrono::Utc;
+use rspotify::model::{Id, PrivateUser};
+use sqlx::SqlitePool;
+use std::error::Error;
+
+#[derive(Debug, sqlx::FromRow)]
+struct UserRow {
+    spotify_id: String,
+    display_name: Option<String>,
+    country: Option<String>,
+    product: Option<String>,
+    preferences: String,
+    created_at: String,
+    last_login_at: String,
+}
+
+impl From<UserRow> for User {
+    fn from(row: UserRow) -> Self {
+        User {
+            spotify_id: row.spotify_id,
+            display_name: row.display_name,
+            country: row.country,
+            product: row.product,
+            preferences: serde_json::from_str(&row.preferences).unwrap_or_default(),
+            created_at: row.created_at,
+            last_login_at: row.last_login_at,
+        }
+    }
+}
+
+const USER_COLUMNS: &str =
+    "spotify_id, display_name, country, product, preferences, created_at, last_login_at";
+
+/// Accounts keyed by Spotify user ID, created the first time someone signs in
+#[derive(Debug, Clone)]
+pub struct UserService {
+    pool: SqlitePool,
+}
+
+impl UserService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Create the user on first login, otherwise refresh their Spotify profile fields.
+    /// Preferences are never touched here.
+    pub async fn record_login(&self, profile: &PrivateUser) -> Result<User, Box<dyn Error>> {
+        let now = Utc::now().to_rfc3339();
+        let country = profile.country.map(<&'static str>::from);
+        let product = profile.product.map(<&'static str>::from);
+
+        let row: UserRow = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO users (spotify_id, display_name, country, product, created_at, updated_at, last_login_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (spotify_id) DO UPDATE SET
+                display_name = excluded.display_name,
+                country = excluded.country,
+                product = excluded.product,
+                updated_at = excluded.updated_at,
+                last_login_at = excluded.last_login_at
+            RETURNING {}
+            "#,
+            USER_COLUMNS
+        ))
+        .bind(profile.id.id())
+        .bind(&profile.display_name)
+        .bind(country)
+        .bind(product)
+        .bind(&now)
+        .bind(&now)
+        .bind(&now)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.into())
+    }
+
+    pub async fn get(&self, spotify_id: &str) -> Result<Option<User>, sqlx::Error> {
+        let row: Option<UserRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM users WHERE spotify_id = ?",
+            USER_COLUMNS
+        ))
+        .bind(spotify_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(User::from))
+    }
+
+    /// Merge `update` into the stored preferences; `None` if the user does not exist
+    pub async fn update_preferences(
+        &self,
+        spotify_id: &str,
+        update: UserPreferences,
+    ) -> Result<Option<User>, Box<dyn Error>> {
+        let Some(mut user) = self.get(spotify_id).await? else {
+            return Ok(None);
+        };
+        user.preferences.merge(update);
+
+        sqlx::query("UPDATE users SET preferences = ?, updated_at = ? WHERE spotify_id = ?")
+            .bind(serde_json::to_string(&user.preferences)?)
+            .bind(Utc::now().to_rfc3339())
+            .bind(spotify_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(Some(user))
+    }
+}
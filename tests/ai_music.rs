@@ -0,0 +1,325 @@
+use actix_session::storage::CookieSessionStore;
+use actix_session::SessionMiddleware;
+use actix_web::cookie::Key;
+use actix_web::{test, web, App};
+use spotify_ai_playlist::audio::AudioClip;
+use spotify_ai_playlist::config::Secret;
+use spotify_ai_playlist::db;
+use spotify_ai_playlist::middleware::rate_limit::{RateLimitPolicy, RateLimiter};
+use spotify_ai_playlist::models::api_key::ApiScope;
+use spotify_ai_playlist::models::playlist::{AiMusicBatchResponse, AiMusicResponse};
+use spotify_ai_playlist::services::api_key_service::ApiKeyService;
+use spotify_ai_playlist::services::audio_cache_service::AudioCacheService;
+use spotify_ai_playlist::services::cover_service::CoverService;
+use spotify_ai_playlist::services::gemini_service::GeminiService;
+use spotify_ai_playlist::services::health_service::HealthService;
+use spotify_ai_playlist::services::job_service::JobService;
+use spotify_ai_playlist::services::library_service::LibraryService;
+use spotify_ai_playlist::services::mock_musicgen_service::{MockMusicGenService, FAILURE_MARKER};
+use spotify_ai_playlist::services::music_generator::MusicGenerator;
+use spotify_ai_playlist::services::playlist_history_service::PlaylistHistoryService;
+use spotify_ai_playlist::services::quota_service::QuotaService;
+use spotify_ai_playlist::services::user_service::UserService;
+use spotify_ai_playlist::{configure_app, AppState};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// App state backed by the mock MusicGen backend and a throwaway SQLite file
+async fn mock_state() -> AppState {
+    let dir: PathBuf = std::env::temp_dir().join(format!("melanify-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let database_url = format!("sqlite://{}", dir.join("test.db").display());
+    let pool = db::init_pool(&database_url).await.unwrap();
+
+    let musicgen_service: Arc<dyn MusicGenerator> = Arc::new(MockMusicGenService::new());
+    let gemini_service = GeminiService::new(Secret::new("test"));
+    let audio_cache_service = AudioCacheService::new(
+        pool.clone(),
+        dir.join("cache"),
+        dir.join("processed"),
+        u64::MAX,
+    );
+    let library_service = LibraryService::new(pool.clone(), audio_cache_service.clone());
+    let (job_service, job_receiver) = JobService::new(
+        pool.clone(),
+        audio_cache_service.clone(),
+        library_service.clone(),
+        gemini_service.clone(),
+    );
+    job_service
+        .start_workers(job_receiver, Arc::clone(&musicgen_service))
+        .await
+        .unwrap();
+    let health_service = HealthService::new(
+        pool.clone(),
+        gemini_service.clone(),
+        Arc::clone(&musicgen_service),
+        None,
+        Duration::from_secs(10),
+        Duration::from_secs(1),
+    );
+
+    AppState {
+        pending_tracks: Arc::new(Mutex::new(HashMap::new())),
+        gemini_service,
+        spotify_config: None,
+        musicgen_service,
+        auth_states: Arc::new(Mutex::new(HashMap::new())),
+        quota_service: QuotaService::new(pool.clone(), 50),
+        rate_limiter: RateLimiter::new(HashMap::new(), false),
+        job_service,
+        audio_cache_service,
+        library_service,
+        health_service,
+        api_key_service: ApiKeyService::new(pool.clone()),
+        user_service: UserService::new(pool.clone()),
+        playlist_history_service: PlaylistHistoryService::new(pool.clone()),
+        cover_service: CoverService::new(None),
+        db: pool,
+    }
+}
+
+macro_rules! init_app {
+    () => {
+        init_app!(mock_state().await)
+    };
+    ($state:expr) => {
+        test::init_service(
+            App::new()
+                .wrap(SessionMiddleware::new(
+                    CookieSessionStore::default(),
+                    Key::generate(),
+                ))
+                .app_data(web::Data::new($state))
+                .configure(configure_app),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+async fn generated_song_streams_as_wav_of_requested_duration() {
+    let app = init_app!();
+
+    let request = test::TestRequest::post()
+        .uri("/generate-ai-music")
+        .set_json(serde_json::json!({ "prompt": "calm piano", "duration": 3 }))
+        .to_request();
+    let song: AiMusicResponse = test::call_and_read_body_json(&app, request).await;
+    assert!(song.success);
+    assert_eq!(song.duration, 3);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/ai-music/{}", song.file_id))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert!(response.status().is_success());
+
+    let wav = test::read_body(response).await;
+    let clip = AudioClip::from_wav(&wav).unwrap();
+    assert_eq!(clip.channels, 1);
+    assert_eq!(clip.duration_seconds(), 3.0);
+}
+
+#[actix_web::test]
+async fn batch_reports_failed_prompt_by_index() {
+    let app = init_app!();
+
+    let request = test::TestRequest::post()
+        .uri("/generate-ai-music-batch")
+        .set_json(serde_json::json!({
+            "prompts": [
+                { "title": "Intro", "prompt": "soft strings" },
+                { "title": "Broken", "prompt": format!("noise {}", FAILURE_MARKER) },
+                { "title": "Outro", "prompt": "warm synth pads" }
+            ],
+            "duration": 1
+        }))
+        .to_request();
+    let batch: AiMusicBatchResponse = test::call_and_read_body_json(&app, request).await;
+
+    assert!(batch.success);
+    assert_eq!(batch.count, 2);
+    let indices: Vec<usize> = batch.songs.iter().map(|song| song.index).collect();
+    assert_eq!(indices, vec![0, 2]);
+    assert_eq!(batch.failures.len(), 1);
+    assert_eq!(batch.failures[0].index, 1);
+    assert_eq!(batch.failures[0].title, "Broken");
+}
+
+#[actix_web::test]
+async fn share_routes_are_limited_per_ip_without_quota() {
+    let mut state = mock_state().await;
+    state.rate_limiter = RateLimiter::new(
+        HashMap::from([("share", RateLimitPolicy::per_minute(2))]),
+        false,
+    );
+    let pool = state.db.clone();
+    let app = init_app!(state);
+
+    let qr_request = || {
+        test::TestRequest::get()
+            .uri("/share/37i9dQZF1DXcBWIGoYBM5M/qr")
+            .peer_addr("203.0.113.7:4000".parse().unwrap())
+            .to_request()
+    };
+
+    for _ in 0..2 {
+        let response = test::call_service(&app, qr_request()).await;
+        assert!(response.status().is_success());
+    }
+    let response = test::call_service(&app, qr_request()).await;
+    assert_eq!(response.status().as_u16(), 429);
+    assert!(response.headers().contains_key("Retry-After"));
+
+    let charged: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ai_daily_quota")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(charged, 0);
+}
+
+/// Units of the daily quota `user_id` has used today
+async fn quota_used(pool: &sqlx::SqlitePool, user_id: &str) -> i64 {
+    sqlx::query_scalar("SELECT COALESCE(SUM(used), 0) FROM ai_daily_quota WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[actix_web::test]
+async fn quota_is_charged_per_prompt_and_refunded_on_failure() {
+    let mut state = mock_state().await;
+    state.quota_service = QuotaService::new(state.db.clone(), 3);
+    let pool = state.db.clone();
+    let (_, key) = state
+        .api_key_service
+        .create("listener", "tests", &[ApiScope::MusicGenerate], None)
+        .await
+        .unwrap();
+    let app = init_app!(state);
+
+    let generate = |body: serde_json::Value| {
+        test::TestRequest::post()
+            .uri("/generate-ai-music")
+            .insert_header(("Authorization", format!("Bearer {}", key)))
+            .set_json(body)
+            .to_request()
+    };
+    let batch = |prompts: usize| {
+        let prompts: Vec<serde_json::Value> = (0..prompts)
+            .map(
+                |i| serde_json::json!({ "title": format!("Song {}", i), "prompt": "soft strings" }),
+            )
+            .collect();
+        test::TestRequest::post()
+            .uri("/generate-ai-music-batch")
+            .insert_header(("Authorization", format!("Bearer {}", key)))
+            .set_json(serde_json::json!({ "prompts": prompts, "duration": 1 }))
+            .to_request()
+    };
+
+    let response = test::call_service(
+        &app,
+        generate(serde_json::json!({ "prompt": "calm piano", "duration": 1 })),
+    )
+    .await;
+    assert!(response.status().is_success());
+    assert_eq!(quota_used(&pool, "listener").await, 1);
+
+    // Rejected by validation
+    let response = test::call_service(&app, generate(serde_json::json!({ "prompt": " " }))).await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(quota_used(&pool, "listener").await, 1);
+
+    // MusicGen failed
+    let response = test::call_service(
+        &app,
+        generate(
+            serde_json::json!({ "prompt": format!("noise {}", FAILURE_MARKER), "duration": 1 }),
+        ),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), 500);
+    assert_eq!(quota_used(&pool, "listener").await, 1);
+
+    // Three prompts do not fit in the two units left
+    let response = test::call_service(&app, batch(3)).await;
+    assert_eq!(response.status().as_u16(), 429);
+    assert_eq!(quota_used(&pool, "listener").await, 1);
+
+    let response = test::call_service(&app, batch(2)).await;
+    assert!(response.status().is_success());
+    assert_eq!(quota_used(&pool, "listener").await, 3);
+
+    let response = test::call_service(
+        &app,
+        generate(serde_json::json!({ "prompt": "calm piano", "duration": 1 })),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), 429);
+    assert!(response.headers().contains_key("Retry-After"));
+    assert_eq!(quota_used(&pool, "listener").await, 3);
+}
+
+#[actix_web::test]
+async fn batch_rejects_more_than_the_prompt_limit() {
+    let app = init_app!();
+
+    let prompts: Vec<serde_json::Value> = (0..11)
+        .map(|i| serde_json::json!({ "title": format!("Song {}", i), "prompt": "soft strings" }))
+        .collect();
+    let request = test::TestRequest::post()
+        .uri("/generate-ai-music-batch")
+        .set_json(serde_json::json!({ "prompts": prompts }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[actix_web::test]
+async fn anonymous_jobs_are_only_visible_to_the_session_that_queued_them() {
+    let app = init_app!();
+
+    let request = test::TestRequest::post()
+        .uri("/jobs/ai-music")
+        .set_json(serde_json::json!({ "prompt": "calm piano", "duration": 1 }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status().as_u16(), 202);
+    let cookie = response
+        .response()
+        .cookies()
+        .next()
+        .expect("session cookie")
+        .into_owned();
+    let created: serde_json::Value = test::read_body_json(response).await;
+    let status_url = created["status_url"].as_str().unwrap().to_string();
+
+    let request = test::TestRequest::get()
+        .uri(&status_url)
+        .cookie(cookie.clone())
+        .to_request();
+    assert!(test::call_service(&app, request)
+        .await
+        .status()
+        .is_success());
+
+    let request = test::TestRequest::get().uri(&status_url).to_request();
+    assert_eq!(
+        test::call_service(&app, request).await.status().as_u16(),
+        404
+    );
+
+    let request = test::TestRequest::post()
+        .uri(&format!("{}/retry", status_url))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, request).await.status().as_u16(),
+        404
+    );
+}